@@ -23,6 +23,8 @@ __all__ = [
     "convert_datascrubbing_config",
     "pii_strip_event",
     "pii_selector_suggestions_from_event",
+    "generate_encryption_key_pair",
+    "pii_decrypt_value",
     "VALID_PLATFORMS",
     "validate_sampling_condition",
     "validate_sampling_configuration",
@@ -181,12 +183,22 @@ def convert_datascrubbing_config(config):
     return json.loads(decode_str(raw_rv, free=True))
 
 
-def pii_strip_event(config, event):
+def pii_strip_event(config, event, with_stats=False):
     """
     Scrub an event using new PII stripping config.
+
+    If `with_stats` is set, returns a tuple of the scrubbed event and statistics
+    on the redacted data. The statistics map rule IDs and remark types to the
+    number of modified fields and redacted bytes.
     """
     raw_config = encode_str(json.dumps(config))
     raw_event = encode_str(json.dumps(event))
+
+    if with_stats:
+        raw_rv = rustcall(lib.relay_pii_strip_event_with_stats, raw_config, raw_event)
+        rv = json.loads(decode_str(raw_rv, free=True))
+        return rv["event"], rv["stats"]
+
     raw_rv = rustcall(lib.relay_pii_strip_event, raw_config, raw_event)
     return json.loads(decode_str(raw_rv, free=True))
 
@@ -202,6 +214,27 @@ def pii_selector_suggestions_from_event(event):
     return json.loads(decode_str(raw_rv, free=True))
 
 
+def generate_encryption_key_pair():
+    """
+    Generate a key pair for the `encrypt` redaction method.
+
+    Returns a dictionary with the `secretKey` and `publicKey`. Only the public
+    key belongs into PII configs, the secret key is required to decrypt values.
+    """
+    raw_rv = rustcall(lib.relay_generate_encryption_key_pair)
+    return json.loads(decode_str(raw_rv, free=True))
+
+
+def pii_decrypt_value(secret_key, value):
+    """
+    Decrypt a value that was scrubbed with the `encrypt` redaction method.
+    """
+    raw_rv = rustcall(
+        lib.relay_pii_decrypt_value, encode_str(secret_key), encode_str(value)
+    )
+    return decode_str(raw_rv, free=True)
+
+
 def parse_release(release):
     """Parses a release string into a dictionary of its components."""
     return json.loads(
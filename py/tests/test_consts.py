@@ -4,6 +4,8 @@ from sentry_relay import DataCategory, SPAN_STATUS_CODE_TO_NAME
 def test_parse_data_category():
     assert DataCategory.parse("default") == DataCategory.DEFAULT
     assert DataCategory.parse("transaction") == DataCategory.TRANSACTION
+    assert DataCategory.parse("metric_bucket") == DataCategory.METRIC_BUCKET
+    assert DataCategory.parse("attachment_item") == DataCategory.ATTACHMENT_ITEM
     assert DataCategory.parse("") is None
     assert DataCategory.parse(None) is None
     assert DataCategory.parse("something completely different") is None
@@ -144,6 +144,35 @@ def test_pii_strip_event():
     assert sentry_relay.pii_strip_event({}, event) == event
 
 
+def test_pii_strip_event_with_stats():
+    config = {"applications": {"$string": ["@ip"]}}
+    event = {"logentry": {"formatted": "hi from 127.0.0.1"}}
+    event, stats = sentry_relay.pii_strip_event(config, event, with_stats=True)
+    assert event["logentry"]["formatted"] == "hi from [ip]"
+    assert stats == {"@ip": {"s": {"fields": 1, "bytes": 9}}}
+
+
+def test_pii_encrypt_and_decrypt():
+    key_pair = sentry_relay.generate_encryption_key_pair()
+    config = {
+        "rules": {
+            "encrypt_id": {"type": "anything", "redaction": {"method": "encrypt"}}
+        },
+        "vars": {"encryptionKey": key_pair["publicKey"]},
+        "applications": {"$user.id": ["encrypt_id"]},
+    }
+
+    event = sentry_relay.pii_strip_event(config, {"user": {"id": "user-42"}})
+    encrypted = event["user"]["id"]
+    assert encrypted.startswith("enc:v1:")
+    assert event["_meta"]["user"]["id"][""]["rem"] == [["encrypt_id", "p", 0, 81]]
+
+    assert sentry_relay.pii_decrypt_value(key_pair["secretKey"], encrypted) == "user-42"
+
+    with pytest.raises(sentry_relay.EncryptionErrorBadCiphertext):
+        sentry_relay.pii_decrypt_value(key_pair["secretKey"], "user-42")
+
+
 def test_pii_selector_suggestions_from_event():
     event = {"logentry": {"formatted": "hi"}}
     assert sentry_relay.pii_selector_suggestions_from_event(event) == [
@@ -40,6 +40,14 @@ enum RelayDataCategory {
    * Session updates. Quantity is the number of updates in the batch.
    */
   RELAY_DATA_CATEGORY_SESSION = 5,
+  /**
+   * Pre-aggregated metric buckets. Quantity is the number of buckets.
+   */
+  RELAY_DATA_CATEGORY_METRIC_BUCKET = 6,
+  /**
+   * An attachment item. Quantity is the number of attachments, regardless of their size.
+   */
+  RELAY_DATA_CATEGORY_ATTACHMENT_ITEM = 7,
   /**
    * Any other data category not known by this Relay.
    */
@@ -89,6 +97,9 @@ enum RelayErrorCode {
   RELAY_ERROR_CODE_INVALID_RELEASE_ERROR_TOO_LONG = 3001,
   RELAY_ERROR_CODE_INVALID_RELEASE_ERROR_RESTRICTED_NAME = 3002,
   RELAY_ERROR_CODE_INVALID_RELEASE_ERROR_BAD_CHARACTERS = 3003,
+  RELAY_ERROR_CODE_ENCRYPTION_ERROR_BAD_ENCODING = 4001,
+  RELAY_ERROR_CODE_ENCRYPTION_ERROR_BAD_KEY = 4002,
+  RELAY_ERROR_CODE_ENCRYPTION_ERROR_BAD_CIPHERTEXT = 4003,
 };
 typedef uint32_t RelayErrorCode;
 
@@ -506,12 +517,35 @@ struct RelayStr relay_convert_datascrubbing_config(const struct RelayStr *config
 struct RelayStr relay_pii_strip_event(const struct RelayStr *config,
                                       const struct RelayStr *event);
 
+/**
+ * Scrub an event using new PII stripping config and return statistics on the redacted data.
+ *
+ * Returns an object with the scrubbed `event` and `stats`, which maps rule IDs and remark types to
+ * the number of modified fields and redacted bytes.
+ */
+struct RelayStr relay_pii_strip_event_with_stats(const struct RelayStr *config,
+                                                 const struct RelayStr *event);
+
 /**
  * Walk through the event and collect selectors that can be applied to it in a PII config. This
  * function is used in the UI to provide auto-completion of selectors.
  */
 struct RelayStr relay_pii_selector_suggestions_from_event(const struct RelayStr *event);
 
+/**
+ * Generates a key pair for the `encrypt` redaction method.
+ *
+ * Returns an object with the `secretKey` and `publicKey`. Only the public key belongs into PII
+ * configs, the secret key is required to decrypt values.
+ */
+struct RelayStr relay_generate_encryption_key_pair(void);
+
+/**
+ * Decrypts a value that was scrubbed with the `encrypt` redaction method.
+ */
+struct RelayStr relay_pii_decrypt_value(const struct RelayStr *secret_key,
+                                        const struct RelayStr *value);
+
 /**
  * A test function that always panics.
  */
@@ -2,6 +2,7 @@ use sentry_release_parser::InvalidRelease;
 
 use relay_auth::{KeyParseError, UnpackError};
 use relay_ffi::Panic;
+use relay_general::pii::EncryptionError;
 use relay_general::store::GeoIpError;
 use relay_general::types::ProcessingAction;
 
@@ -35,6 +36,11 @@ pub enum RelayErrorCode {
     InvalidReleaseErrorTooLong = 3001,
     InvalidReleaseErrorRestrictedName = 3002,
     InvalidReleaseErrorBadCharacters = 3003,
+
+    // relay_general::pii::EncryptionError
+    EncryptionErrorBadEncoding = 4001,
+    EncryptionErrorBadKey = 4002,
+    EncryptionErrorBadCiphertext = 4003,
 }
 
 impl RelayErrorCode {
@@ -83,6 +89,13 @@ impl RelayErrorCode {
                     }
                 };
             }
+            if let Some(err) = cause.downcast_ref::<EncryptionError>() {
+                return match err {
+                    EncryptionError::BadEncoding => RelayErrorCode::EncryptionErrorBadEncoding,
+                    EncryptionError::BadKey => RelayErrorCode::EncryptionErrorBadKey,
+                    EncryptionError::BadCiphertext => RelayErrorCode::EncryptionErrorBadCiphertext,
+                };
+            }
         }
         RelayErrorCode::Unknown
     }
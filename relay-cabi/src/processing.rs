@@ -7,14 +7,17 @@ use std::ffi::CStr;
 use std::os::raw::c_char;
 use std::slice;
 
+use serde::Serialize;
+
 use relay_common::{glob_match_bytes, GlobOptions};
 use relay_general::pii::{
-    selector_suggestions_from_value, DataScrubbingConfig, PiiConfig, PiiProcessor,
+    decrypt_value, selector_suggestions_from_value, DataScrubbingConfig, EncryptionKeyPair,
+    PiiConfig, PiiProcessor, PiiStats,
 };
 use relay_general::processor::{process_value, split_chunks, ProcessingState};
 use relay_general::protocol::{Event, VALID_PLATFORMS};
 use relay_general::store::{GeoIpLookup, StoreConfig, StoreProcessor};
-use relay_general::types::{Annotated, Remark};
+use relay_general::types::{Annotated, Remark, SerializableAnnotated};
 use relay_sampling::{RuleCondition, SamplingConfig};
 
 use crate::core::{RelayBuf, RelayStr};
@@ -156,6 +159,38 @@ pub unsafe extern "C" fn relay_pii_strip_event(
     RelayStr::from_string(event.to_json()?)
 }
 
+/// An event scrubbed with a PII config, along with statistics on the redacted data.
+#[derive(Serialize)]
+struct StrippedEvent<'a> {
+    event: SerializableAnnotated<'a, Event>,
+    stats: PiiStats,
+}
+
+/// Scrub an event using new PII stripping config and return statistics on the redacted data.
+///
+/// Returns an object with the scrubbed `event` and `stats`, which maps rule IDs and remark types to
+/// the number of modified fields and redacted bytes.
+#[no_mangle]
+#[relay_ffi::catch_unwind]
+pub unsafe extern "C" fn relay_pii_strip_event_with_stats(
+    config: *const RelayStr,
+    event: *const RelayStr,
+) -> RelayStr {
+    let config = serde_json::from_str::<PiiConfig>((*config).as_str())?;
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled).with_stats();
+
+    let mut event = Annotated::<Event>::from_json((*event).as_str())?;
+    process_value(&mut event, &mut processor, ProcessingState::root())?;
+
+    let stripped = StrippedEvent {
+        event: SerializableAnnotated(&event),
+        stats: processor.into_stats().unwrap_or_default(),
+    };
+
+    RelayStr::from_string(serde_json::to_string(&stripped)?)
+}
+
 /// Walk through the event and collect selectors that can be applied to it in a PII config. This
 /// function is used in the UI to provide auto-completion of selectors.
 #[no_mangle]
@@ -168,6 +203,27 @@ pub unsafe extern "C" fn relay_pii_selector_suggestions_from_event(
     RelayStr::from_string(serde_json::to_string(&rv)?)
 }
 
+/// Generates a key pair for the `encrypt` redaction method.
+///
+/// Returns an object with the `secretKey` and `publicKey`. Only the public key belongs into PII
+/// configs, the secret key is required to decrypt values.
+#[no_mangle]
+#[relay_ffi::catch_unwind]
+pub unsafe extern "C" fn relay_generate_encryption_key_pair() -> RelayStr {
+    RelayStr::from_string(serde_json::to_string(&EncryptionKeyPair::generate())?)
+}
+
+/// Decrypts a value that was scrubbed with the `encrypt` redaction method.
+#[no_mangle]
+#[relay_ffi::catch_unwind]
+pub unsafe extern "C" fn relay_pii_decrypt_value(
+    secret_key: *const RelayStr,
+    value: *const RelayStr,
+) -> RelayStr {
+    let decrypted = decrypt_value((*secret_key).as_str(), (*value).as_str())?;
+    RelayStr::from_string(decrypted)
+}
+
 /// A test function that always panics.
 #[no_mangle]
 #[relay_ffi::catch_unwind]
@@ -107,6 +107,12 @@ pub enum DataCategory {
     Attachment = 4,
     /// Session updates. Quantity is the number of updates in the batch.
     Session = 5,
+    /// Pre-aggregated metric buckets. Quantity is the number of buckets.
+    #[serde(rename = "metric_bucket")]
+    MetricBucket = 6,
+    /// An attachment item. Quantity is the number of attachments, regardless of their size.
+    #[serde(rename = "attachment_item")]
+    AttachmentItem = 7,
     /// Any other data category not known by this Relay.
     #[serde(other)]
     Unknown = -1,
@@ -122,6 +128,8 @@ impl DataCategory {
             "security" => Self::Security,
             "attachment" => Self::Attachment,
             "session" => Self::Session,
+            "metric_bucket" => Self::MetricBucket,
+            "attachment_item" => Self::AttachmentItem,
             _ => Self::Unknown,
         }
     }
@@ -135,6 +143,8 @@ impl DataCategory {
             Self::Security => "security",
             Self::Attachment => "attachment",
             Self::Session => "session",
+            Self::MetricBucket => "metric_bucket",
+            Self::AttachmentItem => "attachment_item",
             Self::Unknown => "unknown",
         }
     }
@@ -476,6 +476,10 @@ struct Limits {
     max_attachment_size: ByteSize,
     /// The maximum combined size for all attachments in an envelope or request.
     max_attachments_size: ByteSize,
+    /// The maximum size of an attachment that is scrubbed for PII.
+    ///
+    /// Larger attachments are forwarded without scrubbing.
+    max_attachment_scrub_size: ByteSize,
     /// The maximum payload size for an entire envelopes. Individual limits still apply.
     max_envelope_size: ByteSize,
     /// The maximum number of session items per envelope.
@@ -504,6 +508,22 @@ struct Limits {
     /// The maximum number of seconds to wait for pending envelopes after receiving a shutdown
     /// signal.
     shutdown_timeout: u64,
+    /// The rate limiter that counts envelope items against project quotas.
+    ///
+    /// If not set, processing Relays with Redis configured use `redis`, and other Relays do not
+    /// enforce quotas on their own.
+    rate_limiter: Option<RateLimiterKind>,
+    /// Whether to include advisory `X-Sentry-Quota-Remaining` headers in accepted responses.
+    ///
+    /// The header is derived from cached rate limits and a snapshot of quota consumption that is
+    /// refreshed in the background, and allows SDKs to back off before their data is dropped.
+    /// Defaults to `false`.
+    quota_headers: bool,
+    /// Whether to serve quota consumption at `GET /api/relay/quotas/{project_key}/`.
+    ///
+    /// The endpoint is not authenticated and reveals the quotas of cached projects, so it should
+    /// only be enabled for Relays that are not exposed publicly. Defaults to `false`.
+    quota_endpoint: bool,
 }
 
 impl Default for Limits {
@@ -514,6 +534,7 @@ impl Default for Limits {
             max_event_size: ByteSize::mebibytes(1),
             max_attachment_size: ByteSize::mebibytes(100),
             max_attachments_size: ByteSize::mebibytes(100),
+            max_attachment_scrub_size: ByteSize::mebibytes(20),
             max_envelope_size: ByteSize::mebibytes(100),
             max_session_count: 100,
             max_api_payload_size: ByteSize::mebibytes(20),
@@ -525,10 +546,27 @@ impl Default for Limits {
             max_pending_connections: 2048,
             max_connections: 25_000,
             shutdown_timeout: 10,
+            rate_limiter: None,
+            quota_headers: false,
+            quota_endpoint: false,
         }
     }
 }
 
+/// The rate limiter that enforces project quotas.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimiterKind {
+    /// Counts quotas in Redis, shared between all Relays connected to the same Redis.
+    ///
+    /// Requires processing to be enabled and Redis to be configured.
+    Redis,
+    /// Counts quotas in the memory of this Relay instance.
+    ///
+    /// Every instance enforces the full quotas on its own, and counts are lost on restart.
+    Memory,
+}
+
 /// Http content encoding for upstream store requests.
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -636,6 +674,10 @@ struct Cache {
     file_interval: u32,
     /// Interval for evicting outdated project configs from memory.
     eviction_interval: u32,
+    /// The maximum number of event fingerprints tracked in memory by the error spikes filter.
+    ///
+    /// This only applies if Relay does not count error spikes in Redis.
+    error_spike_cache_size: usize,
 }
 
 impl Default for Cache {
@@ -651,6 +693,7 @@ impl Default for Cache {
             batch_size: 500,
             file_interval: 10,     // 10 seconds
             eviction_interval: 60, // 60 seconds
+            error_spike_cache_size: 10_000,
         }
     }
 }
@@ -1408,6 +1451,12 @@ impl Config {
         Duration::from_secs(self.values.cache.eviction_interval.into())
     }
 
+    /// Returns the maximum number of event fingerprints tracked in memory by the error spikes
+    /// filter.
+    pub fn error_spike_cache_size(&self) -> usize {
+        self.values.cache.error_spike_cache_size
+    }
+
     /// Returns the maximum size of an event payload in bytes.
     pub fn max_event_size(&self) -> usize {
         self.values.limits.max_event_size.as_bytes()
@@ -1424,6 +1473,11 @@ impl Config {
         self.values.limits.max_attachments_size.as_bytes()
     }
 
+    /// Returns the maximum size of an attachment that is scrubbed for PII in bytes.
+    pub fn max_attachment_scrub_size(&self) -> usize {
+        self.values.limits.max_attachment_scrub_size.as_bytes()
+    }
+
     /// Returns the maximum size of an envelope payload in bytes.
     ///
     /// Individual item size limits still apply.
@@ -1487,6 +1541,23 @@ impl Config {
         Duration::from_secs(self.values.limits.shutdown_timeout)
     }
 
+    /// Returns the configured rate limiter for project quotas.
+    ///
+    /// If not set, processing Relays use Redis if it is configured.
+    pub fn rate_limiter(&self) -> Option<RateLimiterKind> {
+        self.values.limits.rate_limiter
+    }
+
+    /// Returns `true` if accepted responses should include advisory quota headers.
+    pub fn quota_headers(&self) -> bool {
+        self.values.limits.quota_headers
+    }
+
+    /// Returns `true` if the quota usage endpoint is enabled.
+    pub fn quota_endpoint(&self) -> bool {
+        self.values.limits.quota_endpoint
+    }
+
     /// Returns the number of cores to use for thread pools.
     pub fn cpu_concurrency(&self) -> usize {
         self.values.limits.max_thread_count
@@ -163,7 +163,13 @@ mod tests {
         ];
 
         for event in &events {
-            let filter_result = should_filter(event, &FilterConfig { is_enabled: false });
+            let filter_result = should_filter(
+                event,
+                &FilterConfig {
+                    is_enabled: false,
+                    ..FilterConfig::default()
+                },
+            );
             assert_eq!(
                 filter_result,
                 Ok(()),
@@ -192,7 +198,13 @@ mod tests {
 
         for source_name in &sources {
             let event = get_event_with_exception_source(source_name);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &FilterConfig {
+                    is_enabled: true,
+                    ..FilterConfig::default()
+                },
+            );
 
             assert_ne!(
                 filter_result,
@@ -230,7 +242,13 @@ mod tests {
 
         for exc_value in &exceptions {
             let event = get_event_with_exception_value(exc_value);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &FilterConfig {
+                    is_enabled: true,
+                    ..FilterConfig::default()
+                },
+            );
             assert_ne!(
                 filter_result,
                 Ok(()),
@@ -248,7 +266,13 @@ mod tests {
         ];
 
         for event in &events {
-            let filter_result = should_filter(event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                event,
+                &FilterConfig {
+                    is_enabled: true,
+                    ..FilterConfig::default()
+                },
+            );
             assert_eq!(
                 filter_result,
                 Ok(()),
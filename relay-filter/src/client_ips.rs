@@ -104,6 +104,7 @@ mod tests {
             let ip_addr = ip_addr.parse::<IpAddr>().ok();
             let config = ClientIpsFilterConfig {
                 blacklisted_ips: blacklisted_ips.iter().map(|&ip| ip.to_string()).collect(),
+                ..ClientIpsFilterConfig::default()
             };
 
             let actual = should_filter(ip_addr, &config) != Ok(());
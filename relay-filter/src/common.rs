@@ -126,6 +126,12 @@ pub enum FilterStatKey {
 
     /// Filtered due to invalid CSP policy.
     InvalidCsp,
+
+    /// Filtered by exception type, module, mechanism or top in-app frame.
+    Exceptions,
+
+    /// Filtered as duplicate during an error spike.
+    ErrorSpike,
 }
 
 // An event grouped to a removed group.
@@ -152,6 +158,8 @@ impl FilterStatKey {
             FilterStatKey::Localhost => "localhost",
             FilterStatKey::WebCrawlers => "web-crawlers",
             FilterStatKey::InvalidCsp => "invalid-csp",
+            FilterStatKey::Exceptions => "exceptions",
+            FilterStatKey::ErrorSpike => "error-spike",
         }
     }
 }
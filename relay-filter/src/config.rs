@@ -9,12 +9,58 @@ use serde::{Deserialize, Serialize};
 
 use crate::common::GlobPatterns;
 
+/// Returns `true` if the given flag is not set.
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Restricts an event filter to a set of environments.
+///
+/// Both lists contain glob patterns matched against the event's environment. If no lists are
+/// given, the filter applies to all events.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterEnvironments {
+    /// If not empty, the filter only applies to events in one of these environments.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub include: GlobPatterns,
+    /// The filter never applies to events in one of these environments.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub exclude: GlobPatterns,
+}
+
+impl FilterEnvironments {
+    /// Returns true if the filter applies to all environments.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Returns `true` if the filter applies to events in the given environment.
+    ///
+    /// Events without an environment are only excluded if there is a list of included
+    /// environments.
+    pub fn applies_to(&self, environment: Option<&str>) -> bool {
+        match environment {
+            Some(environment) if self.exclude.is_match(environment) => false,
+            Some(environment) if !self.include.is_empty() => self.include.is_match(environment),
+            Some(_) => true,
+            None => self.include.is_empty(),
+        }
+    }
+}
+
 /// Common configuration for event filters.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FilterConfig {
     /// Specifies whether this filter is enabled.
     pub is_enabled: bool,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
 }
 
 impl FilterConfig {
@@ -105,6 +151,12 @@ impl Serialize for LegacyBrowser {
 pub struct ClientIpsFilterConfig {
     /// Blacklisted client ip addresses.
     pub blacklisted_ips: Vec<String>,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
 }
 
 impl ClientIpsFilterConfig {
@@ -120,6 +172,12 @@ impl ClientIpsFilterConfig {
 pub struct CspFilterConfig {
     /// Disallowed sources for CSP reports.
     pub disallowed_sources: Vec<String>,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
 }
 
 impl CspFilterConfig {
@@ -131,9 +189,16 @@ impl CspFilterConfig {
 
 /// Configuration for the error messages filter.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ErrorMessagesFilterConfig {
     /// List of error message patterns that will be filtered.
     pub patterns: GlobPatterns,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
 }
 
 impl ErrorMessagesFilterConfig {
@@ -143,11 +208,102 @@ impl ErrorMessagesFilterConfig {
     }
 }
 
+/// A rule of the exceptions filter.
+///
+/// All non-empty lists of patterns in a rule must match the same exception for the rule to apply.
+/// Empty lists act as wildcards. A rule without any patterns never matches.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceptionFilterRule {
+    /// Patterns matched against the exception type.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub types: GlobPatterns,
+    /// Patterns matched against the exception module.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub modules: GlobPatterns,
+    /// Patterns matched against the type of the exception mechanism.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub mechanisms: GlobPatterns,
+    /// Patterns matched against the module of the top in-app frame.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub frame_modules: GlobPatterns,
+    /// Patterns matched against the function of the top in-app frame.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub frame_functions: GlobPatterns,
+}
+
+impl ExceptionFilterRule {
+    /// Returns true if no patterns are declared in this rule.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+            && self.modules.is_empty()
+            && self.mechanisms.is_empty()
+            && self.frame_modules.is_empty()
+            && self.frame_functions.is_empty()
+    }
+}
+
+/// Configuration for the exceptions filter.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceptionsFilterConfig {
+    /// List of rules matched against the exceptions of an event.
+    #[serde(default)]
+    pub rules: Vec<ExceptionFilterRule>,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
+}
+
+impl ExceptionsFilterConfig {
+    /// Returns true if no configuration for this filter is given.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Configuration for the error spikes filter.
+///
+/// This filter is stateful and therefore not applied by [`should_filter`](crate::should_filter).
+/// Instead, the caller counts events by their [fingerprint](crate::error_spikes::fingerprint).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorSpikesFilterConfig {
+    /// The maximum number of events with the same fingerprint accepted per minute.
+    ///
+    /// If not set, the filter is disabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_minute: Option<u64>,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
+}
+
+impl ErrorSpikesFilterConfig {
+    /// Returns true if no configuration for this filter is given.
+    pub fn is_empty(&self) -> bool {
+        self.max_per_minute.is_none()
+    }
+}
+
 /// Configuration for the releases filter.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ReleasesFilterConfig {
     /// List of release names that will be filtered.
     pub releases: GlobPatterns,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
 }
 
 impl ReleasesFilterConfig {
@@ -166,12 +322,52 @@ pub struct LegacyBrowsersFilterConfig {
     /// The browsers to filter.
     #[serde(default, rename = "options")]
     pub browsers: BTreeSet<LegacyBrowser>,
+    /// Additional user agent patterns that are considered legacy browsers.
+    ///
+    /// These patterns apply regardless of `is_enabled`.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub user_agents: GlobPatterns,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
 }
 
 impl LegacyBrowsersFilterConfig {
     /// Returns true if no configuration for this filter is given.
     pub fn is_empty(&self) -> bool {
-        !self.is_enabled && self.browsers.is_empty()
+        !self.is_enabled && self.browsers.is_empty() && self.user_agents.is_empty()
+    }
+}
+
+/// Configuration for the web crawlers filter.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebCrawlersFilterConfig {
+    /// Specifies whether the built-in list of web crawlers is filtered.
+    pub is_enabled: bool,
+    /// Additional user agent patterns of web crawlers, such as in-house synthetic monitors.
+    ///
+    /// These patterns apply regardless of `is_enabled`.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub user_agents: GlobPatterns,
+    /// User agent patterns that are exempt from the built-in list of web crawlers.
+    #[serde(default, skip_serializing_if = "GlobPatterns::is_empty")]
+    pub allowed_user_agents: GlobPatterns,
+    /// Restricts the filter to a set of environments.
+    #[serde(default, skip_serializing_if = "FilterEnvironments::is_empty")]
+    pub environments: FilterEnvironments,
+    /// Evaluates the filter without dropping matching events.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
+}
+
+impl WebCrawlersFilterConfig {
+    /// Returns true if no configuration for this filter is given.
+    pub fn is_empty(&self) -> bool {
+        !self.is_enabled && self.user_agents.is_empty() && self.allowed_user_agents.is_empty()
     }
 }
 
@@ -188,8 +384,8 @@ pub struct FiltersConfig {
     pub client_ips: ClientIpsFilterConfig,
 
     /// Configuration for the Web Crawlers filter
-    #[serde(default, skip_serializing_if = "FilterConfig::is_empty")]
-    pub web_crawlers: FilterConfig,
+    #[serde(default, skip_serializing_if = "WebCrawlersFilterConfig::is_empty")]
+    pub web_crawlers: WebCrawlersFilterConfig,
 
     /// Configuration for the CSP filter.
     #[serde(default, skip_serializing_if = "CspFilterConfig::is_empty")]
@@ -199,6 +395,14 @@ pub struct FiltersConfig {
     #[serde(default, skip_serializing_if = "ErrorMessagesFilterConfig::is_empty")]
     pub error_messages: ErrorMessagesFilterConfig,
 
+    /// Configuration for the Error Spikes filter.
+    #[serde(default, skip_serializing_if = "ErrorSpikesFilterConfig::is_empty")]
+    pub error_spikes: ErrorSpikesFilterConfig,
+
+    /// Configuration for the Exceptions filter.
+    #[serde(default, skip_serializing_if = "ExceptionsFilterConfig::is_empty")]
+    pub exceptions: ExceptionsFilterConfig,
+
     /// Configuration for the Legacy Browsers filter.
     #[serde(default, skip_serializing_if = "LegacyBrowsersFilterConfig::is_empty")]
     pub legacy_browsers: LegacyBrowsersFilterConfig,
@@ -220,6 +424,8 @@ impl FiltersConfig {
             && self.web_crawlers.is_empty()
             && self.csp.is_empty()
             && self.error_messages.is_empty()
+            && self.error_spikes.is_empty()
+            && self.exceptions.is_empty()
             && self.legacy_browsers.is_empty()
             && self.localhost.is_empty()
             && self.releases.is_empty()
@@ -237,28 +443,87 @@ mod tests {
         FiltersConfig {
             browser_extensions: FilterConfig {
                 is_enabled: false,
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
             client_ips: ClientIpsFilterConfig {
                 blacklisted_ips: [],
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
-            web_crawlers: FilterConfig {
+            web_crawlers: WebCrawlersFilterConfig {
                 is_enabled: false,
+                user_agents: [],
+                allowed_user_agents: [],
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
             csp: CspFilterConfig {
                 disallowed_sources: [],
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
             error_messages: ErrorMessagesFilterConfig {
                 patterns: [],
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
+            },
+            error_spikes: ErrorSpikesFilterConfig {
+                max_per_minute: None,
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
+            },
+            exceptions: ExceptionsFilterConfig {
+                rules: [],
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
             legacy_browsers: LegacyBrowsersFilterConfig {
                 is_enabled: false,
                 browsers: {},
+                user_agents: [],
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
             localhost: FilterConfig {
                 is_enabled: false,
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
             releases: ReleasesFilterConfig {
                 releases: [],
+                environments: FilterEnvironments {
+                    include: [],
+                    exclude: [],
+                },
+                dry_run: false,
             },
         }
         "###);
@@ -274,24 +539,66 @@ mod tests {
     #[test]
     fn test_serialize_full() {
         let filters_config = FiltersConfig {
-            browser_extensions: FilterConfig { is_enabled: true },
+            browser_extensions: FilterConfig {
+                is_enabled: true,
+                environments: FilterEnvironments::default(),
+                dry_run: false,
+            },
             client_ips: ClientIpsFilterConfig {
                 blacklisted_ips: vec!["127.0.0.1".to_string()],
+                environments: FilterEnvironments::default(),
+                dry_run: false,
+            },
+            web_crawlers: WebCrawlersFilterConfig {
+                is_enabled: true,
+                user_agents: GlobPatterns::new(vec!["*SyntheticMonitor*".to_string()]),
+                allowed_user_agents: GlobPatterns::new(vec!["Slack*".to_string()]),
+                environments: FilterEnvironments::default(),
+                dry_run: true,
             },
-            web_crawlers: FilterConfig { is_enabled: true },
             csp: CspFilterConfig {
                 disallowed_sources: vec!["https://*".to_string()],
+                environments: FilterEnvironments::default(),
+                dry_run: false,
             },
             error_messages: ErrorMessagesFilterConfig {
                 patterns: GlobPatterns::new(vec!["Panic".to_string()]),
+                environments: FilterEnvironments::default(),
+                dry_run: true,
+            },
+            error_spikes: ErrorSpikesFilterConfig {
+                max_per_minute: Some(1000),
+                environments: FilterEnvironments::default(),
+                dry_run: false,
+            },
+            exceptions: ExceptionsFilterConfig {
+                rules: vec![ExceptionFilterRule {
+                    types: GlobPatterns::new(vec!["ChunkLoadError".to_string()]),
+                    frame_modules: GlobPatterns::new(vec!["*.bundle".to_string()]),
+                    ..ExceptionFilterRule::default()
+                }],
+                environments: FilterEnvironments::default(),
+                dry_run: false,
             },
             legacy_browsers: LegacyBrowsersFilterConfig {
                 is_enabled: false,
                 browsers: [LegacyBrowser::Ie9].iter().cloned().collect(),
+                user_agents: GlobPatterns::new(vec!["*MSIE 7.0*".to_string()]),
+                environments: FilterEnvironments::default(),
+                dry_run: false,
+            },
+            localhost: FilterConfig {
+                is_enabled: true,
+                environments: FilterEnvironments {
+                    include: GlobPatterns::new(vec!["production".to_string()]),
+                    exclude: GlobPatterns::default(),
+                },
+                dry_run: false,
             },
-            localhost: FilterConfig { is_enabled: true },
             releases: ReleasesFilterConfig {
                 releases: GlobPatterns::new(vec!["1.2.3".to_string()]),
+                environments: FilterEnvironments::default(),
+                dry_run: false,
             },
         };
 
@@ -306,7 +613,14 @@ mod tests {
             ]
           },
           "webCrawlers": {
-            "isEnabled": true
+            "isEnabled": true,
+            "userAgents": [
+              "*SyntheticMonitor*"
+            ],
+            "allowedUserAgents": [
+              "Slack*"
+            ],
+            "dryRun": true
           },
           "csp": {
             "disallowedSources": [
@@ -316,16 +630,40 @@ mod tests {
           "errorMessages": {
             "patterns": [
               "Panic"
+            ],
+            "dryRun": true
+          },
+          "errorSpikes": {
+            "maxPerMinute": 1000
+          },
+          "exceptions": {
+            "rules": [
+              {
+                "types": [
+                  "ChunkLoadError"
+                ],
+                "frameModules": [
+                  "*.bundle"
+                ]
+              }
             ]
           },
           "legacyBrowsers": {
             "isEnabled": false,
             "options": [
               "ie9"
+            ],
+            "userAgents": [
+              "*MSIE 7.0*"
             ]
           },
           "localhost": {
-            "isEnabled": true
+            "isEnabled": true,
+            "environments": {
+              "include": [
+                "production"
+              ]
+            }
           },
           "releases": {
             "releases": [
@@ -344,7 +682,46 @@ mod tests {
         LegacyBrowsersFilterConfig {
             is_enabled: false,
             browsers: {},
+            user_agents: [],
+            environments: FilterEnvironments {
+                include: [],
+                exclude: [],
+            },
+            dry_run: false,
         }
         "###);
     }
+
+    #[test]
+    fn test_deserialize_dry_run() {
+        let json = r#"{"isEnabled":true,"dryRun":true}"#;
+        let config = serde_json::from_str::<FilterConfig>(json).unwrap();
+        assert!(config.is_enabled);
+        assert!(config.dry_run);
+    }
+
+    #[test]
+    fn test_environments_applies_to() {
+        let environments = FilterEnvironments {
+            include: GlobPatterns::new(vec!["prod*".to_string()]),
+            exclude: GlobPatterns::new(vec!["production-eu".to_string()]),
+        };
+
+        assert!(environments.applies_to(Some("production")));
+        assert!(!environments.applies_to(Some("production-eu")));
+        assert!(!environments.applies_to(Some("staging")));
+        assert!(!environments.applies_to(None));
+    }
+
+    #[test]
+    fn test_environments_exclude_only() {
+        let environments = FilterEnvironments {
+            include: GlobPatterns::default(),
+            exclude: GlobPatterns::new(vec!["staging".to_string()]),
+        };
+
+        assert!(environments.applies_to(Some("production")));
+        assert!(!environments.applies_to(Some("staging")));
+        assert!(environments.applies_to(None));
+    }
 }
@@ -302,6 +302,7 @@ mod tests {
         let event = get_csp_event(None, Some("http://known.bad.com"));
         let config = CspFilterConfig {
             disallowed_sources: vec!["http://known.bad.com".to_string()],
+            ..CspFilterConfig::default()
         };
 
         let actual = should_filter(&event, &config);
@@ -317,6 +318,7 @@ mod tests {
         let event = get_csp_event(None, Some("http://good.file.com"));
         let config = CspFilterConfig {
             disallowed_sources: vec!["http://known.bad.com".to_string()],
+            ..CspFilterConfig::default()
         };
 
         let actual = should_filter(&event, &config);
@@ -332,6 +334,7 @@ mod tests {
         let event = get_csp_event(Some("http://known.bad.com"), None);
         let config = CspFilterConfig {
             disallowed_sources: vec!["http://known.bad.com".to_string()],
+            ..CspFilterConfig::default()
         };
 
         let actual = should_filter(&event, &config);
@@ -347,6 +350,7 @@ mod tests {
         let event = get_csp_event(Some("http://good.file.com"), None);
         let config = CspFilterConfig {
             disallowed_sources: vec!["http://known.bad.com".to_string()],
+            ..CspFilterConfig::default()
         };
 
         let actual = should_filter(&event, &config);
@@ -363,6 +367,7 @@ mod tests {
         event.ty = Annotated::from(EventType::Transaction);
         let config = CspFilterConfig {
             disallowed_sources: vec!["http://known.bad.com".to_string()],
+            ..CspFilterConfig::default()
         };
 
         let actual = should_filter(&event, &config);
@@ -403,6 +408,7 @@ mod tests {
             let event = get_csp_event(*blocked_uri, *source_file);
             let config = CspFilterConfig {
                 disallowed_sources: get_disallowed_sources(),
+                ..CspFilterConfig::default()
             };
 
             let actual = should_filter(&event, &config);
@@ -428,6 +434,7 @@ mod tests {
             let event = get_csp_event(*blocked_uri, *source_file);
             let config = CspFilterConfig {
                 disallowed_sources: get_disallowed_sources(),
+                ..CspFilterConfig::default()
             };
 
             let actual = should_filter(&event, &config);
@@ -76,6 +76,7 @@ mod tests {
                     "".to_string(),
                     "this is".to_string(),
                 ]),
+                ..ErrorMessagesFilterConfig::default()
             },
             // without globs
             ErrorMessagesFilterConfig {
@@ -84,6 +85,7 @@ mod tests {
                     "filteredexception".to_string(),
                     "this is a filtered exception.".to_string(),
                 ]),
+                ..ErrorMessagesFilterConfig::default()
             },
         ];
 
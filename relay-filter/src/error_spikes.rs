@@ -0,0 +1,140 @@
+//! Implements fingerprinting for the error spikes filter.
+//!
+//! Unlike the other filters, this filter is stateful: It drops events once more than a configured
+//! number of events with the same fingerprint have been seen within a minute. This crate only
+//! computes the fingerprint, counting is left to the caller.
+
+use relay_general::protocol::{Event, EventType};
+
+/// Returns the fingerprint of an error event for spike deduplication.
+///
+/// The fingerprint is composed of the log message, the type of the primary exception and the
+/// location of its top frame. Returns `None` for transactions and for events that carry neither a
+/// message nor an exception.
+pub fn fingerprint(event: &Event) -> Option<String> {
+    if event.ty.value() == Some(&EventType::Transaction) {
+        return None;
+    }
+
+    let message = event.logentry.value().and_then(|logentry| {
+        logentry
+            .formatted
+            .as_str()
+            .or_else(|| logentry.message.as_str())
+    });
+
+    // The primary exception is the last one in the list.
+    let exception = event
+        .exceptions
+        .value()
+        .and_then(|values| values.values.value())
+        .and_then(|exceptions| exceptions.last())
+        .and_then(|exception| exception.value());
+
+    let exception_type = exception.and_then(|exception| exception.ty.as_str());
+
+    // The top frame is the last one in the stack trace.
+    let frame = exception
+        .and_then(|exception| exception.stacktrace.value())
+        .and_then(|stacktrace| stacktrace.frames.value())
+        .and_then(|frames| frames.last())
+        .and_then(|frame| frame.value());
+
+    if message.is_none() && exception_type.is_none() && frame.is_none() {
+        return None;
+    }
+
+    let (module, function, lineno) = match frame {
+        Some(frame) => (
+            frame
+                .module
+                .as_str()
+                .or_else(|| frame.filename.value().map(|path| path.as_str())),
+            frame.function.as_str(),
+            frame.lineno.value().copied(),
+        ),
+        None => (None, None, None),
+    };
+
+    Some(format!(
+        "{}\n{}\n{}\n{}\n{}",
+        message.unwrap_or_default(),
+        exception_type.unwrap_or_default(),
+        module.unwrap_or_default(),
+        function.unwrap_or_default(),
+        lineno.map(|lineno| lineno.to_string()).unwrap_or_default(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use relay_general::protocol::{Exception, Frame, LogEntry, RawStacktrace, Stacktrace, Values};
+    use relay_general::types::Annotated;
+
+    fn get_event(ty: &str, function: &str) -> Event {
+        let exception = Exception {
+            ty: Annotated::new(ty.to_string()),
+            value: Annotated::new("some value".to_string().into()),
+            stacktrace: Annotated::new(Stacktrace(RawStacktrace {
+                frames: Annotated::new(vec![Annotated::new(Frame {
+                    module: Annotated::new("app.views".to_string()),
+                    function: Annotated::new(function.to_string()),
+                    lineno: Annotated::new(42),
+                    ..Frame::default()
+                })]),
+                ..RawStacktrace::default()
+            })),
+            ..Exception::default()
+        };
+
+        Event {
+            exceptions: Annotated::new(Values::new(vec![Annotated::new(exception)])),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_exception() {
+        let fingerprint = fingerprint(&get_event("ValueError", "index"));
+        assert_eq!(
+            fingerprint.as_deref(),
+            Some("\nValueError\napp.views\nindex\n42")
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_differs_by_frame() {
+        assert_ne!(
+            fingerprint(&get_event("ValueError", "index")),
+            fingerprint(&get_event("ValueError", "detail"))
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_message() {
+        let event = Event {
+            logentry: Annotated::new(LogEntry {
+                formatted: Annotated::new("Connection refused".to_string().into()),
+                ..LogEntry::default()
+            }),
+            ..Event::default()
+        };
+
+        let fingerprint = fingerprint(&event);
+        assert_eq!(fingerprint.as_deref(), Some("Connection refused\n\n\n\n"));
+    }
+
+    #[test]
+    fn test_no_fingerprint_for_transactions() {
+        let mut event = get_event("ValueError", "index");
+        event.ty = Annotated::new(EventType::Transaction);
+        assert_eq!(fingerprint(&event), None);
+    }
+
+    #[test]
+    fn test_no_fingerprint_for_empty_event() {
+        assert_eq!(fingerprint(&Event::default()), None);
+    }
+}
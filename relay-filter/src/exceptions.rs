@@ -0,0 +1,196 @@
+//! Implements event filtering based on structured exception data.
+//!
+//! In contrast to the error messages filter, which matches on the formatted exception message,
+//! this filter matches on individual attributes of exceptions, their mechanism and the top in-app
+//! frame of their stack trace.
+
+use relay_general::protocol::{Event, Exception, Frame};
+
+use crate::{ExceptionFilterRule, ExceptionsFilterConfig, FilterStatKey, GlobPatterns};
+
+/// Returns `true` if the patterns are empty or match the given value.
+///
+/// An empty list of patterns acts as wildcard. If there are patterns but no value, the patterns do
+/// not match.
+fn matches_optional(patterns: &GlobPatterns, value: Option<&str>) -> bool {
+    patterns.is_empty() || value.map_or(false, |value| patterns.is_match(value))
+}
+
+/// Returns the most recent in-app frame of the exception's stack trace.
+fn get_top_in_app_frame(exception: &Exception) -> Option<&Frame> {
+    let frames = exception.stacktrace.value()?.frames.value()?;
+    frames
+        .iter()
+        .rev()
+        .filter_map(|frame| frame.value())
+        .find(|frame| frame.in_app.value().copied().unwrap_or(false))
+}
+
+/// Checks if a single exception matches all attributes of the given rule.
+fn matches_exception(exception: &Exception, rule: &ExceptionFilterRule) -> bool {
+    if rule.is_empty() {
+        return false;
+    }
+
+    let mechanism = exception.mechanism.value();
+    let frame = get_top_in_app_frame(exception);
+
+    matches_optional(&rule.types, exception.ty.as_str())
+        && matches_optional(&rule.modules, exception.module.as_str())
+        && matches_optional(&rule.mechanisms, mechanism.and_then(|m| m.ty.as_str()))
+        && matches_optional(&rule.frame_modules, frame.and_then(|f| f.module.as_str()))
+        && matches_optional(
+            &rule.frame_functions,
+            frame.and_then(|f| f.function.as_str()),
+        )
+}
+
+/// Checks if any of the event's exceptions matches any of the given rules.
+pub fn matches(event: &Event, rules: &[ExceptionFilterRule]) -> bool {
+    let exceptions = match event.exceptions.value().and_then(|v| v.values.value()) {
+        Some(exceptions) => exceptions,
+        None => return false,
+    };
+
+    exceptions
+        .iter()
+        .filter_map(|exception| exception.value())
+        .any(|exception| rules.iter().any(|rule| matches_exception(exception, rule)))
+}
+
+/// Filters events by attributes of their exceptions.
+pub fn should_filter(event: &Event, config: &ExceptionsFilterConfig) -> Result<(), FilterStatKey> {
+    if matches(event, &config.rules) {
+        Err(FilterStatKey::Exceptions)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use relay_general::protocol::{Mechanism, RawStacktrace, Stacktrace, Values};
+    use relay_general::types::Annotated;
+
+    macro_rules! globs {
+        ($($pattern:literal),*) => {
+            GlobPatterns::new(vec![
+                $($pattern.to_string()),*
+            ])
+        };
+    }
+
+    fn get_frame(module: &str, function: &str, in_app: bool) -> Annotated<Frame> {
+        Annotated::new(Frame {
+            module: Annotated::new(module.to_string()),
+            function: Annotated::new(function.to_string()),
+            in_app: Annotated::new(in_app),
+            ..Frame::default()
+        })
+    }
+
+    fn get_event() -> Event {
+        let exception = Exception {
+            ty: Annotated::new("ChunkLoadError".to_string()),
+            module: Annotated::new("webpack".to_string()),
+            mechanism: Annotated::new(Mechanism {
+                ty: Annotated::new("onunhandledrejection".to_string()),
+                ..Mechanism::default()
+            }),
+            stacktrace: Annotated::new(Stacktrace(RawStacktrace {
+                frames: Annotated::new(vec![
+                    get_frame("app/checkout", "loadCart", true),
+                    get_frame("app/vendor.bundle", "requireEnsure", true),
+                    get_frame("webpack/runtime", "jsonpScriptSrc", false),
+                ]),
+                ..RawStacktrace::default()
+            })),
+            ..Exception::default()
+        };
+
+        Event {
+            exceptions: Annotated::new(Values::new(vec![Annotated::new(exception)])),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_exception_type() {
+        let config = ExceptionsFilterConfig {
+            rules: vec![ExceptionFilterRule {
+                types: globs!("ChunkLoadError"),
+                ..ExceptionFilterRule::default()
+            }],
+            ..ExceptionsFilterConfig::default()
+        };
+
+        let result = should_filter(&get_event(), &config);
+        assert_eq!(result, Err(FilterStatKey::Exceptions));
+    }
+
+    #[test]
+    fn test_filter_combined_attributes() {
+        let config = ExceptionsFilterConfig {
+            rules: vec![ExceptionFilterRule {
+                types: globs!("ChunkLoadError"),
+                mechanisms: globs!("onunhandled*"),
+                frame_modules: globs!("*.bundle"),
+                frame_functions: globs!("require*"),
+                ..ExceptionFilterRule::default()
+            }],
+            ..ExceptionsFilterConfig::default()
+        };
+
+        let result = should_filter(&get_event(), &config);
+        assert_eq!(result, Err(FilterStatKey::Exceptions));
+    }
+
+    #[test]
+    fn test_dont_filter_partial_match() {
+        let config = ExceptionsFilterConfig {
+            rules: vec![ExceptionFilterRule {
+                types: globs!("ChunkLoadError"),
+                frame_modules: globs!("app/checkout"),
+                ..ExceptionFilterRule::default()
+            }],
+            ..ExceptionsFilterConfig::default()
+        };
+
+        // The top in-app frame is `app/vendor.bundle`, not `app/checkout`.
+        let result = should_filter(&get_event(), &config);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_dont_filter_missing_attribute() {
+        let config = ExceptionsFilterConfig {
+            rules: vec![ExceptionFilterRule {
+                modules: globs!("*"),
+                ..ExceptionFilterRule::default()
+            }],
+            ..ExceptionsFilterConfig::default()
+        };
+
+        let event = Event {
+            exceptions: Annotated::new(Values::new(vec![Annotated::new(Exception {
+                ty: Annotated::new("ChunkLoadError".to_string()),
+                ..Exception::default()
+            })])),
+            ..Event::default()
+        };
+
+        assert_eq!(should_filter(&event, &config), Ok(()));
+    }
+
+    #[test]
+    fn test_dont_filter_empty_rule() {
+        let config = ExceptionsFilterConfig {
+            rules: vec![ExceptionFilterRule::default()],
+            ..ExceptionsFilterConfig::default()
+        };
+
+        assert_eq!(should_filter(&get_event(), &config), Ok(()));
+    }
+}
@@ -7,7 +7,7 @@ use lazy_static::lazy_static;
 use relay_general::protocol::Event;
 use relay_general::user_agent::{self, UserAgent};
 
-use crate::{FilterStatKey, LegacyBrowser, LegacyBrowsersFilterConfig};
+use crate::{FilterStatKey, GlobPatterns, LegacyBrowser, LegacyBrowsersFilterConfig};
 
 /// Checks if the event originates from legacy browsers.
 pub fn matches(event: &Event, browsers: &BTreeSet<LegacyBrowser>) -> bool {
@@ -61,6 +61,10 @@ pub fn should_filter(
     event: &Event,
     config: &LegacyBrowsersFilterConfig,
 ) -> Result<(), FilterStatKey> {
+    if matches_user_agents(event, &config.user_agents) {
+        return Err(FilterStatKey::LegacyBrowsers);
+    }
+
     if !config.is_enabled || config.browsers.is_empty() {
         return Ok(()); // globally disabled or no individual browser enabled
     }
@@ -73,6 +77,14 @@ pub fn should_filter(
     }
 }
 
+/// Checks if the event's user agent matches any of the custom legacy browser patterns.
+fn matches_user_agents(event: &Event, user_agents: &GlobPatterns) -> bool {
+    match user_agent::get_user_agent(event) {
+        Some(user_agent) => user_agents.is_match(user_agent),
+        None => false,
+    }
+}
+
 lazy_static! {
     static ref MIN_VERSIONS: HashMap<&'static str, i32> = {
         let mut x = HashMap::new();
@@ -172,9 +184,27 @@ mod tests {
                 }
                 browsers
             },
+            ..LegacyBrowsersFilterConfig::default()
         }
     }
 
+    #[test]
+    fn test_filter_custom_user_agents() {
+        let config = LegacyBrowsersFilterConfig {
+            user_agents: GlobPatterns::new(vec!["*Trident/7.0*".to_string()]),
+            ..get_legacy_browsers_config(false, &[])
+        };
+
+        let evt = testutils::get_event_with_user_agent(IE11_UA);
+        assert_eq!(
+            should_filter(&evt, &config),
+            Err(FilterStatKey::LegacyBrowsers)
+        );
+
+        let evt = testutils::get_event_with_user_agent(IE10_UA);
+        assert_eq!(should_filter(&evt, &config), Ok(()));
+    }
+
     #[test]
     fn test_dont_filter_when_disabled() {
         let evt = testutils::get_event_with_user_agent(IE8_UA);
@@ -6,6 +6,8 @@
 //! * browser extensions (filter events caused by known problematic browser extensions)
 //! * web crawlers (filter events sent by user agents known to be web crawlers)
 //! * legacy browsers (filter events originating from legacy browsers, can be configured)
+//! * exceptions (filter events by exception type, module, mechanism and top in-app frame)
+//! * error spikes (drop duplicate errors above a rate, counted by the caller)
 #![warn(missing_docs)]
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/getsentry/relay/master/artwork/relay-icon.png",
@@ -20,6 +22,8 @@ pub mod browser_extensions;
 pub mod client_ips;
 pub mod csp;
 pub mod error_messages;
+pub mod error_spikes;
+pub mod exceptions;
 pub mod legacy_browsers;
 pub mod localhost;
 pub mod web_crawlers;
@@ -39,22 +43,190 @@ pub use crate::csp::matches_any_origin;
 ///
 /// If the event should be filter, the `Err` returned contains a filter reason.
 /// The reason is the message returned by the first filter that didn't pass.
+///
+/// Filters are skipped if they are restricted to environments that do not match the event's
+/// environment.
+///
+/// Filters configured with `dryRun` never cause the event to be filtered. Instead, their filter
+/// reasons are pushed to `dry_run_matches`, so that the caller can report which filters would
+/// have applied. Dry-run filters are evaluated even if another filter rejects the event. All other
+/// filters are skipped after the first rejection.
 pub fn should_filter(
     event: &Event,
     client_ip: Option<IpAddr>,
     config: &FiltersConfig,
+    dry_run_matches: &mut Vec<FilterStatKey>,
 ) -> Result<(), FilterStatKey> {
+    let environment = event.environment.as_str();
+    let mut result = Ok(());
+
+    macro_rules! apply_filter {
+        ($config:expr, $filter:expr) => {
+            // Once the event is rejected, only dry-run filters still need to be evaluated.
+            if ($config.dry_run || result.is_ok()) && $config.environments.applies_to(environment) {
+                match $filter {
+                    Err(filter_stat_key) if $config.dry_run => {
+                        dry_run_matches.push(filter_stat_key)
+                    }
+                    Err(filter_stat_key) => result = Err(filter_stat_key),
+                    Ok(()) => (),
+                }
+            }
+        };
+    }
+
     // NB: The order of applying filters should not matter as they are additive. Still, be careful
     // when making changes to this order.
+    apply_filter!(config.csp, csp::should_filter(event, &config.csp));
+    apply_filter!(
+        config.client_ips,
+        client_ips::should_filter(client_ip, &config.client_ips)
+    );
+    apply_filter!(
+        config.releases,
+        releases::should_filter(event, &config.releases)
+    );
+    apply_filter!(
+        config.error_messages,
+        error_messages::should_filter(event, &config.error_messages)
+    );
+    apply_filter!(
+        config.exceptions,
+        exceptions::should_filter(event, &config.exceptions)
+    );
+    apply_filter!(
+        config.localhost,
+        localhost::should_filter(event, &config.localhost)
+    );
+    apply_filter!(
+        config.browser_extensions,
+        browser_extensions::should_filter(event, &config.browser_extensions)
+    );
+    apply_filter!(
+        config.legacy_browsers,
+        legacy_browsers::should_filter(event, &config.legacy_browsers)
+    );
+    apply_filter!(
+        config.web_crawlers,
+        web_crawlers::should_filter(event, &config.web_crawlers)
+    );
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use relay_general::protocol::{IpAddr as EventIpAddr, User};
+    use relay_general::types::Annotated;
+
+    fn get_localhost_event() -> Event {
+        Event {
+            user: Annotated::from(User {
+                ip_address: Annotated::from(EventIpAddr("127.0.0.1".to_string())),
+                ..User::default()
+            }),
+            environment: Annotated::new("staging".to_string()),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn test_should_filter() {
+        let event = get_localhost_event();
+        let config = FiltersConfig {
+            localhost: FilterConfig {
+                is_enabled: true,
+                ..FilterConfig::default()
+            },
+            ..FiltersConfig::default()
+        };
+
+        let mut dry_run_matches = Vec::new();
+        let result = should_filter(&event, None, &config, &mut dry_run_matches);
+        assert_eq!(result, Err(FilterStatKey::Localhost));
+        assert!(dry_run_matches.is_empty());
+    }
+
+    #[test]
+    fn test_should_filter_dry_run() {
+        let event = get_localhost_event();
+        let config = FiltersConfig {
+            localhost: FilterConfig {
+                is_enabled: true,
+                dry_run: true,
+                ..FilterConfig::default()
+            },
+            ..FiltersConfig::default()
+        };
+
+        let mut dry_run_matches = Vec::new();
+        let result = should_filter(&event, None, &config, &mut dry_run_matches);
+        assert_eq!(result, Ok(()));
+        assert_eq!(dry_run_matches, vec![FilterStatKey::Localhost]);
+    }
+
+    #[test]
+    fn test_should_filter_dry_run_with_other_match() {
+        let event = get_localhost_event();
+        let config = FiltersConfig {
+            client_ips: ClientIpsFilterConfig {
+                blacklisted_ips: vec!["127.0.0.1".to_string()],
+                ..ClientIpsFilterConfig::default()
+            },
+            localhost: FilterConfig {
+                is_enabled: true,
+                dry_run: true,
+                ..FilterConfig::default()
+            },
+            ..FiltersConfig::default()
+        };
+
+        let client_ip = "127.0.0.1".parse().ok();
+        let mut dry_run_matches = Vec::new();
+        let result = should_filter(&event, client_ip, &config, &mut dry_run_matches);
+        assert_eq!(result, Err(FilterStatKey::IpAddress));
+        assert_eq!(dry_run_matches, vec![FilterStatKey::Localhost]);
+    }
+
+    #[test]
+    fn test_should_filter_included_environment() {
+        let event = get_localhost_event();
+        let config = FiltersConfig {
+            localhost: FilterConfig {
+                is_enabled: true,
+                environments: FilterEnvironments {
+                    include: GlobPatterns::new(vec!["staging".to_string()]),
+                    exclude: GlobPatterns::default(),
+                },
+                ..FilterConfig::default()
+            },
+            ..FiltersConfig::default()
+        };
+
+        let mut dry_run_matches = Vec::new();
+        let result = should_filter(&event, None, &config, &mut dry_run_matches);
+        assert_eq!(result, Err(FilterStatKey::Localhost));
+    }
 
-    csp::should_filter(event, &config.csp)?;
-    client_ips::should_filter(client_ip, &config.client_ips)?;
-    releases::should_filter(event, &config.releases)?;
-    error_messages::should_filter(event, &config.error_messages)?;
-    localhost::should_filter(event, &config.localhost)?;
-    browser_extensions::should_filter(event, &config.browser_extensions)?;
-    legacy_browsers::should_filter(event, &config.legacy_browsers)?;
-    web_crawlers::should_filter(event, &config.web_crawlers)?;
+    #[test]
+    fn test_should_filter_other_environment() {
+        let event = get_localhost_event();
+        let config = FiltersConfig {
+            localhost: FilterConfig {
+                is_enabled: true,
+                environments: FilterEnvironments {
+                    include: GlobPatterns::new(vec!["production".to_string()]),
+                    exclude: GlobPatterns::default(),
+                },
+                ..FilterConfig::default()
+            },
+            ..FiltersConfig::default()
+        };
 
-    Ok(())
+        let mut dry_run_matches = Vec::new();
+        let result = should_filter(&event, None, &config, &mut dry_run_matches);
+        assert_eq!(result, Ok(()));
+    }
 }
@@ -85,7 +85,13 @@ mod tests {
             get_event_with_ip_addr("127.0.0.1"),
             get_event_with_domain("localhost"),
         ] {
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: false });
+            let filter_result = should_filter(
+                &event,
+                &FilterConfig {
+                    is_enabled: false,
+                    ..FilterConfig::default()
+                },
+            );
             assert_eq!(
                 filter_result,
                 Ok(()),
@@ -98,7 +104,13 @@ mod tests {
     fn test_filter_local_ip() {
         for ip_addr in &["127.0.0.1", "::1"] {
             let event = get_event_with_ip_addr(ip_addr);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &FilterConfig {
+                    is_enabled: true,
+                    ..FilterConfig::default()
+                },
+            );
             assert_ne!(
                 filter_result,
                 Ok(()),
@@ -112,7 +124,13 @@ mod tests {
     fn test_dont_filter_non_local_ip() {
         for ip_addr in &["133.12.12.1", "2001:db8:0:0:0:ff00:42:8329"] {
             let event = get_event_with_ip_addr(ip_addr);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &FilterConfig {
+                    is_enabled: true,
+                    ..FilterConfig::default()
+                },
+            );
             assert_eq!(
                 filter_result,
                 Ok(()),
@@ -125,7 +143,13 @@ mod tests {
     #[test]
     fn test_dont_filter_missing_ip_or_domains() {
         let event = Event::default();
-        let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+        let filter_result = should_filter(
+            &event,
+            &FilterConfig {
+                is_enabled: true,
+                ..FilterConfig::default()
+            },
+        );
         assert_eq!(
             filter_result,
             Ok(()),
@@ -137,7 +161,13 @@ mod tests {
     fn test_filter_local_domains() {
         for domain in &["127.0.0.1", "localhost"] {
             let event = get_event_with_domain(domain);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &FilterConfig {
+                    is_enabled: true,
+                    ..FilterConfig::default()
+                },
+            );
             assert_ne!(
                 filter_result,
                 Ok(()),
@@ -151,7 +181,13 @@ mod tests {
     fn test_dont_filter_non_local_domains() {
         for domain in &["my.dom.com", "123.123.123.44"] {
             let event = get_event_with_domain(domain);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &FilterConfig {
+                    is_enabled: true,
+                    ..FilterConfig::default()
+                },
+            );
             assert_eq!(
                 filter_result,
                 Ok(()),
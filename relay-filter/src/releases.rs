@@ -64,6 +64,7 @@ mod tests {
                 releases: GlobPatterns::new(
                     blocked_releases.iter().map(|&r| r.to_string()).collect(),
                 ),
+                ..ReleasesFilterConfig::default()
             };
 
             let actual = should_filter(&evt, &config) != Ok(());
@@ -6,24 +6,48 @@ use regex::Regex;
 use relay_general::protocol::Event;
 use relay_general::user_agent;
 
-use crate::{FilterConfig, FilterStatKey};
+use crate::{FilterStatKey, WebCrawlersFilterConfig};
+
+/// Checks if the user agent belongs to one of the built-in web crawlers.
+fn is_builtin_web_crawler(user_agent: &str) -> bool {
+    WEB_CRAWLERS.is_match(user_agent) && !ALLOWED_WEB_CRAWLERS.is_match(user_agent)
+}
 
 /// Checks if the event originates from a known web crawler.
 pub fn matches(event: &Event) -> bool {
     if let Some(user_agent) = user_agent::get_user_agent(event) {
-        WEB_CRAWLERS.is_match(user_agent) && !ALLOWED_WEB_CRAWLERS.is_match(user_agent)
+        is_builtin_web_crawler(user_agent)
     } else {
         false
     }
 }
 
+/// Checks if the event originates from a web crawler according to the given configuration.
+///
+/// Custom user agent patterns always apply. The built-in list of web crawlers only applies if the
+/// filter is enabled, and user agents in the list of allowed user agents are exempt from it.
+pub fn matches_config(event: &Event, config: &WebCrawlersFilterConfig) -> bool {
+    let user_agent = match user_agent::get_user_agent(event) {
+        Some(user_agent) => user_agent,
+        None => return false,
+    };
+
+    if config.user_agents.is_match(user_agent) {
+        return true;
+    }
+
+    config.is_enabled
+        && is_builtin_web_crawler(user_agent)
+        && !config.allowed_user_agents.is_match(user_agent)
+}
+
 /// Filters events originating from a known web crawler.
-pub fn should_filter(event: &Event, config: &FilterConfig) -> Result<(), FilterStatKey> {
-    if !config.is_enabled {
+pub fn should_filter(event: &Event, config: &WebCrawlersFilterConfig) -> Result<(), FilterStatKey> {
+    if config.is_empty() {
         return Ok(());
     }
 
-    if matches(event) {
+    if matches_config(event, config) {
         return Err(FilterStatKey::WebCrawlers);
     }
 
@@ -70,12 +94,18 @@ lazy_static! {
 mod tests {
     use super::*;
 
-    use crate::testutils;
+    use crate::{testutils, GlobPatterns};
 
     #[test]
     fn test_filter_when_disabled() {
         let evt = testutils::get_event_with_user_agent("Googlebot");
-        let filter_result = should_filter(&evt, &FilterConfig { is_enabled: false });
+        let filter_result = should_filter(
+            &evt,
+            &WebCrawlersFilterConfig {
+                is_enabled: false,
+                ..WebCrawlersFilterConfig::default()
+            },
+        );
         assert_eq!(
             filter_result,
             Ok(()),
@@ -119,7 +149,13 @@ mod tests {
 
         for banned_user_agent in &user_agents {
             let event = testutils::get_event_with_user_agent(banned_user_agent);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &WebCrawlersFilterConfig {
+                    is_enabled: true,
+                    ..WebCrawlersFilterConfig::default()
+                },
+            );
             assert_ne!(
                 filter_result,
                 Ok(()),
@@ -143,7 +179,13 @@ mod tests {
         ];
         for user_agent in &normal_user_agents {
             let event = testutils::get_event_with_user_agent(user_agent);
-            let filter_result = should_filter(&event, &FilterConfig { is_enabled: true });
+            let filter_result = should_filter(
+                &event,
+                &WebCrawlersFilterConfig {
+                    is_enabled: true,
+                    ..WebCrawlersFilterConfig::default()
+                },
+            );
             assert_eq!(
                 filter_result,
                 Ok(()),
@@ -152,4 +194,40 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_filter_custom_user_agents() {
+        let config = WebCrawlersFilterConfig {
+            user_agents: GlobPatterns::new(vec!["*SyntheticMonitor/*".to_string()]),
+            ..WebCrawlersFilterConfig::default()
+        };
+
+        let event = testutils::get_event_with_user_agent("Mozilla/5.0 SyntheticMonitor/2.1");
+        assert_eq!(
+            should_filter(&event, &config),
+            Err(FilterStatKey::WebCrawlers)
+        );
+
+        // Built-in web crawlers are not filtered unless the filter is enabled.
+        let event = testutils::get_event_with_user_agent("Googlebot");
+        assert_eq!(should_filter(&event, &config), Ok(()));
+    }
+
+    #[test]
+    fn test_dont_filter_allowed_user_agents() {
+        let config = WebCrawlersFilterConfig {
+            is_enabled: true,
+            allowed_user_agents: GlobPatterns::new(vec!["*pingdom*".to_string()]),
+            ..WebCrawlersFilterConfig::default()
+        };
+
+        let event = testutils::get_event_with_user_agent("Pingdom.com_bot_version_1.4");
+        assert_eq!(should_filter(&event, &config), Ok(()));
+
+        let event = testutils::get_event_with_user_agent("Googlebot");
+        assert_eq!(
+            should_filter(&event, &config),
+            Err(FilterStatKey::WebCrawlers)
+        );
+    }
 }
@@ -7,10 +7,11 @@ use smallvec::SmallVec;
 use utf16string::{LittleEndian, WStr};
 
 use crate::pii::compiledconfig::RuleRef;
-use crate::pii::regexes::{get_regex_for_rule_type, ReplaceBehavior};
+use crate::pii::regexes::{get_regex_for_rule_type, validate_match, ReplaceBehavior};
 use crate::pii::utils::hash_value;
-use crate::pii::{CompiledPiiConfig, Redaction};
-use crate::processor::{FieldAttrs, Pii, ProcessingState, ValueType};
+use crate::pii::{CompiledPiiConfig, PiiProcessor, Redaction, RuleType};
+use crate::processor::{process_value, FieldAttrs, Pii, ProcessValue, ProcessingState, ValueType};
+use crate::types::{Annotated, Value};
 
 /// The minimum length a string needs to be in a binary blob.
 ///
@@ -44,6 +45,12 @@ fn apply_regex_to_utf8_bytes(
     };
 
     for captures in regex.captures_iter(data) {
+        let is_valid =
+            std::str::from_utf8(&captures[0]).map_or(false, |text| validate_match(&rule.ty, text));
+        if !is_valid {
+            continue;
+        }
+
         for (idx, group) in captures.iter().enumerate() {
             if let Some(group) = group {
                 if group.start() == group.end() {
@@ -82,6 +89,10 @@ fn apply_regex_to_utf16le_bytes(
         match replace_behavior {
             ReplaceBehavior::Value => {
                 for re_match in regex.find_iter(&segment.decoded) {
+                    if !validate_match(&rule.ty, re_match.as_str()) {
+                        continue;
+                    }
+
                     changed = true;
                     let match_wstr = get_wstr_match(&segment.decoded, re_match, segment.encoded);
                     match_wstr.apply_redaction(&rule.redaction);
@@ -89,6 +100,10 @@ fn apply_regex_to_utf16le_bytes(
             }
             ReplaceBehavior::Groups(ref replace_groups) => {
                 for captures in regex.captures_iter(&segment.decoded) {
+                    if !validate_match(&rule.ty, &captures[0]) {
+                        continue;
+                    }
+
                     for group_idx in replace_groups.iter() {
                         if let Some(re_match) = captures.get(*group_idx as usize) {
                             changed = true;
@@ -158,14 +173,15 @@ trait StringMods: AsRef<[u8]> {
         const MASK: char = '*';
 
         match redaction {
-            Redaction::Default | Redaction::Remove => {
+            // Encrypted values do not fit into the original value, so they are removed instead.
+            Redaction::Default | Redaction::Remove | Redaction::Encrypt(_) => {
                 self.fill_content(PADDING);
             }
             Redaction::Mask => {
                 self.fill_content(MASK);
             }
-            Redaction::Hash => {
-                let hashed = hash_value(self.as_ref());
+            Redaction::Hash(ref hash) => {
+                let hashed = hash_value(hash, self.as_ref());
                 self.swap_content(&hashed, PADDING);
             }
             Redaction::Replace(ref replace) => {
@@ -340,12 +356,55 @@ pub struct PiiAttachmentsProcessor<'a> {
 }
 
 /// Which encodings to scrub for `scrub_bytes`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ScrubEncodings {
+    /// Scrub strings encoded as UTF-8.
     Utf8,
+    /// Scrub strings encoded as UTF-16 with little-endian byte order.
     Utf16Le,
+    /// Scrub both UTF-8 and UTF-16LE strings, for binary data of unknown encoding.
     All,
 }
 
+impl ScrubEncodings {
+    /// Determines the encodings to scrub in an attachment from its content type and contents.
+    ///
+    /// Text with a UTF-16LE byte order mark or charset is scrubbed as UTF-16LE, other text and JSON
+    /// as UTF-8. UTF-16BE is not supported by the scrubber, so such text and all other attachments
+    /// are treated as binary data. This includes `utf-16` without a byte order mark, which defaults
+    /// to big endian according to RFC 2781.
+    pub fn for_attachment(content_type: Option<&str>, data: &[u8]) -> Self {
+        if data.starts_with(&[0xff, 0xfe]) {
+            return ScrubEncodings::Utf16Le;
+        } else if data.starts_with(&[0xfe, 0xff]) {
+            return ScrubEncodings::All;
+        }
+
+        let content_type = match content_type {
+            Some(content_type) => content_type.to_ascii_lowercase(),
+            None => return ScrubEncodings::All,
+        };
+
+        let mut params = content_type.split(';');
+        let mime_type = params.next().unwrap_or_default().trim();
+        let is_text = mime_type.starts_with("text/")
+            || mime_type == "application/json"
+            || mime_type.ends_with("+json");
+
+        let charset = params
+            .filter_map(|param| param.trim().strip_prefix("charset="))
+            .map(|charset| charset.trim_matches('"'))
+            .next();
+
+        match charset {
+            _ if !is_text => ScrubEncodings::All,
+            Some("utf-16le") => ScrubEncodings::Utf16Le,
+            Some(charset) if charset.starts_with("utf-16") => ScrubEncodings::All,
+            _ => ScrubEncodings::Utf8,
+        }
+    }
+}
+
 impl<'a> PiiAttachmentsProcessor<'a> {
     /// Creates a new `PiiAttachmentsProcessor` from the given PII config.
     pub fn new(compiled_config: &'a CompiledPiiConfig) -> Self {
@@ -393,6 +452,12 @@ impl<'a> PiiAttachmentsProcessor<'a> {
         for (selector, rules) in &self.compiled_config.applications {
             if state.path().matches_selector(&selector) {
                 for rule in rules {
+                    // SQL statements cannot be detected in binary data, so rules restricted to
+                    // them never apply to attachments.
+                    if rule.ty == RuleType::SqlLiterals {
+                        continue;
+                    }
+
                     // Note:
                     //
                     // - We ignore pattern_type and just treat every regex like a value regex (i.e.
@@ -462,8 +527,84 @@ impl<'a> PiiAttachmentsProcessor<'a> {
     ///
     /// Returns `true`, if the attachment was modified.
     pub fn scrub_attachment(&self, filename: &str, data: &mut [u8]) -> bool {
+        self.scrub_encoded_attachment(filename, data, ScrubEncodings::All)
+    }
+
+    /// Applies PII scrubbing rules to a plain attachment with known encodings.
+    ///
+    /// Returns `true`, if the attachment was modified.
+    pub fn scrub_encoded_attachment(
+        &self,
+        filename: &str,
+        data: &mut [u8],
+        encodings: ScrubEncodings,
+    ) -> bool {
         let state = self.state(filename, ValueType::Binary);
-        self.scrub_bytes(data, &state, ScrubEncodings::All)
+        self.scrub_bytes(data, &state, encodings)
+    }
+
+    /// Applies PII scrubbing rules to the structure of a JSON attachment.
+    ///
+    /// The attachment is parsed into a value tree and scrubbed like event payloads, which allows
+    /// selectors to address fields within the document, for instance
+    /// `$attachments.'state.json'.user.email`. If any rules applied, the scrubbed document is
+    /// serialized back into `data`, which may change its length and formatting.
+    ///
+    /// Rules that apply to the attachment as a whole, such as `$attachments.'state.json'` or
+    /// `$binary`, are applied to every string in the document rather than to its serialized bytes,
+    /// so that they cannot break its syntax.
+    ///
+    /// Returns `true`, if the attachment was modified. Returns an error if the attachment does not
+    /// contain valid JSON, in which case `data` is not modified.
+    pub fn scrub_json_attachment(
+        &self,
+        filename: &str,
+        data: &mut Vec<u8>,
+    ) -> Result<bool, serde_json::Error> {
+        let mut value = Annotated::new(serde_json::from_slice::<Value>(data)?);
+        let original = value.clone();
+
+        let file_state = self.state(filename, ValueType::Binary);
+        let file_rules = self
+            .compiled_config
+            .applications
+            .iter()
+            .filter(|(selector, _)| file_state.path().matches_selector(selector))
+            .flat_map(|(_, rules)| rules)
+            .collect();
+
+        let attrs = Cow::Owned(FieldAttrs::new().pii(Pii::True));
+        let state =
+            self.root_state
+                .enter_borrowed(filename, Some(attrs), ValueType::for_field(&value));
+
+        // The PII processor never rejects values, it only deletes or modifies them.
+        let is_container = matches!(
+            value.value(),
+            Some(Value::Object(_)) | Some(Value::Array(_))
+        );
+        if is_container {
+            // Rules for the entire attachment apply to all strings within the document, so its
+            // root is not matched against selectors again.
+            let mut processor =
+                PiiProcessor::new(self.compiled_config).with_string_rules(file_rules);
+            match value.value_mut() {
+                Some(Value::Object(object)) => object.process_child_values(&mut processor, &state),
+                Some(Value::Array(array)) => array.process_child_values(&mut processor, &state),
+                _ => Ok(()),
+            }
+            .ok();
+        } else {
+            let mut processor = PiiProcessor::new(self.compiled_config);
+            process_value(&mut value, &mut processor, &state).ok();
+        }
+
+        if value.value() == original.value() {
+            return Ok(false);
+        }
+
+        *data = value.payload_to_json()?.into_bytes();
+        Ok(true)
     }
 
     /// Scrub a filepath, preserving the basename.
@@ -959,4 +1100,189 @@ mod tests {
             b"h\x00e\x00l\x00l\x00o\x00 \x00t\x00h\x00e\x00r\x00e\x00"
         );
     }
+
+    #[test]
+    fn test_scrub_encodings_for_attachment() {
+        let text = b"hello";
+        let utf16 = b"\xff\xfeh\x00e\x00";
+
+        assert_eq!(
+            ScrubEncodings::for_attachment(None, text),
+            ScrubEncodings::All
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(Some("application/octet-stream"), text),
+            ScrubEncodings::All
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(Some("text/plain"), text),
+            ScrubEncodings::Utf8
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(Some("application/json"), text),
+            ScrubEncodings::Utf8
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(Some("Text/Plain; charset=UTF-16LE"), text),
+            ScrubEncodings::Utf16Le
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(Some("text/plain; charset=\"utf-16\""), text),
+            ScrubEncodings::All
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(Some("text/plain; charset=utf-16be"), text),
+            ScrubEncodings::All
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(None, utf16),
+            ScrubEncodings::Utf16Le
+        );
+        assert_eq!(
+            ScrubEncodings::for_attachment(Some("text/plain"), b"\xfe\xff\x00h\x00e"),
+            ScrubEncodings::All
+        );
+    }
+
+    #[test]
+    fn test_scrub_encoded_attachment_utf8_only() {
+        let config =
+            PiiConfig::from_json(r#"{"applications": {"$attachments.'log.txt'": ["@ip:mask"]}}"#)
+                .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let mut data = b"from 127.0.0.1 and 1\x002\x007\x00.\x000\x00.\x000\x00.\x001\x00".to_vec();
+        let changed =
+            processor.scrub_encoded_attachment("log.txt", &mut data, ScrubEncodings::Utf8);
+
+        assert!(changed);
+        assert_eq!(
+            data,
+            b"from ********* and 1\x002\x007\x00.\x000\x00.\x000\x00.\x001\x00".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_scrub_json_attachment() {
+        let config = PiiConfig::from_json(
+            r#"{"applications": {"$attachments.'state.json'.user.email": ["@anything:mask"]}}"#,
+        )
+        .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let mut data = br#"{"user": {"email": "a@b.c", "name": "\"quoted\""}}"#.to_vec();
+        let changed = processor
+            .scrub_json_attachment("state.json", &mut data)
+            .unwrap();
+
+        assert!(changed);
+        assert_eq!(
+            std::str::from_utf8(&data).unwrap(),
+            r#"{"user":{"email":"*****","name":"\"quoted\""}}"#
+        );
+    }
+
+    #[test]
+    fn test_scrub_json_attachment_whole_file() {
+        let config = PiiConfig::from_json(
+            r#"{"applications": {"$attachments.'state.json'": ["@ip:mask"]}}"#,
+        )
+        .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let mut data = br#"{"user": {"ip": "127.0.0.1"}}"#.to_vec();
+        let changed = processor
+            .scrub_json_attachment("state.json", &mut data)
+            .unwrap();
+
+        assert!(changed);
+        assert_eq!(
+            std::str::from_utf8(&data).unwrap(),
+            r#"{"user":{"ip":"*********"}}"#
+        );
+    }
+
+    #[test]
+    fn test_scrub_json_attachment_whole_file_remove() {
+        let config = PiiConfig::from_json(
+            r#"{"applications": {"$attachments.'state.json'": ["@anything:remove"]}}"#,
+        )
+        .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let mut data = br#"{"user": {"id": 42, "ip": "127.0.0.1"}}"#.to_vec();
+        let changed = processor
+            .scrub_json_attachment("state.json", &mut data)
+            .unwrap();
+
+        // Strings are removed individually, which keeps the document valid.
+        assert!(changed);
+        let scrubbed: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(scrubbed["user"]["id"], 42);
+        assert!(scrubbed["user"]["ip"].is_null());
+    }
+
+    #[test]
+    fn test_scrub_json_attachment_across_syntax() {
+        let config = PiiConfig::from_json(
+            r#"{
+                "rules": {
+                    "quoted": {
+                        "type": "pattern",
+                        "pattern": "a\",\\s*\"b",
+                        "redaction": {"method": "mask"}
+                    }
+                },
+                "applications": {"$attachments.'state.json'": ["quoted"]}
+            }"#,
+        )
+        .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        // The pattern only matches the serialized document across two strings.
+        let original = br#"["a", "b"]"#.to_vec();
+        let mut data = original.clone();
+        let changed = processor
+            .scrub_json_attachment("state.json", &mut data)
+            .unwrap();
+
+        assert!(!changed);
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn test_scrub_json_attachment_unchanged() {
+        let config = PiiConfig::from_json(
+            r#"{"applications": {"$attachments.'other.json'.**": ["@anything:remove"]}}"#,
+        )
+        .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let original = br#"{ "user": { "email": "a@b.c" } }"#.to_vec();
+        let mut data = original.clone();
+        let changed = processor
+            .scrub_json_attachment("state.json", &mut data)
+            .unwrap();
+
+        assert!(!changed);
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn test_scrub_json_attachment_invalid() {
+        let config = PiiConfig::default();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let mut data = b"{\"user\": ".to_vec();
+        assert!(processor
+            .scrub_json_attachment("state.json", &mut data)
+            .is_err());
+    }
 }
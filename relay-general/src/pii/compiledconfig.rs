@@ -1,9 +1,10 @@
 use std::cmp::Ordering;
 use std::collections::BTreeSet;
+use std::sync::Arc;
 
 use crate::pii::builtin::BUILTIN_RULES_MAP;
-use crate::pii::{PiiConfig, Redaction, RuleSpec, RuleType};
-use crate::processor::SelectorSpec;
+use crate::pii::{EncryptRedaction, HashRedaction, PiiConfig, Redaction, RuleSpec, RuleType, Vars};
+use crate::processor::{PredicateField, SelectorPathItem, SelectorSpec};
 
 /// A representation of `PiiConfig` that is more (CPU-)efficient for use in `PiiProcessor`. It is
 /// lossy in the sense that it cannot be consumed by downstream relays, so both versions have to be
@@ -11,6 +12,13 @@ use crate::processor::SelectorSpec;
 #[derive(Debug, Clone)]
 pub struct CompiledPiiConfig {
     pub(super) applications: Vec<(SelectorSpec, BTreeSet<RuleRef>)>,
+    /// Whether any selector uses predicates on the length of strings.
+    pub(super) predicate_lengths: bool,
+    /// Names of fields referenced by selector predicates.
+    ///
+    /// If this is empty and no selector uses length predicates, the processor does not need to
+    /// record values.
+    pub(super) predicate_fields: BTreeSet<String>,
 }
 
 impl CompiledPiiConfig {
@@ -20,31 +28,64 @@ impl CompiledPiiConfig {
             #[allow(clippy::mutable_key_type)]
             let mut rule_set = BTreeSet::default();
             for rule_id in rules {
-                collect_rules(config, &mut rule_set, &rule_id, None);
+                collect_rules(config, &mut rule_set, &rule_id, None, false);
             }
             applications.push((selector.clone(), rule_set));
         }
 
-        CompiledPiiConfig { applications }
+        let (predicate_lengths, predicate_fields) = collect_predicate_fields(&applications);
+        CompiledPiiConfig {
+            applications,
+            predicate_lengths,
+            predicate_fields,
+        }
+    }
+}
+
+/// Collects whether any of the selectors use length predicates, and the names of fields referenced
+/// by their predicates.
+fn collect_predicate_fields(
+    applications: &[(SelectorSpec, BTreeSet<RuleRef>)],
+) -> (bool, BTreeSet<String>) {
+    let mut lengths = false;
+    let mut fields = BTreeSet::new();
+
+    for (selector, _) in applications {
+        for predicate in selector.predicates() {
+            match predicate.field() {
+                PredicateField::Key => (),
+                PredicateField::Length => lengths = true,
+                PredicateField::Field(name) => {
+                    fields.insert(name.clone());
+                }
+            }
+        }
     }
+
+    (lengths, fields)
 }
 
 fn get_rule(config: &PiiConfig, id: &str) -> Option<RuleRef> {
     if let Some(spec) = config.rules.get(id) {
-        Some(RuleRef::new(id.to_owned(), spec))
+        Some(RuleRef::new(id.to_owned(), spec, &config.vars))
     } else {
         BUILTIN_RULES_MAP
             .get(id)
-            .map(|spec| RuleRef::new(id.to_owned(), spec))
+            .map(|spec| RuleRef::new(id.to_owned(), spec, &config.vars))
     }
 }
 
+/// Resolves the rule with the given id into `rules`, following aliases and multiple rules.
+///
+/// If `embedded` is set, rules are collected for an embedded document and JSON string rules are
+/// skipped, since embedded documents are not parsed recursively.
 #[allow(clippy::mutable_key_type)]
 fn collect_rules(
     config: &PiiConfig,
     rules: &mut BTreeSet<RuleRef>,
     rule_id: &str,
     parent: Option<RuleRef>,
+    embedded: bool,
 ) {
     let rule = match get_rule(config, rule_id) {
         Some(rule) => rule,
@@ -68,7 +109,7 @@ fn collect_rules(
                 None
             };
             for rule_id in &m.rules {
-                collect_rules(config, rules, &rule_id, parent.clone());
+                collect_rules(config, rules, &rule_id, parent.clone(), embedded);
             }
         }
         RuleType::Alias(ref a) => {
@@ -77,7 +118,28 @@ fn collect_rules(
             } else {
                 None
             };
-            collect_rules(config, rules, &a.rule, parent);
+            collect_rules(config, rules, &a.rule, parent, embedded);
+        }
+        RuleType::JsonString(_) if embedded => (),
+        RuleType::JsonString(ref j) => {
+            #[allow(clippy::mutable_key_type)]
+            let mut inner_rules = BTreeSet::default();
+            for rule_id in &j.rules {
+                collect_rules(config, &mut inner_rules, rule_id, None, true);
+            }
+
+            // Within the embedded document, the rules apply to all values.
+            let selector = SelectorSpec::Path(vec![SelectorPathItem::DeepWildcard]);
+            let inner = CompiledPiiConfig {
+                applications: vec![(selector, inner_rules)],
+                predicate_lengths: false,
+                predicate_fields: BTreeSet::new(),
+            };
+
+            rules.insert(RuleRef {
+                inner: Some(Arc::new(inner)),
+                ..rule
+            });
         }
         _ => {
             rules.insert(rule);
@@ -92,15 +154,32 @@ pub(super) struct RuleRef {
     pub origin: String,
     pub ty: RuleType,
     pub redaction: Redaction,
+    /// Rules to apply within embedded documents, for `RuleType::JsonString`.
+    pub inner: Option<Arc<CompiledPiiConfig>>,
 }
 
 impl RuleRef {
-    fn new(id: String, spec: &RuleSpec) -> Self {
+    fn new(id: String, spec: &RuleSpec, vars: &Vars) -> Self {
+        // Hash and encrypt redactions without their own key fall back to the config's default keys.
+        let redaction = match spec.redaction {
+            Redaction::Hash(ref hash) if hash.key.is_none() => Redaction::Hash(HashRedaction {
+                algorithm: hash.algorithm,
+                key: vars.hash_key.clone(),
+            }),
+            Redaction::Encrypt(ref encrypt) if encrypt.key.is_none() => {
+                Redaction::Encrypt(EncryptRedaction {
+                    key: vars.encryption_key.clone(),
+                })
+            }
+            ref redaction => redaction.clone(),
+        };
+
         RuleRef {
             origin: id.clone(),
             id,
             ty: spec.ty.clone(),
-            redaction: spec.redaction.clone(),
+            redaction,
+            inner: None,
         }
     }
 
@@ -113,6 +192,7 @@ impl RuleRef {
                 Redaction::Default => self.redaction,
                 _ => parent.redaction,
             },
+            inner: self.inner,
         }
     }
 }
@@ -105,6 +105,14 @@ pub struct RedactPairRule {
     pub key_pattern: Pattern,
 }
 
+/// A rule that applies other rules to JSON documents embedded in strings.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonStringRule {
+    /// References to the rules to apply within the parsed document.
+    pub rules: Vec<String>,
+}
+
 /// Supported stripping rules.
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -133,8 +141,24 @@ pub enum RuleType {
     UrlAuth,
     /// US SSN.
     UsSsn,
+    /// International bank account numbers with a valid checksum
+    Iban,
+    /// International phone numbers in E.164 format
+    Phone,
+    /// UK National Insurance numbers
+    UkNino,
+    /// German tax identification numbers with a valid check digit
+    DeTaxId,
+    /// JSON web tokens
+    Jwt,
+    /// Bearer tokens in authorization values
+    Bearer,
     /// Keys that look like passwords
     Password,
+    /// String and number literals in SQL statements
+    SqlLiterals,
+    /// Parses strings containing JSON objects or arrays and applies rules to their contents.
+    JsonString(JsonStringRule),
     /// When a regex matches a key, a value is removed
     #[serde(alias = "redactPair")]
     RedactPair(RedactPairRule),
@@ -154,12 +178,25 @@ pub struct RuleSpec {
 }
 
 /// Configuration for rule parameters.
-#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Default, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Vars {
     /// The default secret key for hashing operations.
     #[serde(default)]
     pub hash_key: Option<String>,
+    /// The default public key for encryption operations.
+    #[serde(default)]
+    pub encryption_key: Option<String>,
+}
+
+impl fmt::Debug for Vars {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never leak the secret key into logs.
+        f.debug_struct("Vars")
+            .field("hash_key", &self.hash_key.as_ref().map(|_| "[redacted]"))
+            .field("encryption_key", &self.encryption_key)
+            .finish()
+    }
 }
 
 /// A set of named rule configurations.
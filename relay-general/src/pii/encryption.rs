@@ -0,0 +1,266 @@
+//! Reversible encryption of PII values.
+//!
+//! Values are encrypted for a recipient's X25519 public key, so that Relay can encrypt values
+//! without being able to decrypt them. Only the holder of the corresponding secret key can
+//! recover the original values using [`decrypt_value`].
+//!
+//! # Format
+//!
+//! Encrypted values are strings of the form:
+//!
+//! ```text
+//! enc:v1:<payload>
+//! ```
+//!
+//! The payload is the URL-safe base64 encoding without padding of:
+//!
+//!  1. The ephemeral X25519 public key (32 bytes).
+//!  2. The ChaCha20-Poly1305 ciphertext of the UTF-8 encoded value, followed by its
+//!     authentication tag (16 bytes).
+//!
+//! For every value, a new ephemeral key pair is generated and the encryption key is derived from
+//! the X25519 shared secret using HKDF-SHA256. The HKDF salt is the ephemeral public key followed
+//! by the recipient's public key, and the info string is `relay-pii-encryption-v1`. Since every
+//! key is used only once, the nonce is all zeros. There is no associated data.
+//!
+//! Keys are exchanged as URL-safe base64 encoded strings without padding of their 32 raw bytes.
+
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use failure::Fail;
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use serde::Serialize;
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+/// The prefix of values encrypted with the current format.
+const PREFIX: &str = "enc:v1:";
+
+/// The HKDF info string for the current format.
+const KDF_INFO: &[u8] = b"relay-pii-encryption-v1";
+
+/// Size of X25519 keys in bytes.
+const KEY_SIZE: usize = 32;
+
+/// Size of the Poly1305 authentication tag in bytes.
+const TAG_SIZE: usize = 16;
+
+/// Raised if a value cannot be encrypted or decrypted.
+#[derive(Debug, Fail, PartialEq, Eq, Hash)]
+pub enum EncryptionError {
+    /// Invalid key encoding.
+    #[fail(display = "bad key encoding")]
+    BadEncoding,
+    /// Invalid key data.
+    #[fail(display = "bad key data")]
+    BadKey,
+    /// The value is not a valid encrypted value or was not encrypted for this key.
+    #[fail(display = "bad encrypted value")]
+    BadCiphertext,
+}
+
+/// A key pair for encrypting PII values.
+///
+/// The public key goes into the `encryptionKey` of the PII config's `vars` or the `key` of an
+/// encrypt redaction. The secret key is required to decrypt values and must never be passed to
+/// Relay.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionKeyPair {
+    /// The encoded secret key.
+    pub secret_key: String,
+    /// The encoded public key.
+    pub public_key: String,
+}
+
+impl EncryptionKeyPair {
+    /// Generates a new random key pair.
+    pub fn generate() -> Self {
+        let secret = StaticSecret::new(OsRng);
+        let public = PublicKey::from(&secret);
+
+        EncryptionKeyPair {
+            secret_key: encode(&secret.to_bytes()),
+            public_key: encode(public.as_bytes()),
+        }
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode_key(s: &str) -> Result<[u8; KEY_SIZE], EncryptionError> {
+    let bytes = base64::decode_config(s, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| EncryptionError::BadEncoding)?;
+
+    if bytes.len() != KEY_SIZE {
+        return Err(EncryptionError::BadKey);
+    }
+
+    let mut key = [0; KEY_SIZE];
+    key.copy_from_slice(&bytes);
+    Ok(key)
+}
+
+/// Derives the symmetric key from the shared secret with HKDF-SHA256.
+///
+/// Since the output is exactly as long as the hash, the expand step needs a single block.
+fn derive_key(
+    shared_secret: &[u8],
+    ephemeral_key: &PublicKey,
+    public_key: &PublicKey,
+) -> Result<Key, EncryptionError> {
+    // Contributory behavior: reject low-order points that result in an all-zero secret.
+    if shared_secret.iter().all(|b| *b == 0) {
+        return Err(EncryptionError::BadKey);
+    }
+
+    let mut salt = Vec::with_capacity(KEY_SIZE * 2);
+    salt.extend_from_slice(ephemeral_key.as_bytes());
+    salt.extend_from_slice(public_key.as_bytes());
+
+    let mut extract = Hmac::<Sha256>::new_varkey(&salt).unwrap();
+    extract.input(shared_secret);
+    let prk = extract.result().code();
+
+    let mut expand = Hmac::<Sha256>::new_varkey(&prk).unwrap();
+    expand.input(KDF_INFO);
+    expand.input(&[1]);
+    Ok(Key::clone_from_slice(&expand.result().code()))
+}
+
+/// Encrypts a value for the given encoded public key.
+///
+/// The result is non-deterministic, so that equal values cannot be correlated. See the
+/// [module documentation](self) for the format.
+pub fn encrypt_value(public_key: &str, data: &[u8]) -> Result<String, EncryptionError> {
+    let public_key = PublicKey::from(decode_key(public_key)?);
+    let ephemeral_secret = EphemeralSecret::new(OsRng);
+    let ephemeral_key = PublicKey::from(&ephemeral_secret);
+
+    let shared_secret = ephemeral_secret.diffie_hellman(&public_key);
+    let key = derive_key(shared_secret.as_bytes(), &ephemeral_key, &public_key)?;
+
+    let ciphertext = ChaCha20Poly1305::new(&key)
+        .encrypt(Nonce::from_slice(&[0; 12]), data)
+        .map_err(|_| EncryptionError::BadCiphertext)?;
+
+    let mut payload = Vec::with_capacity(KEY_SIZE + ciphertext.len());
+    payload.extend_from_slice(ephemeral_key.as_bytes());
+    payload.extend_from_slice(&ciphertext);
+
+    Ok(format!("{}{}", PREFIX, encode(&payload)))
+}
+
+/// Decrypts a value produced by [`encrypt_value`] with the given encoded secret key.
+pub fn decrypt_value(secret_key: &str, value: &str) -> Result<String, EncryptionError> {
+    let secret = StaticSecret::from(decode_key(secret_key)?);
+    let public_key = PublicKey::from(&secret);
+
+    let payload = value
+        .strip_prefix(PREFIX)
+        .and_then(|payload| base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok())
+        .filter(|payload| payload.len() >= KEY_SIZE + TAG_SIZE)
+        .ok_or(EncryptionError::BadCiphertext)?;
+
+    let (ephemeral_key, ciphertext) = payload.split_at(KEY_SIZE);
+    let mut ephemeral_bytes = [0; KEY_SIZE];
+    ephemeral_bytes.copy_from_slice(ephemeral_key);
+    let ephemeral_key = PublicKey::from(ephemeral_bytes);
+
+    let shared_secret = secret.diffie_hellman(&ephemeral_key);
+    let key = derive_key(shared_secret.as_bytes(), &ephemeral_key, &public_key)
+        .map_err(|_| EncryptionError::BadCiphertext)?;
+
+    let plaintext = ChaCha20Poly1305::new(&key)
+        .decrypt(Nonce::from_slice(&[0; 12]), ciphertext)
+        .map_err(|_| EncryptionError::BadCiphertext)?;
+
+    String::from_utf8(plaintext).map_err(|_| EncryptionError::BadCiphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let key_pair = EncryptionKeyPair::generate();
+
+        let encrypted = encrypt_value(&key_pair.public_key, b"user-42").unwrap();
+        assert!(encrypted.starts_with("enc:v1:"));
+
+        let decrypted = decrypt_value(&key_pair.secret_key, &encrypted).unwrap();
+        assert_eq!(decrypted, "user-42");
+    }
+
+    #[test]
+    fn test_non_deterministic() {
+        let key_pair = EncryptionKeyPair::generate();
+
+        let first = encrypt_value(&key_pair.public_key, b"user-42").unwrap();
+        let second = encrypt_value(&key_pair.public_key, b"user-42").unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let key_pair = EncryptionKeyPair::generate();
+        let other = EncryptionKeyPair::generate();
+
+        let encrypted = encrypt_value(&key_pair.public_key, b"user-42").unwrap();
+        assert_eq!(
+            decrypt_value(&other.secret_key, &encrypted),
+            Err(EncryptionError::BadCiphertext)
+        );
+    }
+
+    #[test]
+    fn test_tampered() {
+        let key_pair = EncryptionKeyPair::generate();
+
+        let mut encrypted = encrypt_value(&key_pair.public_key, b"user-42").unwrap();
+        // Flip a character in the ciphertext after the ephemeral key.
+        let index = encrypted.len() - 10;
+        let flipped = if &encrypted[index..=index] == "A" {
+            "B"
+        } else {
+            "A"
+        };
+        encrypted.replace_range(index..=index, flipped);
+
+        assert_eq!(
+            decrypt_value(&key_pair.secret_key, &encrypted),
+            Err(EncryptionError::BadCiphertext)
+        );
+        assert_eq!(
+            decrypt_value(&key_pair.secret_key, "enc:v1:"),
+            Err(EncryptionError::BadCiphertext)
+        );
+        assert_eq!(
+            decrypt_value(&key_pair.secret_key, "user-42"),
+            Err(EncryptionError::BadCiphertext)
+        );
+    }
+
+    #[test]
+    fn test_bad_keys() {
+        assert_eq!(
+            encrypt_value("not a key!", b"user-42"),
+            Err(EncryptionError::BadEncoding)
+        );
+        assert_eq!(
+            encrypt_value("dXNlci00Mg", b"user-42"),
+            Err(EncryptionError::BadKey)
+        );
+
+        // The all-zero point has low order and results in an all-zero shared secret.
+        let zero_key = encode(&[0; KEY_SIZE]);
+        assert_eq!(
+            encrypt_value(&zero_key, b"user-42"),
+            Err(EncryptionError::BadKey)
+        );
+    }
+}
@@ -1,11 +1,11 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use serde::Serialize;
 
 use crate::pii::utils::process_pairlist;
 use crate::processor::{
-    process_value, Pii, ProcessValue, ProcessingState, Processor, SelectorPathItem, SelectorSpec,
-    ValueType,
+    process_value, Pii, PredicateOperator, ProcessValue, ProcessingState, Processor,
+    SelectorPathItem, SelectorPredicate, SelectorSpec, ValueType,
 };
 use crate::protocol::{AsPair, PairList};
 use crate::types::{Annotated, Meta, ProcessingResult, Value};
@@ -21,8 +21,59 @@ pub struct SelectorSuggestion {
     pub value: Option<String>,
 }
 
+/// Replaces a trailing key in the path with a predicate on its prefix.
+///
+/// For example, this turns `$http.headers.X-Forwarded-For` into `$http.headers[?key=~"X-*"]`.
+/// Returns the new path and the replaced key, or `None` if the path does not end with a key that
+/// has a dash-separated prefix.
+fn with_key_prefix_predicate(path: &[SelectorPathItem]) -> Option<(Vec<SelectorPathItem>, String)> {
+    let (last, parent) = path.split_last()?;
+    let key = match last {
+        SelectorPathItem::Key(key) => key,
+        _ => return None,
+    };
+
+    let index = key.find('-').filter(|index| *index > 0)?;
+    let pattern = format!("{}*", &key[..=index]);
+    let predicate = SelectorPredicate::new(PredicateOperator::Matches, &pattern).ok()?;
+
+    let mut path = parent.to_vec();
+    path.push(SelectorPathItem::Predicate(predicate));
+    Some((path, key.clone()))
+}
+
 struct GenerateSelectorsProcessor {
     selectors: BTreeSet<SelectorSuggestion>,
+    /// Candidates for key prefix predicates with the distinct keys they matched.
+    ///
+    /// A predicate is only worth suggesting if it matches more than one key, so these are only
+    /// added to the suggestions at the end.
+    prefix_candidates: BTreeMap<SelectorSpec, (BTreeSet<String>, Option<String>)>,
+}
+
+impl GenerateSelectorsProcessor {
+    fn add_prefix_candidate(
+        &mut self,
+        path: Vec<SelectorPathItem>,
+        key: String,
+        value: Option<String>,
+    ) {
+        let (keys, _) = self
+            .prefix_candidates
+            .entry(SelectorSpec::Path(path))
+            .or_insert_with(|| (BTreeSet::new(), value));
+        keys.insert(key);
+    }
+
+    fn into_selectors(self) -> BTreeSet<SelectorSuggestion> {
+        let mut selectors = self.selectors;
+        for (path, (keys, value)) in self.prefix_candidates {
+            if keys.len() > 1 {
+                selectors.insert(SelectorSuggestion { path, value });
+            }
+        }
+        selectors
+    }
 }
 
 impl Processor for GenerateSelectorsProcessor {
@@ -40,17 +91,18 @@ impl Processor for GenerateSelectorsProcessor {
             return Ok(());
         }
 
+        let mut string_value = None;
+        if let Some(value) = value {
+            if let Value::String(s) = value.clone().into_value() {
+                string_value = Some(s);
+            }
+        }
+
         let mut insert_path = |path: SelectorSpec| {
             if state.path().matches_selector(&path) {
-                let mut string_value = None;
-                if let Some(value) = value {
-                    if let Value::String(s) = value.clone().into_value() {
-                        string_value = Some(s);
-                    }
-                }
                 self.selectors.insert(SelectorSuggestion {
                     path,
-                    value: string_value,
+                    value: string_value.clone(),
                 });
                 true
             } else {
@@ -59,9 +111,10 @@ impl Processor for GenerateSelectorsProcessor {
         };
 
         let mut path = Vec::new();
+        let mut typed_path = None;
 
         // Walk through processing state in reverse order and build selector path off of that.
-        for substate in state.iter() {
+        'states: for substate in state.iter() {
             if !substate.entered_anything() {
                 continue;
             }
@@ -83,10 +136,11 @@ impl Processor for GenerateSelectorsProcessor {
                         let mut path = path.clone();
                         path.push(SelectorPathItem::Type(ty));
                         path.reverse();
-                        if insert_path(SelectorSpec::Path(path)) {
+                        if insert_path(SelectorSpec::Path(path.clone())) {
                             // If we managed to generate $http.header.Authorization, we do not want to
                             // generate request.headers.Authorization as well.
-                            return Ok(());
+                            typed_path = Some(path);
+                            break 'states;
                         }
                     }
                 }
@@ -102,9 +156,18 @@ impl Processor for GenerateSelectorsProcessor {
             }
         }
 
-        if !path.is_empty() {
-            path.reverse();
-            insert_path(SelectorSpec::Path(path));
+        let path = match typed_path {
+            Some(path) => path,
+            None if !path.is_empty() => {
+                path.reverse();
+                insert_path(SelectorSpec::Path(path.clone()));
+                path
+            }
+            None => return Ok(()),
+        };
+
+        if let Some((predicate_path, key)) = with_key_prefix_predicate(&path) {
+            self.add_prefix_candidate(predicate_path, key, string_value);
         }
 
         Ok(())
@@ -137,12 +200,13 @@ pub fn selector_suggestions_from_value<T: ProcessValue>(
 ) -> BTreeSet<SelectorSuggestion> {
     let mut processor = GenerateSelectorsProcessor {
         selectors: BTreeSet::new(),
+        prefix_candidates: BTreeMap::new(),
     };
 
     process_value(value, &mut processor, ProcessingState::root())
         .expect("This processor is supposed to be infallible");
 
-    processor.selectors
+    processor.into_selectors()
 }
 
 #[cfg(test)]
@@ -256,4 +320,42 @@ mod tests {
           value: "123"
         "###);
     }
+
+    #[test]
+    fn test_key_predicates() {
+        let mut event = Annotated::<Event>::from_json(
+            r##"
+            {
+              "request": {
+                "headers": {
+                  "Content-Type": "text/plain",
+                  "X-Forwarded-For": "127.0.0.1",
+                  "X-Real-Ip": "127.0.0.1"
+                }
+              }
+            }
+            "##,
+        )
+        .unwrap();
+
+        // Only the prefix shared by multiple headers is suggested as predicate.
+        let selectors = selector_suggestions_from_value(&mut event);
+        insta::assert_yaml_snapshot!(selectors, @r###"
+        ---
+        - path: $string
+          value: 127.0.0.1
+        - path: $string
+          value: text/plain
+        - path: $http.headers
+          value: ~
+        - path: $http.headers.Content-Type
+          value: text/plain
+        - path: $http.headers.X-Forwarded-For
+          value: 127.0.0.1
+        - path: $http.headers.X-Real-Ip
+          value: 127.0.0.1
+        - path: "$http.headers[?key=~\"X-*\"]"
+          value: 127.0.0.1
+        "###);
+    }
 }
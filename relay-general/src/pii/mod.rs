@@ -5,23 +5,30 @@ mod builtin;
 mod compiledconfig;
 mod config;
 mod convert;
+mod encryption;
 mod generate_selectors;
 mod legacy;
 mod minidumps;
 mod processor;
 mod redactions;
 mod regexes;
+mod stats;
+mod textreports;
 mod utils;
 
 pub use self::attachments::{PiiAttachmentsProcessor, ScrubEncodings};
 pub use self::builtin::BUILTIN_RULES;
 pub use self::compiledconfig::CompiledPiiConfig;
 pub use self::config::{
-    AliasRule, MultipleRule, Pattern, PatternRule, PiiConfig, RedactPairRule, RuleSpec, RuleType,
-    Vars,
+    AliasRule, JsonStringRule, MultipleRule, Pattern, PatternRule, PiiConfig, RedactPairRule,
+    RuleSpec, RuleType, Vars,
 };
+pub use self::encryption::{decrypt_value, encrypt_value, EncryptionError, EncryptionKeyPair};
 pub use self::generate_selectors::selector_suggestions_from_value;
 pub use self::legacy::DataScrubbingConfig;
 pub use self::minidumps::ScrubMinidumpError;
 pub use self::processor::PiiProcessor;
-pub use self::redactions::{Redaction, ReplaceRedaction};
+pub use self::redactions::{
+    EncryptRedaction, HashAlgorithm, HashRedaction, Redaction, ReplaceRedaction,
+};
+pub use self::stats::{PiiStats, RedactionStats};
@@ -5,14 +5,20 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::pii::compiledconfig::RuleRef;
-use crate::pii::regexes::{get_regex_for_rule_type, PatternType, ReplaceBehavior, ANYTHING_REGEX};
+use crate::pii::regexes::{
+    get_regex_for_rule_type, validate_match, validate_value, PatternType, ReplaceBehavior,
+    ANYTHING_REGEX,
+};
 use crate::pii::utils::{hash_value, process_pairlist};
-use crate::pii::{CompiledPiiConfig, Redaction, RuleType};
+use crate::pii::{encrypt_value, CompiledPiiConfig, PiiStats, Redaction, RuleType};
 use crate::processor::{
-    process_chunked_value, Chunk, Pii, ProcessValue, ProcessingState, Processor, ValueType,
+    process_chunked_value, process_value, Chunk, FieldAttrs, Pii, PredicateValues, ProcessValue,
+    ProcessingState, Processor, ValueType,
 };
 use crate::protocol::{AsPair, IpAddr, NativeImagePath, PairList, User};
-use crate::types::{Meta, ProcessingAction, ProcessingResult, Remark, RemarkType};
+use crate::types::{
+    Annotated, Meta, ProcessingAction, ProcessingResult, Remark, RemarkType, Value,
+};
 
 // The Regex initializer needs a scope to avoid an endless loop/recursion in RustAnalyzer:
 // https://github.com/rust-analyzer/rust-analyzer/issues/5896. Note that outside of lazy_static,
@@ -27,6 +33,11 @@ lazy_static! {
 /// A processor that performs PII stripping.
 pub struct PiiProcessor<'a> {
     compiled_config: &'a CompiledPiiConfig,
+    stats: Option<PiiStats>,
+    /// Values of the items in the current path for selector predicates, indexed by depth.
+    values: Vec<PredicateValues>,
+    /// Rules applied to every string regardless of selectors.
+    string_rules: Vec<&'a RuleRef>,
 }
 
 impl<'a> PiiProcessor<'a> {
@@ -34,11 +45,70 @@ impl<'a> PiiProcessor<'a> {
     pub fn new(compiled_config: &'a CompiledPiiConfig) -> PiiProcessor<'a> {
         // this constructor needs to be cheap... a new PiiProcessor is created for each event. Move
         // any init logic into CompiledPiiConfig::new.
-        PiiProcessor { compiled_config }
+        PiiProcessor {
+            compiled_config,
+            stats: None,
+            values: Vec::new(),
+            string_rules: Vec::new(),
+        }
+    }
+
+    /// Applies the given rules to every string in addition to the rules matched by selectors.
+    ///
+    /// This is used for rules that address a JSON attachment as a whole, so that they scrub the
+    /// strings within the document instead of its serialized bytes.
+    pub(super) fn with_string_rules(mut self, rules: Vec<&'a RuleRef>) -> Self {
+        self.string_rules = rules;
+        self
+    }
+
+    /// Enables collection of statistics on the data redacted by each rule.
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(PiiStats::new());
+        self
+    }
+
+    /// Returns the statistics collected so far, if enabled with `with_stats`.
+    pub fn stats(&self) -> Option<&PiiStats> {
+        self.stats.as_ref()
+    }
+
+    /// Consumes the processor and returns the collected statistics, if enabled.
+    pub fn into_stats(self) -> Option<PiiStats> {
+        self.stats
+    }
+
+    /// Records the values of the current item for predicates on lengths and fields.
+    ///
+    /// Lengths are only read from strings and fields only from objects, and only if a selector
+    /// uses such predicates. Of an object, only the referenced fields are read and copied.
+    fn record_values<T: ProcessValue>(&mut self, value: Option<&T>, state: &ProcessingState<'_>) {
+        let lengths = self.compiled_config.predicate_lengths;
+        let fields = &self.compiled_config.predicate_fields;
+        if !lengths && fields.is_empty() {
+            return;
+        }
+
+        let value_type = state.value_type();
+        let applies = if value_type.contains(ValueType::String) {
+            lengths
+        } else {
+            !fields.is_empty() && !value_type.iter().any(is_scalar_or_array)
+        };
+
+        let values = match value {
+            Some(value) if applies => PredicateValues::new(value, fields),
+            _ => PredicateValues::default(),
+        };
+
+        let depth = state.depth();
+        self.values.truncate(depth);
+        self.values.resize_with(depth, PredicateValues::default);
+        self.values.push(values);
     }
 
     fn apply_all_rules(
-        &self,
+        &mut self,
         meta: &mut Meta,
         state: &ProcessingState<'_>,
         mut value: Option<&mut String>,
@@ -48,17 +118,55 @@ impl<'a> PiiProcessor<'a> {
             return Ok(());
         }
 
-        for (selector, rules) in self.compiled_config.applications.iter() {
-            if state.path().matches_selector(selector) {
+        let compiled_config = self.compiled_config;
+        for (selector, rules) in compiled_config.applications.iter() {
+            if state
+                .path()
+                .matches_selector_with_values(selector, &self.values)
+            {
                 for rule in rules {
-                    let reborrowed_value = value.as_deref_mut();
-                    apply_rule_to_value(meta, rule, state.path().key(), reborrowed_value)?;
+                    self.apply_rule(meta, state, rule, value.as_deref_mut())?;
                 }
             }
         }
 
+        if let Some(value) = value {
+            for index in 0..self.string_rules.len() {
+                let rule = self.string_rules[index];
+                self.apply_rule(meta, state, rule, Some(&mut *value))?;
+            }
+        }
+
         Ok(())
     }
+
+    /// Applies a single rule to a value and records its redactions in the statistics.
+    fn apply_rule(
+        &mut self,
+        meta: &mut Meta,
+        state: &ProcessingState<'_>,
+        rule: &RuleRef,
+        value: Option<&mut String>,
+    ) -> ProcessingResult {
+        // JSON string rules do not match on their own, but apply their inner rules to the parsed
+        // document. Strings that are not valid JSON are left untouched.
+        if let Some(ref inner) = rule.inner {
+            let inner_stats = value.and_then(|value| apply_json_string_rule(meta, inner, value));
+            if let (Some(stats), Some(inner_stats)) = (self.stats.as_mut(), inner_stats) {
+                stats.merge(inner_stats);
+            }
+            return Ok(());
+        }
+
+        let mut redactions = Vec::new();
+        let result = apply_rule_to_value(meta, rule, state.path().key(), value, &mut redactions);
+
+        if let Some(ref mut stats) = self.stats {
+            stats.record(&rule.origin, &redactions);
+        }
+
+        result
+    }
 }
 
 impl<'a> Processor for PiiProcessor<'a> {
@@ -68,6 +176,8 @@ impl<'a> Processor for PiiProcessor<'a> {
         meta: &mut Meta,
         state: &ProcessingState<'_>,
     ) -> ProcessingResult {
+        self.record_values(value, state);
+
         // booleans cannot be PII, and strings are handled in process_string
         if state.value_type().contains(ValueType::Boolean)
             || state.value_type().contains(ValueType::String)
@@ -173,11 +283,28 @@ impl<'a> Processor for PiiProcessor<'a> {
     }
 }
 
+/// Returns whether values of this type cannot hold fields for selector predicates.
+fn is_scalar_or_array(value_type: ValueType) -> bool {
+    match value_type {
+        ValueType::String
+        | ValueType::Binary
+        | ValueType::Number
+        | ValueType::Boolean
+        | ValueType::DateTime
+        | ValueType::Array => true,
+        _ => false,
+    }
+}
+
+/// Applies a single rule to a value.
+///
+/// Every redaction is appended to `redactions` as remark type and number of redacted bytes.
 fn apply_rule_to_value(
     meta: &mut Meta,
     rule: &RuleRef,
     key: Option<&str>,
     mut value: Option<&mut String>,
+    redactions: &mut Vec<(RemarkType, usize)>,
 ) -> ProcessingResult {
     // The rule might specify to remove or to redact. If redaction is chosen, we need to
     // chunk up the value, otherwise we need to simply mark the value for deletion.
@@ -187,15 +314,23 @@ fn apply_rule_to_value(
     // anything, we can only remove the value (not replace, hash, etc).
     if rule.ty == RuleType::Anything && (value.is_none() || !should_redact_chunks) {
         // The value is a container, @anything on a container can do nothing but delete.
+        redactions.push((RemarkType::Removed, value.as_ref().map_or(0, |v| v.len())));
         meta.add_remark(Remark::new(RemarkType::Removed, rule.origin.clone()));
         return Err(ProcessingAction::DeleteValueHard);
     }
 
+    if !value
+        .as_ref()
+        .map_or(true, |value| validate_value(&rule.ty, value))
+    {
+        return Ok(());
+    }
+
     macro_rules! apply_regex {
         ($regex:expr, $replace_behavior:expr) => {
             if let Some(ref mut value) = value {
                 process_chunked_value(value, meta, |chunks| {
-                    apply_regex_to_chunks(chunks, rule, $regex, $replace_behavior)
+                    apply_regex_to_chunks(chunks, rule, $regex, $replace_behavior, redactions)
                 });
             }
         };
@@ -210,6 +345,8 @@ fn apply_rule_to_value(
                         // @anything.
                         apply_regex!(&ANYTHING_REGEX, replace_behavior);
                     } else {
+                        redactions
+                            .push((RemarkType::Removed, value.as_ref().map_or(0, |v| v.len())));
                         meta.add_remark(Remark::new(RemarkType::Removed, rule.origin.clone()));
                         return Err(ProcessingAction::DeleteValueHard);
                     }
@@ -228,11 +365,62 @@ fn apply_rule_to_value(
     Ok(())
 }
 
+/// Parses a string containing a JSON object or array and applies the given rules to it.
+///
+/// If any rule modified the document, the value is replaced with the serialized document and the
+/// statistics of the inner rules are returned. Since the redactions cannot be located in the
+/// serialized document, they are reported in remarks with the ids of the inner rules but without
+/// ranges.
+fn apply_json_string_rule(
+    meta: &mut Meta,
+    config: &CompiledPiiConfig,
+    value: &mut String,
+) -> Option<PiiStats> {
+    if !value.trim_start().starts_with(|c| c == '{' || c == '[') {
+        return None;
+    }
+
+    let mut json = Annotated::new(serde_json::from_str::<Value>(value).ok()?);
+    let original = json.clone();
+
+    let attrs = Cow::Owned(FieldAttrs::new().pii(Pii::True));
+    let state = ProcessingState::new_root(Some(attrs), ValueType::for_field(&json));
+    let mut processor = PiiProcessor::new(config).with_stats();
+    // The PII processor never rejects values, it only deletes or modifies them.
+    process_value(&mut json, &mut processor, &state).ok();
+
+    if json.value() == original.value() {
+        return None;
+    }
+
+    let scrubbed = json.payload_to_json().ok()?;
+    let stats = processor.into_stats().unwrap_or_default();
+
+    // Ranges of earlier remarks no longer apply to the rewritten value.
+    let remarks: Vec<_> = meta
+        .iter_remarks()
+        .map(|remark| Remark::new(remark.ty(), remark.rule_id()))
+        .collect();
+    meta.clear_remarks();
+    for remark in remarks {
+        meta.add_remark(remark);
+    }
+    for (rule_id, ty, _) in stats.iter() {
+        meta.add_remark(Remark::new(ty, rule_id));
+    }
+
+    meta.set_original_length(Some(bytecount::num_chars(value.as_bytes())));
+    *value = scrubbed;
+
+    Some(stats)
+}
+
 fn apply_regex_to_chunks<'a>(
     chunks: Vec<Chunk<'a>>,
     rule: &RuleRef,
     regex: &Regex,
     replace_behavior: ReplaceBehavior,
+    redactions: &mut Vec<(RemarkType, usize)>,
 ) -> Vec<Chunk<'a>> {
     // NB: This function allocates the entire string and all chunks a second time. This means it
     // cannot reuse chunks and reallocates them. Ideally, we would be able to run the regex directly
@@ -247,7 +435,10 @@ fn apply_regex_to_chunks<'a>(
     }
 
     // Early exit if this regex does not match and return the original chunks.
-    let mut captures_iter = regex.captures_iter(&search_string).peekable();
+    let mut captures_iter = regex
+        .captures_iter(&search_string)
+        .filter(|captures| validate_match(&rule.ty, &captures[0]))
+        .peekable();
     if captures_iter.peek().is_none() {
         return chunks;
     }
@@ -297,7 +488,7 @@ fn apply_regex_to_chunks<'a>(
                                 &mut rv,
                                 &mut replacement_chunks,
                             );
-                            insert_replacement_chunks(&rule, g.as_str(), &mut rv);
+                            insert_replacement_chunks(&rule, g.as_str(), &mut rv, redactions);
                             pos = g.end();
                         }
                     }
@@ -305,7 +496,7 @@ fn apply_regex_to_chunks<'a>(
             }
             ReplaceBehavior::Value => {
                 process_text(&"", &mut rv, &mut replacement_chunks);
-                insert_replacement_chunks(&rule, &search_string, &mut rv);
+                insert_replacement_chunks(&rule, &search_string, &mut rv, redactions);
                 pos = search_string.len();
                 break;
             }
@@ -318,7 +509,12 @@ fn apply_regex_to_chunks<'a>(
     rv
 }
 
-fn insert_replacement_chunks(rule: &RuleRef, text: &str, output: &mut Vec<Chunk<'_>>) {
+fn insert_replacement_chunks(
+    rule: &RuleRef,
+    text: &str,
+    output: &mut Vec<Chunk<'_>>,
+    redactions: &mut Vec<(RemarkType, usize)>,
+) {
     match &rule.redaction {
         Redaction::Default | Redaction::Remove => {
             output.push(Chunk::Redaction {
@@ -336,11 +532,31 @@ fn insert_replacement_chunks(rule: &RuleRef, text: &str, output: &mut Vec<Chunk<
                 text: buf.into_iter().collect(),
             })
         }
-        Redaction::Hash => {
+        Redaction::Hash(hash) => {
             output.push(Chunk::Redaction {
                 ty: RemarkType::Pseudonymized,
                 rule_id: Cow::Owned(rule.origin.to_string()),
-                text: Cow::Owned(hash_value(text.as_bytes())),
+                text: Cow::Owned(hash_value(hash, text.as_bytes())),
+            });
+        }
+        Redaction::Encrypt(encrypt) => {
+            // Without a valid key, values cannot be encrypted and are removed instead.
+            let encrypted = encrypt
+                .key
+                .as_deref()
+                .and_then(|key| encrypt_value(key, text.as_bytes()).ok());
+
+            output.push(match encrypted {
+                Some(encrypted) => Chunk::Redaction {
+                    ty: RemarkType::Pseudonymized,
+                    rule_id: Cow::Owned(rule.origin.to_string()),
+                    text: Cow::Owned(encrypted),
+                },
+                None => Chunk::Redaction {
+                    ty: RemarkType::Removed,
+                    rule_id: Cow::Owned(rule.origin.to_string()),
+                    text: Cow::Borrowed(""),
+                },
             });
         }
         Redaction::Replace(replace) => {
@@ -351,17 +567,20 @@ fn insert_replacement_chunks(rule: &RuleRef, text: &str, output: &mut Vec<Chunk<
             });
         }
     }
+
+    if let Some(Chunk::Redaction { ty, .. }) = output.last() {
+        redactions.push((*ty, text.len()));
+    }
 }
 
 #[cfg(test)]
 use {
     crate::pii::PiiConfig,
-    crate::processor::process_value,
     crate::protocol::{
         Addr, DebugImage, DebugMeta, Event, ExtraValue, Headers, LogEntry, NativeDebugImage,
         Request,
     },
-    crate::types::{Annotated, Object, Value},
+    crate::types::Object,
 };
 
 #[test]
@@ -944,3 +1163,403 @@ fn test_ip_address_hashing_does_not_overwrite_id() {
 
     assert_eq!(user.id.value().unwrap().as_str(), "123");
 }
+
+#[test]
+fn test_ip_address_hashing_with_key() {
+    let config = PiiConfig::from_json(
+        r##"
+            {
+                "vars": {
+                    "hashKey": "secret"
+                },
+                "applications": {
+                    "$user.ip_address": ["@ip:hash"]
+                }
+            }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        user: Annotated::new(User {
+            ip_address: Annotated::new(IpAddr("127.0.0.1".to_string())),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let user = event.value().unwrap().user.value().unwrap();
+    assert_eq!(
+        user.id.value().unwrap().as_str(),
+        "367284D5E570A770971D4764716EC7DBE6B2FCE6"
+    );
+
+    // The key must not be exposed through debug output, which ends up in logs.
+    assert!(!format!("{:?}", config).contains("secret"));
+    assert!(!format!("{:?}", *compiled).contains("secret"));
+}
+
+#[test]
+fn test_ip_address_hashing_with_rule_key() {
+    let config = PiiConfig::from_json(
+        r##"
+            {
+                "rules": {
+                    "hash_ip": {
+                        "type": "ip",
+                        "redaction": {
+                            "method": "hash",
+                            "algorithm": "hmac_sha256",
+                            "key": "rule-key"
+                        }
+                    }
+                },
+                "vars": {
+                    "hashKey": "secret"
+                },
+                "applications": {
+                    "$user.ip_address": ["hash_ip"]
+                }
+            }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        user: Annotated::new(User {
+            ip_address: Annotated::new(IpAddr("127.0.0.1".to_string())),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let user = event.value().unwrap().user.value().unwrap();
+    assert_eq!(
+        user.id.value().unwrap().as_str(),
+        "A860922A1582A710F00AB01910C6A39433136A6173609BF738C46FA3FC988B43"
+    );
+
+    assert!(!format!("{:?}", config).contains("rule-key"));
+}
+
+#[test]
+fn test_encrypt_user_id() {
+    use crate::pii::{decrypt_value, EncryptionKeyPair};
+    use crate::protocol::LenientString;
+
+    let key_pair = EncryptionKeyPair::generate();
+    let config = PiiConfig::from_json(&format!(
+        r##"
+            {{
+                "rules": {{
+                    "encrypt_id": {{
+                        "type": "anything",
+                        "redaction": {{
+                            "method": "encrypt"
+                        }}
+                    }}
+                }},
+                "vars": {{
+                    "encryptionKey": "{}"
+                }},
+                "applications": {{
+                    "$user.id": ["encrypt_id"]
+                }}
+            }}
+        "##,
+        key_pair.public_key
+    ))
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        user: Annotated::new(User {
+            id: Annotated::new(LenientString("user-42".to_string())),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let id = &event.value().unwrap().user.value().unwrap().id;
+    let encrypted = id.value().unwrap().as_str();
+    assert!(encrypted.starts_with("enc:v1:"));
+    assert_eq!(
+        decrypt_value(&key_pair.secret_key, encrypted).unwrap(),
+        "user-42"
+    );
+
+    let remark = id.meta().iter_remarks().next().unwrap();
+    assert_eq!(remark.rule_id(), "encrypt_id");
+    assert_eq!(remark.ty(), RemarkType::Pseudonymized);
+}
+
+#[test]
+fn test_encrypt_without_key() {
+    use crate::protocol::LenientString;
+
+    let config = PiiConfig::from_json(
+        r##"
+            {
+                "rules": {
+                    "encrypt_id": {
+                        "type": "anything",
+                        "redaction": {
+                            "method": "encrypt"
+                        }
+                    }
+                },
+                "applications": {
+                    "$user.id": ["encrypt_id"]
+                }
+            }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        user: Annotated::new(User {
+            id: Annotated::new(LenientString("user-42".to_string())),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    // Values must never be leaked if they cannot be encrypted.
+    let id = &event.value().unwrap().user.value().unwrap().id;
+    assert_eq!(id.value().unwrap().as_str(), "");
+    assert_eq!(
+        id.meta().iter_remarks().next().unwrap().ty(),
+        RemarkType::Removed
+    );
+}
+
+#[test]
+fn test_json_string() {
+    use crate::pii::RedactionStats;
+
+    let config = PiiConfig::from_json(
+        r##"
+            {
+                "rules": {
+                    "embedded_json": {
+                        "type": "json_string",
+                        "rules": ["@password", "embedded_json"]
+                    }
+                },
+                "applications": {
+                    "$string": ["embedded_json"]
+                }
+            }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        extra: {
+            let mut map = Object::new();
+            map.insert(
+                "payload".to_string(),
+                Annotated::new(ExtraValue(Value::String(
+                    r#"{"password":"hunter2","user":"jane"}"#.to_string(),
+                ))),
+            );
+            map.insert(
+                "message".to_string(),
+                Annotated::new(ExtraValue(Value::String("{password: hunter2}".to_string()))),
+            );
+            Annotated::new(map)
+        },
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled).with_stats();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let extra = event.value().unwrap().extra.value().unwrap();
+
+    let payload = extra.get("payload").unwrap();
+    assert_eq!(
+        payload.value().unwrap().0.as_str(),
+        Some(r#"{"password":null,"user":"jane"}"#)
+    );
+    // Remarks and statistics refer to the inner rule and the data it redacted.
+    let remarks: Vec<_> = payload.meta().iter_remarks().collect();
+    assert_eq!(
+        remarks,
+        vec![&Remark::new(RemarkType::Removed, "@password")]
+    );
+    assert_eq!(payload.meta().original_length(), Some(36));
+
+    let stats: Vec<_> = processor.stats().unwrap().iter().collect();
+    assert_eq!(
+        stats,
+        vec![(
+            "@password",
+            RemarkType::Removed,
+            RedactionStats {
+                fields: 1,
+                bytes: 7
+            }
+        )]
+    );
+
+    // Strings that are not valid JSON are left untouched.
+    let message = extra.get("message").unwrap();
+    assert_eq!(
+        message.value().unwrap().0.as_str(),
+        Some("{password: hunter2}")
+    );
+}
+
+#[test]
+fn test_length_predicate() {
+    let config =
+        PiiConfig::from_json(r#"{"applications": {"extra[?length>=8]": ["@anything:replace"]}}"#)
+            .unwrap();
+
+    let mut event =
+        Annotated::<Event>::from_json(r#"{"extra": {"short": "abc", "long": "abcdefghij"}}"#)
+            .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let extra = event.value().unwrap().extra.value().unwrap();
+    let short = extra.get("short").unwrap();
+    assert_eq!(short.value().unwrap().0.as_str(), Some("abc"));
+    let long = extra.get("long").unwrap();
+    assert_eq!(long.value().unwrap().0.as_str(), Some("[Filtered]"));
+}
+
+#[test]
+fn test_field_predicate() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "exception.values[?type==\"ValueError\"].value": ["@anything:remove"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::<Event>::from_json(
+        r#"{
+            "exception": {
+                "values": [
+                    {"type": "ValueError", "value": "secret"},
+                    {"type": "KeyError", "value": "secret"}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let exceptions = event.value().unwrap().exception.value().unwrap();
+    let values = exceptions.values.value().unwrap();
+    assert!(values[0].value().unwrap().value.value().is_none());
+    assert!(values[1].value().unwrap().value.value().is_some());
+}
+
+#[test]
+fn test_stats() {
+    use crate::pii::RedactionStats;
+
+    let config = PiiConfig::from_json(
+        r##"
+            {
+                "applications": {
+                    "$string": ["@email:mask", "@ip"],
+                    "password": ["@anything:remove"]
+                }
+            }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        logentry: Annotated::new(LogEntry {
+            formatted: Annotated::new(
+                "jane@example.org logged in from 127.0.0.1"
+                    .to_string()
+                    .into(),
+            ),
+            ..Default::default()
+        }),
+        extra: {
+            let mut map = Object::new();
+            map.insert(
+                "password".to_string(),
+                Annotated::new(ExtraValue(Value::String("hunter2".to_string()))),
+            );
+            Annotated::new(map)
+        },
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled).with_stats();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let stats = processor.into_stats().unwrap();
+    let collected: Vec<_> = stats.iter().collect();
+    assert_eq!(
+        collected,
+        vec![
+            (
+                "@anything:remove",
+                RemarkType::Removed,
+                RedactionStats {
+                    fields: 1,
+                    bytes: 7
+                }
+            ),
+            (
+                "@email:mask",
+                RemarkType::Masked,
+                RedactionStats {
+                    fields: 1,
+                    bytes: 16
+                }
+            ),
+            (
+                "@ip",
+                RemarkType::Substituted,
+                RedactionStats {
+                    fields: 1,
+                    bytes: 9
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_stats_disabled() {
+    let config = PiiConfig::from_json(r#"{"applications": {"$string": ["@ip"]}}"#).unwrap();
+    let compiled = config.compiled();
+    let processor = PiiProcessor::new(&compiled);
+    assert!(processor.stats().is_none());
+}
@@ -1,4 +1,6 @@
 //! Redactions for rules.
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 fn default_replace_text() -> String {
@@ -28,6 +30,68 @@ impl Default for ReplaceRedaction {
     }
 }
 
+/// The algorithm used to pseudonymize values in [`Redaction::Hash`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    /// HMAC with SHA-1.
+    HmacSha1,
+    /// HMAC with SHA-256.
+    HmacSha256,
+}
+
+impl HashAlgorithm {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for HashAlgorithm {
+    fn default() -> Self {
+        HashAlgorithm::HmacSha1
+    }
+}
+
+/// Replaces a value with a keyed hash.
+#[derive(Serialize, Deserialize, Clone, Default, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HashRedaction {
+    /// The hash algorithm, defaults to HMAC-SHA1.
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_default")]
+    pub algorithm: HashAlgorithm,
+    /// The secret key for the HMAC.
+    ///
+    /// If not set, the `hashKey` from the PII config's `vars` is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl fmt::Debug for HashRedaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never leak the secret key into logs.
+        f.debug_struct("HashRedaction")
+            .field("algorithm", &self.algorithm)
+            .field("key", &self.key.as_ref().map(|_| "[redacted]"))
+            .finish()
+    }
+}
+
+/// Replaces a value with its encryption for a public key.
+///
+/// Encrypted values can only be recovered with the corresponding secret key. See
+/// [`decrypt_value`](crate::pii::decrypt_value) for the format of encrypted values. Attachments
+/// are scrubbed in place and cannot fit encrypted values, so matches are removed there instead.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptRedaction {
+    /// The X25519 public key to encrypt values for, as URL-safe base64 string.
+    ///
+    /// If not set, the `encryptionKey` from the PII config's `vars` is used. Without a valid key,
+    /// values are removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
 /// Defines how replacements happen.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(tag = "method", rename_all = "snake_case")]
@@ -44,8 +108,10 @@ pub enum Redaction {
     Replace(ReplaceRedaction),
     /// Overwrites the matched value by masking.
     Mask,
-    /// Replaces the value with a hash
-    Hash,
+    /// Replaces the value with a keyed hash.
+    Hash(HashRedaction),
+    /// Replaces the value with its encryption for a public key.
+    Encrypt(EncryptRedaction),
 }
 
 impl Default for Redaction {
@@ -87,12 +87,106 @@ pub fn get_regex_for_rule_type(
         RuleType::UrlAuth => smallvec![(v, &*URL_AUTH_REGEX, ReplaceBehavior::replace_group(1))],
         RuleType::UsSsn => smallvec![(v, &*US_SSN_REGEX, ReplaceBehavior::replace_match())],
         RuleType::Userpath => smallvec![(v, &*PATH_REGEX, ReplaceBehavior::replace_group(1))],
+        RuleType::Iban => smallvec![(v, &*IBAN_REGEX, ReplaceBehavior::replace_match())],
+        RuleType::Phone => smallvec![(v, &*PHONE_REGEX, ReplaceBehavior::replace_group(1))],
+        RuleType::UkNino => smallvec![(v, &*UK_NINO_REGEX, ReplaceBehavior::replace_match())],
+        RuleType::DeTaxId => smallvec![(v, &*DE_TAX_ID_REGEX, ReplaceBehavior::replace_match())],
+        RuleType::Jwt => smallvec![(v, &*JWT_REGEX, ReplaceBehavior::replace_match())],
+        RuleType::Bearer => smallvec![(v, &*BEARER_REGEX, ReplaceBehavior::replace_group(1))],
+        RuleType::SqlLiterals => {
+            smallvec![(v, &*SQL_LITERALS_REGEX, ReplaceBehavior::replace_match())]
+        }
 
         // These ought to have been resolved in CompiledConfig
         RuleType::Alias(_) | RuleType::Multiple(_) => smallvec![],
+
+        // Applied by parsing the value in PiiProcessor
+        RuleType::JsonString(_) => smallvec![],
+    }
+}
+
+/// Checks whether rules of the given type apply to a value at all.
+///
+/// SQL literals are only replaced in values that look like SQL statements, so that applying the
+/// rule to broad selectors such as `$string` does not mangle ordinary text. All other rule types
+/// apply to every value.
+pub fn validate_value(ty: &RuleType, value: &str) -> bool {
+    match ty {
+        RuleType::SqlLiterals => SQL_STATEMENT_REGEX.is_match(value),
+        _ => true,
+    }
+}
+
+/// Checks whether a regex match of the given rule type is an actual match.
+///
+/// Some identifiers carry a checksum that cannot be verified with a regular expression. Matches
+/// with an invalid checksum are skipped to limit false positives. Rule types without additional
+/// validation accept all matches.
+pub fn validate_match(ty: &RuleType, text: &str) -> bool {
+    match ty {
+        RuleType::Iban => is_valid_iban(text),
+        RuleType::UkNino => is_valid_uk_nino(text),
+        RuleType::DeTaxId => is_valid_de_tax_id(text),
+        _ => true,
     }
 }
 
+/// Validates the ISO 7064 MOD 97-10 checksum of an IBAN.
+fn is_valid_iban(text: &str) -> bool {
+    let iban: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if iban.len() < 15 || iban.len() > 34 {
+        return false;
+    }
+
+    // The country code and check digits are moved to the end, and letters are expanded to two
+    // digits each, starting at `A = 10`.
+    let (head, tail) = iban.split_at(4);
+    let mut remainder = 0;
+    for c in tail.iter().chain(head) {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+
+    remainder == 1
+}
+
+/// Validates the prefix of a UK National Insurance number.
+///
+/// Prefixes that are not allocated are already excluded by the regex, except for combinations of
+/// otherwise valid letters.
+fn is_valid_uk_nino(text: &str) -> bool {
+    let prefix = text.get(..2).unwrap_or_default();
+    !matches!(prefix, "BG" | "GB" | "KN" | "NK" | "NT" | "TN" | "ZZ")
+}
+
+/// Validates the ISO 7064 MOD 11-10 check digit of a German tax identification number.
+fn is_valid_de_tax_id(text: &str) -> bool {
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() != 11 {
+        return false;
+    }
+
+    let mut product = 10;
+    for digit in &digits[..10] {
+        let mut sum = (digit + product) % 10;
+        if sum == 0 {
+            sum = 10;
+        }
+        product = (sum * 2) % 11;
+    }
+
+    let check = (11 - product) % 10;
+    check == digits[10]
+}
+
 #[rustfmt::skip]
 macro_rules! ip {
     (v4s) => { "(25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)" };
@@ -228,6 +322,77 @@ lazy_static! {
             )\b
         "#
     ).unwrap();
+    static ref IBAN_REGEX: Regex = Regex::new(
+        r#"(?x)
+            \b
+                [A-Z]{2}\d{2}               # country code and check digits
+                (?:\ ?[A-Z0-9]{4}){2,7}     # account number in groups of four
+                (?:\ ?[A-Z0-9]{1,3})?
+            \b
+        "#
+    ).unwrap();
+    static ref PHONE_REGEX: Regex = Regex::new(
+        r#"(?x)
+            (?:^|[^\w+])
+            (
+                \+[1-9]                     # country code
+                (?:[\ .-]?\d){6,14}         # up to 15 digits in total
+            )
+            \b
+        "#
+    ).unwrap();
+    static ref UK_NINO_REGEX: Regex = Regex::new(
+        r#"(?x)
+            \b
+                [A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z]
+                \ ?\d{2}\ ?\d{2}\ ?\d{2}
+                \ ?[A-D]
+            \b
+        "#
+    ).unwrap();
+    static ref DE_TAX_ID_REGEX: Regex = Regex::new(
+        r#"(?x)
+            \b
+                [1-9]\d
+                (?:\ ?\d{3}){3}
+            \b
+        "#
+    ).unwrap();
+    static ref JWT_REGEX: Regex = Regex::new(
+        r#"(?x)
+            \b
+                eyJ[a-zA-Z0-9_-]+\.         # header, always a JSON object
+                eyJ[a-zA-Z0-9_-]+\.         # payload, always a JSON object
+                [a-zA-Z0-9_-]*              # signature, empty for unsecured tokens
+        "#
+    ).unwrap();
+    static ref BEARER_REGEX: Regex = Regex::new(
+        r#"(?ix)
+            \bbearer\s+
+            ([a-z0-9._~+/-]+=*)
+        "#
+    ).unwrap();
+    static ref SQL_STATEMENT_REGEX: Regex = Regex::new(
+        r#"(?isx)
+            ^\s*
+            (?:
+                select\b.*\bfrom\b
+                | with\b.*\bselect\b
+                | insert\s+into\b
+                | update\b.*\bset\b
+                | delete\s+from\b
+            )
+        "#
+    ).unwrap();
+    static ref SQL_LITERALS_REGEX: Regex = Regex::new(
+        r#"(?x)
+            # quoted strings, with escaped or doubled quotes
+            '(?:[^'\\]|\\.|'')*'
+            |
+            # integers and decimals that are not part of an identifier
+            \b\d+(?:\.\d+)?\b
+        "#
+    ).unwrap();
     static ref PASSWORD_KEY_REGEX: Regex = Regex::new(
         r"(?i)(password|secret|passwd|api_key|apikey|access_token|auth|credentials|mysql_pwd|stripetoken)"
     ).unwrap();
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::types::RemarkType;
+
+/// Statistics on the values redacted by a single rule with a single redaction method.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct RedactionStats {
+    /// The number of fields modified by the rule.
+    pub fields: u64,
+    /// The number of bytes of original data that were redacted.
+    pub bytes: u64,
+}
+
+/// Statistics on the data scrubbed by a `PiiProcessor`.
+///
+/// Statistics are grouped by the ID of the rule that is reported in remarks and by the type of
+/// remark, which corresponds to the redaction method. Serialized, this is a nested map using the
+/// same remark type codes as `_meta`:
+///
+/// ```json
+/// {"@ip": {"s": {"fields": 1, "bytes": 9}}}
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct PiiStats(BTreeMap<String, BTreeMap<RemarkType, RedactionStats>>);
+
+impl PiiStats {
+    /// Creates empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no data has been redacted.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the statistics of all rules and remark types.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, RemarkType, RedactionStats)> {
+        self.0.iter().flat_map(|(rule_id, types)| {
+            types
+                .iter()
+                .map(move |(ty, stats)| (rule_id.as_str(), *ty, *stats))
+        })
+    }
+
+    /// Records the redactions that a rule applied to a single field.
+    ///
+    /// Each redaction is given as remark type and the number of redacted bytes. The field is
+    /// counted once for every distinct remark type.
+    pub(crate) fn record(&mut self, rule_id: &str, redactions: &[(RemarkType, usize)]) {
+        if redactions.is_empty() {
+            return;
+        }
+
+        let types = self.0.entry(rule_id.to_owned()).or_default();
+        let mut counted = Vec::with_capacity(1);
+
+        for &(ty, bytes) in redactions {
+            let stats = types.entry(ty).or_default();
+            stats.bytes += bytes as u64;
+
+            if !counted.contains(&ty) {
+                stats.fields += 1;
+                counted.push(ty);
+            }
+        }
+    }
+
+    /// Adds the statistics of another processor, such as one that scrubbed an embedded document.
+    pub(crate) fn merge(&mut self, other: PiiStats) {
+        for (rule_id, types) in other.0 {
+            let merged = self.0.entry(rule_id).or_default();
+            for (ty, stats) in types {
+                let merged = merged.entry(ty).or_default();
+                merged.fields += stats.fields;
+                merged.bytes += stats.bytes;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut stats = PiiStats::new();
+        assert!(stats.is_empty());
+
+        stats.record(
+            "@ip",
+            &[(RemarkType::Substituted, 9), (RemarkType::Substituted, 11)],
+        );
+        stats.record("@ip", &[(RemarkType::Substituted, 9)]);
+        stats.record("@email", &[]);
+
+        let collected: Vec<_> = stats.iter().collect();
+        assert_eq!(
+            collected,
+            vec![(
+                "@ip",
+                RemarkType::Substituted,
+                RedactionStats {
+                    fields: 2,
+                    bytes: 29
+                }
+            )]
+        );
+
+        assert_eq!(
+            serde_json::to_string(&stats).unwrap(),
+            r#"{"@ip":{"s":{"fields":2,"bytes":29}}}"#
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Scrubbing of line-oriented text attachments.
+//!
+//! Apple crash reports and Unreal Engine logs are plain text, but they also contain information
+//! that is required for symbolication and must therefore survive scrubbing, such as instruction
+//! addresses and image UUIDs. The scrubbers in this module understand the structure of these
+//! formats and only apply PII rules to those parts of a line that can contain PII.
+
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::pii::{PiiAttachmentsProcessor, ScrubEncodings};
+use crate::processor::{FieldAttrs, Pii, ProcessingState, ValueType};
+
+lazy_static! {
+    /// The `Path` header of an Apple crash report, capturing the executable path.
+    static ref PATH_HEADER_RE: Regex = Regex::new(r"^Path:\s*(/.*?)\s*$").unwrap();
+
+    /// The header of a register dump in an Apple crash report.
+    static ref THREAD_STATE_RE: Regex = Regex::new(r"^Thread \d+ crashed with .*Thread State").unwrap();
+
+    /// A frame in a thread backtrace, up to and including the instruction address:
+    ///
+    /// `0   libsystem_kernel.dylib        0x00007fff6a1b72c2 __pthread_kill + 10`
+    static ref FRAME_RE: Regex = Regex::new(r"^\d+\s+.+?\s+0x[0-9a-fA-F]+").unwrap();
+
+    /// An image in the binary images section, capturing the image path:
+    ///
+    /// `0x10a3f2000 - 0x10a3f5fff +MyApp x86_64 (1.0 - 1) <6b8f6b8f...> /path/to/MyApp`
+    static ref IMAGE_RE: Regex =
+        Regex::new(r"^\s*0x[0-9a-fA-F]+\s*-\s*0x[0-9a-fA-F]+\s[^/]*(/.*?)\s*$").unwrap();
+
+    /// The prefix of an Unreal Engine log line, including timestamp, frame, category and verbosity:
+    ///
+    /// `[2018.10.29-16.56.38:332][  0]LogInit: Display: `
+    static ref UNREAL_LOG_PREFIX_RE: Regex = Regex::new(
+        r"^\[[0-9.:-]+\]\[\s*\d+\]\w+:\s*(?:(?:Fatal|Error|Warning|Display|Log|Verbose|VeryVerbose):\s*)?"
+    )
+    .unwrap();
+}
+
+/// The section of an Apple crash report that is currently being scrubbed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Section {
+    /// Headers, exception information and thread backtraces.
+    Text,
+    /// Register values of the crashing thread.
+    ThreadState,
+    /// The list of loaded images.
+    BinaryImages,
+}
+
+/// Returns `true` if the line contains only whitespace.
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace)
+}
+
+/// Returns `true` if the data starts with a UTF-16LE byte order mark.
+fn is_utf16le(data: &[u8]) -> bool {
+    data.starts_with(&[0xff, 0xfe])
+}
+
+impl<'a> PiiAttachmentsProcessor<'a> {
+    /// Scrubs an image path, preserving its basename.
+    fn scrub_image_path(&self, path: &mut [u8], state: &ProcessingState<'_>) -> bool {
+        match std::str::from_utf8_mut(path) {
+            Ok(path) => self.scrub_utf8_filepath(path, state),
+            Err(_) => false,
+        }
+    }
+
+    /// Applies PII rules to an Apple crash report.
+    ///
+    /// Image paths in the binary images section and the `Path` header are scrubbed as
+    /// `$attachments.<filename>.code_file`, preserving the basename of the image. In thread
+    /// backtraces, only the symbol after the instruction address is scrubbed, and register values
+    /// are never scrubbed. All other lines are scrubbed as plain text.
+    ///
+    /// Returns `true`, if the crash report was modified.
+    pub fn scrub_apple_crash_report(&self, filename: &str, data: &mut [u8]) -> bool {
+        if is_utf16le(data) {
+            return self.scrub_encoded_attachment(filename, data, ScrubEncodings::Utf16Le);
+        }
+
+        let file_state = self.state(filename, ValueType::Binary);
+        // Mirrors decisions made on NativeImagePath type
+        let attrs = Cow::Owned(FieldAttrs::new().pii(Pii::True));
+        let path_state = file_state.enter_static("code_file", Some(attrs), Some(ValueType::String));
+
+        let mut section = Section::Text;
+        let mut changed = false;
+
+        for line in data.split_mut(|byte| *byte == b'\n') {
+            if is_blank(line) {
+                section = Section::Text;
+                continue;
+            } else if line.starts_with(b"Binary Images:") {
+                section = Section::BinaryImages;
+                continue;
+            } else if THREAD_STATE_RE.is_match(line) {
+                section = Section::ThreadState;
+                continue;
+            }
+
+            match section {
+                Section::ThreadState => (),
+                Section::BinaryImages => {
+                    let path = IMAGE_RE
+                        .captures(line)
+                        .and_then(|captures| captures.get(1))
+                        .map(|path| path.range());
+
+                    if let Some(range) = path {
+                        changed |= self.scrub_image_path(&mut line[range], &path_state);
+                    }
+                }
+                Section::Text => {
+                    let path = PATH_HEADER_RE
+                        .captures(line)
+                        .and_then(|captures| captures.get(1))
+                        .map(|path| path.range());
+
+                    if let Some(range) = path {
+                        changed |= self.scrub_image_path(&mut line[range], &path_state);
+                        continue;
+                    }
+
+                    // Keep the frame index, image name and instruction address of frames intact.
+                    let start = FRAME_RE.find(line).map_or(0, |frame| frame.end());
+                    changed |=
+                        self.scrub_bytes(&mut line[start..], &file_state, ScrubEncodings::Utf8);
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Applies PII rules to an Unreal Engine log file.
+    ///
+    /// Only the messages of log lines are scrubbed. The timestamp, frame counter, category and
+    /// verbosity of each line are preserved so that the log can still be parsed into breadcrumbs.
+    ///
+    /// Returns `true`, if the log file was modified.
+    pub fn scrub_unreal_logs(&self, filename: &str, data: &mut [u8]) -> bool {
+        if is_utf16le(data) {
+            return self.scrub_encoded_attachment(filename, data, ScrubEncodings::Utf16Le);
+        }
+
+        let file_state = self.state(filename, ValueType::Binary);
+        let mut changed = false;
+
+        for line in data.split_mut(|byte| *byte == b'\n') {
+            let start = UNREAL_LOG_PREFIX_RE
+                .find(line)
+                .map_or(0, |prefix| prefix.end());
+            changed |= self.scrub_bytes(&mut line[start..], &file_state, ScrubEncodings::Utf8);
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pii::PiiConfig;
+
+    use super::*;
+
+    fn scrub_crash_report(selector: &str, rule: &str, input: &str) -> String {
+        let config = PiiConfig::from_json(&format!(
+            r#"{{"applications": {{"{}": ["{}"]}}}}"#,
+            selector, rule
+        ))
+        .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let mut data = input.as_bytes().to_vec();
+        processor.scrub_apple_crash_report("crash.txt", &mut data);
+        String::from_utf8(data).unwrap()
+    }
+
+    const CRASH_REPORT: &str = r#"Incident Identifier: 5F6F6C8D-5D0A-4A5B-8F0C-2B6C3A0E6A3E
+Process:               MyApp [1234]
+Path:                  /Users/jane/Applications/MyApp.app/Contents/MacOS/MyApp
+Crashed on behalf of jane@example.org
+
+Thread 0 Crashed:
+0   libsystem_kernel.dylib        	0x00007fff6a1b72c2 __pthread_kill + 10
+1   MyApp                         	0x000000010a3f3a3c main (/Users/jane/src/main.c:12)
+
+Thread 0 crashed with X86 Thread State (64-bit):
+  rax: 0x0000000000000000  rbx: 0x00007fff9e4c1380
+
+Binary Images:
+       0x10a3f2000 -        0x10a3f5fff +MyApp x86_64 (1.0 - 1) <6b8f6b8f-8c2e-3b6b-9f0f-3f0e2c1a8b7d> /Users/jane/Applications/MyApp.app/Contents/MacOS/MyApp
+    0x7fff6a1a0000 -     0x7fff6a1ccff7  libsystem_kernel.dylib (6153.141.1) <1a2b3c4d-5e6f-7a8b-9c0d-1e2f3a4b5c6d> /usr/lib/system/libsystem_kernel.dylib
+"#;
+
+    #[test]
+    fn test_apple_crash_report_paths() {
+        let scrubbed = scrub_crash_report("$attachments.**", "@userpath:mask", CRASH_REPORT);
+
+        // Image paths are scrubbed, but keep their basename, UUIDs and addresses.
+        assert!(scrubbed.contains(
+            "<6b8f6b8f-8c2e-3b6b-9f0f-3f0e2c1a8b7d> /Users/****/Applications/MyApp.app/Contents/MacOS/MyApp"
+        ));
+        assert!(scrubbed.contains("Path:                  /Users/****/Applications/"));
+        assert!(scrubbed.contains("/usr/lib/system/libsystem_kernel.dylib"));
+
+        // Symbols after the instruction address are scrubbed.
+        assert!(scrubbed.contains("0x000000010a3f3a3c main (/Users/****/src/main.c:12)"));
+    }
+
+    #[test]
+    fn test_apple_crash_report_text() {
+        let scrubbed = scrub_crash_report("$attachments.'crash.txt'", "@email:mask", CRASH_REPORT);
+        assert!(scrubbed.contains("Crashed on behalf of ****************"));
+    }
+
+    #[test]
+    fn test_apple_crash_report_keeps_addresses() {
+        let scrubbed = scrub_crash_report("$attachments.**", "@anything:mask", CRASH_REPORT);
+
+        assert!(scrubbed.contains("0   libsystem_kernel.dylib        \t0x00007fff6a1b72c2****"));
+        assert!(scrubbed.contains("  rax: 0x0000000000000000  rbx: 0x00007fff9e4c1380"));
+        assert!(scrubbed.contains(
+            "       0x10a3f2000 -        0x10a3f5fff +MyApp x86_64 (1.0 - 1) <6b8f6b8f-8c2e-3b6b-9f0f-3f0e2c1a8b7d> ****"
+        ));
+    }
+
+    #[test]
+    fn test_unreal_logs() {
+        let config = PiiConfig::from_json(
+            r#"{"applications": {"$attachments.'UE4Minidump.log'": ["@userpath:mask"]}}"#,
+        )
+        .unwrap();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+
+        let mut data = b"Log file open, 10/29/18 17:56:37\n\
+            [2018.10.29-16.56.38:332][  0]LogInit: Display: Base directory: C:/Users/jane/Game/\n"
+            .to_vec();
+
+        assert!(processor.scrub_unreal_logs("UE4Minidump.log", &mut data));
+        assert_eq!(
+            std::str::from_utf8(&data).unwrap(),
+            "Log file open, 10/29/18 17:56:37\n\
+            [2018.10.29-16.56.38:332][  0]LogInit: Display: Base directory: C:/Users/****/Game/\n"
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use hmac::{Hmac, Mac};
 use sha1::Sha1;
+use sha2::Sha256;
 
+use crate::pii::{HashAlgorithm, HashRedaction};
 use crate::processor::{process_value, ProcessValue, ProcessingState, Processor, ValueType};
 use crate::protocol::{AsPair, PairList};
 use crate::types::ProcessingResult;
@@ -45,8 +47,22 @@ pub fn process_pairlist<P: Processor, T: ProcessValue + AsPair>(
     Ok(())
 }
 
-pub fn hash_value(data: &[u8]) -> String {
-    let mut mac = Hmac::<Sha1>::new_varkey(&[]).unwrap();
-    mac.input(data);
-    format!("{:X}", mac.result().code())
+/// Computes a keyed hash of the given data as uppercase hex string.
+///
+/// Without a key, the HMAC is computed with an empty key.
+pub fn hash_value(redaction: &HashRedaction, data: &[u8]) -> String {
+    let key = redaction.key.as_deref().unwrap_or_default().as_bytes();
+
+    match redaction.algorithm {
+        HashAlgorithm::HmacSha1 => {
+            let mut mac = Hmac::<Sha1>::new_varkey(key).unwrap();
+            mac.input(data);
+            format!("{:X}", mac.result().code())
+        }
+        HashAlgorithm::HmacSha256 => {
+            let mut mac = Hmac::<Sha256>::new_varkey(key).unwrap();
+            mac.input(data);
+            format!("{:X}", mac.result().code())
+        }
+    }
 }
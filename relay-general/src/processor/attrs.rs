@@ -6,7 +6,7 @@ use enumset::{EnumSet, EnumSetType};
 use failure::Fail;
 use smallvec::SmallVec;
 
-use crate::processor::{ProcessValue, SelectorPathItem, SelectorSpec};
+use crate::processor::{PredicateValues, ProcessValue, SelectorPathItem, SelectorSpec};
 use crate::types::Annotated;
 
 /// Error for unknown value types.
@@ -583,6 +583,18 @@ impl<'a> Path<'a> {
     /// This walks both the selector and the path starting at the end and towards the root
     /// to determine if the selector matches the current path.
     pub fn matches_selector(&self, selector: &SelectorSpec) -> bool {
+        self.matches_selector_with_values(selector, &[])
+    }
+
+    /// Checks if a path matches given selector, evaluating predicates against item values.
+    ///
+    /// `values` contains the [`PredicateValues`] of the items in this path, indexed by their
+    /// depth. Predicates on lengths and fields never match items without values.
+    pub fn matches_selector_with_values(
+        &self,
+        selector: &SelectorSpec,
+        values: &[PredicateValues],
+    ) -> bool {
         let pii = self.0.attrs().pii;
         if pii == Pii::False {
             return false;
@@ -602,7 +614,7 @@ impl<'a> Path<'a> {
                 for state in &mut state_iter {
                     match selector_iter.next() {
                         Some((i, path_item)) => {
-                            if !path_item.matches_state(pii, i, state) {
+                            if !path_item.matches_state(pii, i, state, values) {
                                 return false;
                             }
 
@@ -628,7 +640,7 @@ impl<'a> Path<'a> {
                     None => return !remaining_states.is_empty(),
                 };
                 let mut path_match_iterator = remaining_states.iter().rev().skip_while(|state| {
-                    !first_selector_path.matches_state(pii, first_selector_i, state)
+                    !first_selector_path.matches_state(pii, first_selector_i, state, values)
                 });
                 if path_match_iterator.next().is_none() {
                     return false;
@@ -637,12 +649,18 @@ impl<'a> Path<'a> {
                 // then we check all remaining items and that nothing is left of the selector
                 path_match_iterator
                     .zip(&mut selector_iter)
-                    .all(|(state, (i, selector_path))| selector_path.matches_state(pii, i, state))
+                    .all(|(state, (i, selector_path))| {
+                        selector_path.matches_state(pii, i, state, values)
+                    })
                     && selector_iter.next().is_none()
             }
-            SelectorSpec::And(ref xs) => xs.iter().all(|x| self.matches_selector(x)),
-            SelectorSpec::Or(ref xs) => xs.iter().any(|x| self.matches_selector(x)),
-            SelectorSpec::Not(ref x) => !self.matches_selector(x),
+            SelectorSpec::And(ref xs) => xs
+                .iter()
+                .all(|x| self.matches_selector_with_values(x, values)),
+            SelectorSpec::Or(ref xs) => xs
+                .iter()
+                .any(|x| self.matches_selector_with_values(x, values)),
+            SelectorSpec::Not(ref x) => !self.matches_selector_with_values(x, values),
         }
     }
 }
@@ -807,6 +825,39 @@ mod tests {
         assert_not_matches!(foo_state, "($object & $object.*)",);
     }
 
+    #[test]
+    fn test_predicate_matching() {
+        let pii = Some(Cow::Borrowed(&PII_TRUE_FIELD_ATTRS));
+        let event_state = ProcessingState::new_root(None, Some(ValueType::Event)); // .
+        let request_state = event_state.enter_static("request", None, Some(ValueType::Request)); // .request
+        let headers_state = request_state.enter_static("headers", None, Some(ValueType::Object)); // .request.headers
+        let header_state =
+            headers_state.enter_static("X-Forwarded-For", pii.clone(), Some(ValueType::String)); // .request.headers.X-Forwarded-For
+        let index_state = headers_state.enter_index(0, pii, None); // .request.headers.0
+
+        assert_matches_pii_maybe!(
+            header_state,
+            r#"$http.headers[?key=~"X-*"]"#,
+            r#"request.headers[?key=="x-forwarded-for"]"#,
+            r#"$http.headers[?key!~"Content-*"]"#,
+            r#"$http.headers[?key!="Cookie"]"#,
+        );
+
+        assert_not_matches!(
+            header_state,
+            r#"$http.headers[?key!~"x-*"]"#,
+            r#"$http.headers[?key=="X-Forwarded"]"#,
+            r#"$http[?key=~"X-*"]"#,
+        );
+
+        // Predicates never match items without a key.
+        assert_not_matches!(
+            index_state,
+            r#"$http.headers[?key=~"*"]"#,
+            r#"$http.headers[?key!~"X-*"]"#,
+        );
+    }
+
     #[test]
     fn test_attachments_matching() {
         let event_state = ProcessingState::new_root(None, None);
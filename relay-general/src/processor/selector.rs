@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::str::FromStr;
 
@@ -5,8 +7,11 @@ use failure::Fail;
 use pest::error::Error;
 use pest::iterators::Pair;
 use pest::Parser;
+use regex::Regex;
+use serde::ser::{self, Serialize};
 
 use crate::processor::{Pii, ProcessingState, ValueType};
+use crate::types::{IntoValue, SkipSerialization};
 
 /// Error for invalid selectors
 #[derive(Debug, Fail)]
@@ -26,6 +31,9 @@ pub enum InvalidSelectorError {
     #[fail(display = "invalid selector: unknown value")]
     UnknownType,
 
+    #[fail(display = "invalid selector: invalid predicate pattern")]
+    InvalidPredicate,
+
     #[fail(display = "parser bug: consumed {} (expected {})", _0, _1)]
     UnexpectedToken(String, &'static str),
 }
@@ -41,6 +49,620 @@ mod parser {
 
 use self::parser::{Rule, SelectorParser};
 
+/// The part of an item that a [`SelectorPredicate`] is evaluated against.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum PredicateField {
+    /// `key`: The key of the item within its parent.
+    Key,
+    /// `length`: The number of characters of a string item.
+    Length,
+    /// Any other name: The string value of the field with this name in an object item.
+    Field(String),
+}
+
+impl fmt::Display for PredicateField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PredicateField::Key => write!(f, "key"),
+            PredicateField::Length => write!(f, "length"),
+            PredicateField::Field(ref name) => {
+                if key_needs_quoting(name) || name == "key" || name == "length" {
+                    write!(f, "'{}'", name.replace("'", "''"))
+                } else {
+                    write!(f, "{}", name)
+                }
+            }
+        }
+    }
+}
+
+/// The operator of a [`SelectorPredicate`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum PredicateOperator {
+    /// `==`: The key is equal to the value, ignoring case.
+    Equal,
+    /// `!=`: The key is not equal to the value, ignoring case.
+    NotEqual,
+    /// `=~`: The key matches the glob pattern, ignoring case.
+    Matches,
+    /// `!~`: The key does not match the glob pattern, ignoring case.
+    NotMatches,
+    /// `<`: The length is less than the value.
+    Less,
+    /// `<=`: The length is less than or equal to the value.
+    LessOrEqual,
+    /// `>`: The length is greater than the value.
+    Greater,
+    /// `>=`: The length is greater than or equal to the value.
+    GreaterOrEqual,
+}
+
+impl PredicateOperator {
+    fn as_str(self) -> &'static str {
+        match self {
+            PredicateOperator::Equal => "==",
+            PredicateOperator::NotEqual => "!=",
+            PredicateOperator::Matches => "=~",
+            PredicateOperator::NotMatches => "!~",
+            PredicateOperator::Less => "<",
+            PredicateOperator::LessOrEqual => "<=",
+            PredicateOperator::Greater => ">",
+            PredicateOperator::GreaterOrEqual => ">=",
+        }
+    }
+
+    fn compares_text(self) -> bool {
+        matches!(
+            self,
+            PredicateOperator::Equal
+                | PredicateOperator::NotEqual
+                | PredicateOperator::Matches
+                | PredicateOperator::NotMatches
+        )
+    }
+
+    fn compares_lengths(self) -> bool {
+        !matches!(
+            self,
+            PredicateOperator::Matches | PredicateOperator::NotMatches
+        )
+    }
+
+    fn is_negated(self) -> bool {
+        matches!(
+            self,
+            PredicateOperator::NotEqual | PredicateOperator::NotMatches
+        )
+    }
+}
+
+impl FromStr for PredicateOperator {
+    type Err = InvalidSelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "==" => PredicateOperator::Equal,
+            "!=" => PredicateOperator::NotEqual,
+            "=~" => PredicateOperator::Matches,
+            "!~" => PredicateOperator::NotMatches,
+            "<" => PredicateOperator::Less,
+            "<=" => PredicateOperator::LessOrEqual,
+            ">" => PredicateOperator::Greater,
+            ">=" => PredicateOperator::GreaterOrEqual,
+            other => {
+                return Err(InvalidSelectorError::UnexpectedToken(
+                    other.to_owned(),
+                    "a predicate operator",
+                ))
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum PredicateMatcher {
+    Text(Regex),
+    Length(usize),
+}
+
+/// A condition on an item in a selector path, such as `[?key=~"X-*"]`.
+///
+/// Like a wildcard, a predicate matches a single item in the path, but only if the item satisfies
+/// the condition. Predicates can refer to:
+///
+///  - `key`: The key of the item, for instance `$http.headers[?key=~"X-*"]`. Items without a key,
+///    such as array elements, never match.
+///  - `length`: The number of characters of a string item, compared with `==`, `!=`, `<`, `<=`,
+///    `>` or `>=`, for instance `$frame.vars[?length>=32]`.
+///  - Any other name: The string value of a field in an object item, for instance
+///    `exception.values[?type=="ValueError"].value` to select the messages of `ValueError`
+///    exceptions.
+///
+/// Predicates on lengths and fields require the values of items, which only some processors
+/// record. See [`PredicateValues`].
+#[derive(Clone, Debug)]
+pub struct SelectorPredicate {
+    field: PredicateField,
+    operator: PredicateOperator,
+    value: String,
+    matcher: PredicateMatcher,
+}
+
+impl SelectorPredicate {
+    /// Creates a predicate comparing keys to the given value.
+    ///
+    /// For the `Matches` and `NotMatches` operators, `*` in the value matches any sequence of
+    /// characters and `?` matches a single character.
+    pub fn new(operator: PredicateOperator, value: &str) -> Result<Self, InvalidSelectorError> {
+        Self::with_field(PredicateField::Key, operator, value)
+    }
+
+    /// Creates a predicate comparing the given field of items to a value.
+    ///
+    /// Lengths must be compared to an integer with the `Equal`, `NotEqual`, `Less`,
+    /// `LessOrEqual`, `Greater` or `GreaterOrEqual` operators. Keys and fields support the
+    /// `Equal`, `NotEqual`, `Matches` and `NotMatches` operators, see [`new`](Self::new).
+    pub fn with_field(
+        field: PredicateField,
+        operator: PredicateOperator,
+        value: &str,
+    ) -> Result<Self, InvalidSelectorError> {
+        let matcher = match field {
+            PredicateField::Length if operator.compares_lengths() => PredicateMatcher::Length(
+                value
+                    .parse()
+                    .map_err(|_| InvalidSelectorError::InvalidPredicate)?,
+            ),
+            PredicateField::Key | PredicateField::Field(_) if operator.compares_text() => {
+                PredicateMatcher::Text(text_regex(operator, value)?)
+            }
+            _ => return Err(InvalidSelectorError::InvalidPredicate),
+        };
+
+        Ok(SelectorPredicate {
+            field,
+            operator,
+            value: value.to_owned(),
+            matcher,
+        })
+    }
+
+    /// Returns the field of items that this predicate is evaluated against.
+    pub fn field(&self) -> &PredicateField {
+        &self.field
+    }
+
+    /// Returns the operator of this predicate.
+    pub fn operator(&self) -> PredicateOperator {
+        self.operator
+    }
+
+    /// Returns the value or glob pattern that keys are compared to.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Determines whether the given key satisfies this predicate.
+    ///
+    /// Predicates on lengths and fields never match without the values of the item.
+    pub fn matches_key(&self, key: Option<&str>) -> bool {
+        self.matches(key, None)
+    }
+
+    /// Determines whether an item with the given key and values satisfies this predicate.
+    pub fn matches(&self, key: Option<&str>, values: Option<&PredicateValues>) -> bool {
+        match (&self.field, &self.matcher) {
+            (PredicateField::Key, PredicateMatcher::Text(ref regex)) => {
+                key.map_or(false, |key| self.matches_text(regex, key))
+            }
+            (PredicateField::Field(ref name), PredicateMatcher::Text(ref regex)) => values
+                .and_then(|values| values.fields.get(name))
+                .map_or(false, |text| self.matches_text(regex, text)),
+            (PredicateField::Length, PredicateMatcher::Length(expected)) => values
+                .and_then(|values| values.length)
+                .map_or(false, |length| self.matches_length(length, *expected)),
+            _ => false,
+        }
+    }
+
+    fn matches_text(&self, regex: &Regex, text: &str) -> bool {
+        regex.is_match(text) != self.operator.is_negated()
+    }
+
+    fn matches_length(&self, length: usize, expected: usize) -> bool {
+        match self.operator {
+            PredicateOperator::Equal => length == expected,
+            PredicateOperator::NotEqual => length != expected,
+            PredicateOperator::Less => length < expected,
+            PredicateOperator::LessOrEqual => length <= expected,
+            PredicateOperator::Greater => length > expected,
+            PredicateOperator::GreaterOrEqual => length >= expected,
+            PredicateOperator::Matches | PredicateOperator::NotMatches => false,
+        }
+    }
+}
+
+/// Builds a case-insensitive regex for comparing keys and fields to a value.
+fn text_regex(operator: PredicateOperator, value: &str) -> Result<Regex, InvalidSelectorError> {
+    let mut pattern = String::from("(?is)\\A");
+    match operator {
+        PredicateOperator::Matches | PredicateOperator::NotMatches => {
+            for c in value.chars() {
+                match c {
+                    '*' => pattern.push_str(".*"),
+                    '?' => pattern.push('.'),
+                    c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+                }
+            }
+        }
+        _ => pattern.push_str(&regex::escape(value)),
+    }
+    pattern.push_str("\\z");
+
+    Regex::new(&pattern).map_err(|_| InvalidSelectorError::InvalidPredicate)
+}
+
+impl PartialEq for SelectorPredicate {
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field && self.operator == other.operator && self.value == other.value
+    }
+}
+
+impl Eq for SelectorPredicate {}
+
+impl PartialOrd for SelectorPredicate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SelectorPredicate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.field, self.operator, &self.value).cmp(&(&other.field, other.operator, &other.value))
+    }
+}
+
+impl fmt::Display for SelectorPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.field {
+            PredicateField::Length => {
+                write!(f, "[?length{}{}]", self.operator.as_str(), self.value)
+            }
+            ref field => write!(
+                f,
+                "[?{}{}\"{}\"]",
+                field,
+                self.operator.as_str(),
+                self.value
+            ),
+        }
+    }
+}
+
+/// Values of an item that predicates on lengths and fields are evaluated against.
+///
+/// Selectors only have access to the path of an item. Processors that support these predicates
+/// record the values of every item they enter and pass them to
+/// [`Path::matches_selector_with_values`](crate::processor::Path::matches_selector_with_values).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PredicateValues {
+    length: Option<usize>,
+    fields: BTreeMap<String, String>,
+}
+
+impl PredicateValues {
+    /// Reads the length of a string value, or the given fields of an object value.
+    ///
+    /// Only the length of strings and the referenced fields of objects are read, without cloning or
+    /// traversing the rest of the value. Fields are only recorded if they contain strings.
+    pub fn new<T: IntoValue>(value: &T, fields: &BTreeSet<String>) -> Self {
+        value
+            .serialize_payload(ValuesSerializer { fields }, SkipSerialization::Never)
+            .unwrap_or_default()
+    }
+}
+
+/// Error for values that predicates cannot be evaluated against.
+#[derive(Debug)]
+struct UnsupportedValue;
+
+impl fmt::Display for UnsupportedValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unsupported value for predicates")
+    }
+}
+
+impl std::error::Error for UnsupportedValue {}
+
+impl ser::Error for UnsupportedValue {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        UnsupportedValue
+    }
+}
+
+/// Implements the `Serializer` methods for values other than strings, maps and structs.
+///
+/// Scalars resolve to `$scalar`, while sequences and variants are rejected without serializing
+/// their contents.
+macro_rules! serialize_other_values {
+    ($scalar:expr) => {
+        fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_unit_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+        ) -> Result<Self::Ok, Self::Error> {
+            Ok($scalar)
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            value: &T,
+        ) -> Result<Self::Ok, Self::Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<Self::Ok, Self::Error> {
+            Err(UnsupportedValue)
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+            Err(UnsupportedValue)
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+            Err(UnsupportedValue)
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+            Err(UnsupportedValue)
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+            Err(UnsupportedValue)
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant, Self::Error> {
+            Err(UnsupportedValue)
+        }
+    };
+}
+
+/// Serializer that reads [`PredicateValues`] from the payload of a value.
+struct ValuesSerializer<'f> {
+    fields: &'f BTreeSet<String>,
+}
+
+impl<'f> ser::Serializer for ValuesSerializer<'f> {
+    type Ok = PredicateValues;
+    type Error = UnsupportedValue;
+    type SerializeSeq = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = FieldsSerializer<'f>;
+    type SerializeStruct = FieldsSerializer<'f>;
+    type SerializeStructVariant = ser::Impossible<Self::Ok, Self::Error>;
+
+    serialize_other_values!(PredicateValues::default());
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(PredicateValues {
+            length: Some(v.chars().count()),
+            fields: BTreeMap::new(),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(FieldsSerializer::new(self.fields))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(FieldsSerializer::new(self.fields))
+    }
+}
+
+/// Serializer for the entries of an object that only reads the referenced fields.
+struct FieldsSerializer<'f> {
+    fields: &'f BTreeSet<String>,
+    key: Option<&'f String>,
+    values: PredicateValues,
+}
+
+impl<'f> FieldsSerializer<'f> {
+    fn new(fields: &'f BTreeSet<String>) -> Self {
+        FieldsSerializer {
+            fields,
+            key: None,
+            values: PredicateValues::default(),
+        }
+    }
+
+    fn record<T: ?Sized + Serialize>(&mut self, name: &str, value: &T) {
+        if let Ok(Some(text)) = value.serialize(StringSerializer(str::to_owned)) {
+            self.values.fields.insert(name.to_owned(), text);
+        }
+    }
+}
+
+impl<'f> ser::SerializeMap for FieldsSerializer<'f> {
+    type Ok = PredicateValues;
+    type Error = UnsupportedValue;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let fields = self.fields;
+        self.key = key
+            .serialize(StringSerializer(|key: &str| fields.get(key)))
+            .ok()
+            .flatten()
+            .flatten();
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        if let Some(name) = self.key.take() {
+            self.record(name, value);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.values)
+    }
+}
+
+impl<'f> ser::SerializeStruct for FieldsSerializer<'f> {
+    type Ok = PredicateValues;
+    type Error = UnsupportedValue;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let fields = self.fields;
+        if let Some(name) = fields.get(key) {
+            self.record(name, value);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.values)
+    }
+}
+
+/// Serializer that passes strings to a function and resolves all other scalars to `None`.
+struct StringSerializer<F>(F);
+
+impl<F, R> ser::Serializer for StringSerializer<F>
+where
+    F: FnOnce(&str) -> R,
+{
+    type Ok = Option<R>;
+    type Error = UnsupportedValue;
+    type SerializeSeq = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = ser::Impossible<Self::Ok, Self::Error>;
+
+    serialize_other_values!(None);
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Some((self.0)(v)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(UnsupportedValue)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(UnsupportedValue)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum SelectorPathItem {
     Type(ValueType),
@@ -48,6 +670,7 @@ pub enum SelectorPathItem {
     Key(String),
     Wildcard,
     DeepWildcard,
+    Predicate(SelectorPredicate),
 }
 
 impl fmt::Display for SelectorPathItem {
@@ -64,6 +687,7 @@ impl fmt::Display for SelectorPathItem {
             }
             SelectorPathItem::Wildcard => write!(f, "*"),
             SelectorPathItem::DeepWildcard => write!(f, "**"),
+            SelectorPathItem::Predicate(ref predicate) => write!(f, "{}", predicate),
         }
     }
 }
@@ -73,7 +697,15 @@ impl SelectorPathItem {
     ///
     /// `pii` is not the same as `state.attrs().pii`, but rather the PII flag of the state we're
     /// actually trying to match against. `i` is the position of the path item within the path.
-    pub(super) fn matches_state(&self, pii: Pii, i: usize, state: &ProcessingState<'_>) -> bool {
+    ///
+    /// `values` contains the values of the items in the path by depth, see [`PredicateValues`].
+    pub(super) fn matches_state(
+        &self,
+        pii: Pii,
+        i: usize,
+        state: &ProcessingState<'_>,
+        values: &[PredicateValues],
+    ) -> bool {
         match (self, pii) {
             (_, Pii::False) => false,
 
@@ -128,6 +760,9 @@ impl SelectorPathItem {
                 .key()
                 .map(|k| k.to_lowercase() == key.to_lowercase())
                 .unwrap_or(false),
+            (SelectorPathItem::Predicate(ref predicate), _) => {
+                predicate.matches(state.path().key(), values.get(state.depth()))
+            }
         }
     }
 }
@@ -191,7 +826,8 @@ impl fmt::Display for SelectorSpec {
             }
             SelectorSpec::Path(ref path) => {
                 for (idx, item) in path.iter().enumerate() {
-                    if idx > 0 {
+                    // Predicates are attached to the previous item without a separator.
+                    if idx > 0 && !matches!(item, SelectorPathItem::Predicate(_)) {
                         write!(f, ".")?;
                     }
                     write!(f, "{}", item)?;
@@ -202,6 +838,33 @@ impl fmt::Display for SelectorSpec {
     }
 }
 
+impl SelectorSpec {
+    /// Returns all predicates in this selector.
+    pub fn predicates(&self) -> Vec<&SelectorPredicate> {
+        fn collect<'a>(selector: &'a SelectorSpec, predicates: &mut Vec<&'a SelectorPredicate>) {
+            match *selector {
+                SelectorSpec::And(ref xs) | SelectorSpec::Or(ref xs) => {
+                    for x in xs {
+                        collect(x, predicates);
+                    }
+                }
+                SelectorSpec::Not(ref x) => collect(x, predicates),
+                SelectorSpec::Path(ref path) => {
+                    for item in path {
+                        if let SelectorPathItem::Predicate(ref predicate) = *item {
+                            predicates.push(predicate);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut predicates = Vec::new();
+        collect(self, &mut predicates);
+        predicates
+    }
+}
+
 impl FromStr for SelectorSpec {
     type Err = InvalidSelectorError;
 
@@ -302,6 +965,10 @@ fn handle_selector(pair: Pair<Rule>) -> Result<SelectorSpec, InvalidSelectorErro
 }
 
 fn handle_selector_path_item(pair: Pair<Rule>) -> Result<SelectorPathItem, InvalidSelectorError> {
+    if pair.as_rule() == Rule::Predicate {
+        return Ok(SelectorPathItem::Predicate(handle_predicate(pair)?));
+    }
+
     let pair = pair.into_inner().next().unwrap();
     match pair.as_rule() {
         Rule::ObjectType => Ok(SelectorPathItem::Type(
@@ -324,6 +991,42 @@ fn handle_selector_path_item(pair: Pair<Rule>) -> Result<SelectorPathItem, Inval
     }
 }
 
+fn handle_predicate(pair: Pair<Rule>) -> Result<SelectorPredicate, InvalidSelectorError> {
+    let mut inner = pair.into_inner();
+
+    let field = inner.next().unwrap();
+    let field = match field.as_rule() {
+        Rule::KeyField => PredicateField::Key,
+        Rule::LengthField => PredicateField::Length,
+        Rule::Key => {
+            // Unquoted field names must not be mistaken for the keywords.
+            let is_unquoted =
+                field.clone().into_inner().next().unwrap().as_rule() == Rule::UnquotedKey;
+            let name = handle_key(field)?;
+            if is_unquoted && (name == "key" || name == "length") {
+                return Err(InvalidSelectorError::InvalidPredicate);
+            }
+            PredicateField::Field(name)
+        }
+        rule => {
+            return Err(InvalidSelectorError::UnexpectedToken(
+                format!("{:?}", rule),
+                "a predicate field",
+            ))
+        }
+    };
+
+    let operator = inner.next().unwrap().as_str().parse()?;
+
+    let value = inner.next().unwrap();
+    let value = match value.as_rule() {
+        Rule::PredicateValue => value.into_inner().next().unwrap(),
+        _ => value,
+    };
+
+    SelectorPredicate::with_field(field, operator, value.as_str())
+}
+
 fn handle_key(pair: Pair<Rule>) -> Result<String, InvalidSelectorError> {
     let pair = pair.into_inner().next().unwrap();
     match pair.as_rule() {
@@ -350,6 +1053,9 @@ fn key_needs_quoting(key: &str) -> bool {
 mod tests {
     use super::*;
 
+    use crate::protocol::Exception;
+    use crate::types::{Annotated, Value};
+
     #[test]
     fn test_roundtrip() {
         fn check_roundtrip(s: &str) {
@@ -361,6 +1067,94 @@ mod tests {
         check_roundtrip("!a && !b");
         check_roundtrip("!(a && !b)");
         check_roundtrip("!(a && b)");
+        check_roundtrip(r#"$http.headers[?key=~"X-*"]"#);
+        check_roundtrip(r#"$http.headers[?key!~"X-*"] && !request.headers[?key=="Cookie"]"#);
+        check_roundtrip(r#"**[?key!="password"]"#);
+        check_roundtrip("$frame.vars[?length>=32]");
+        check_roundtrip(r#"exception.values[?type=="ValueError"].value"#);
+        check_roundtrip(r#"extra[?'length'=="x"]"#);
+    }
+
+    #[test]
+    fn test_predicates() {
+        let selector = SelectorSpec::from_str(r#"$http.headers[? key =~ "X-*" ]"#).unwrap();
+        assert_eq!(
+            selector,
+            SelectorSpec::Path(vec![
+                SelectorPathItem::Type(ValueType::Request),
+                SelectorPathItem::Key("headers".to_owned()),
+                SelectorPathItem::Predicate(
+                    SelectorPredicate::new(PredicateOperator::Matches, "X-*").unwrap()
+                ),
+            ])
+        );
+
+        let predicate = SelectorPredicate::new(PredicateOperator::Matches, "X-*.?").unwrap();
+        assert!(predicate.matches_key(Some("x-foo.1")));
+        assert!(!predicate.matches_key(Some("x-foo-1")));
+        assert!(!predicate.matches_key(None));
+
+        let predicate = SelectorPredicate::new(PredicateOperator::NotEqual, "X-*").unwrap();
+        assert!(!predicate.matches_key(Some("x-*")));
+        assert!(predicate.matches_key(Some("X-Foo")));
+    }
+
+    #[test]
+    fn test_value_predicates() {
+        let selector =
+            SelectorSpec::from_str(r#"exception.values[?type=~"Value*"].value"#).unwrap();
+        let predicates = selector.predicates();
+        assert_eq!(predicates.len(), 1);
+        assert_eq!(
+            *predicates[0].field(),
+            PredicateField::Field("type".to_owned())
+        );
+
+        let fields = std::iter::once("url".to_owned()).collect();
+        let login = Value::Object(
+            vec![(
+                "url".to_owned(),
+                Value::String("https://x.io/login".to_owned()).into(),
+            )]
+            .into_iter()
+            .collect(),
+        );
+        let values = PredicateValues::new(&login, &fields);
+        assert!(predicates[0].matches(None, Some(&values)));
+        assert!(!predicates[0].matches(None, Some(&PredicateValues::default())));
+        assert!(!predicates[0].matches_key(Some("url")));
+
+        let predicate = SelectorPredicate::with_field(
+            PredicateField::Length,
+            PredicateOperator::GreaterOrEqual,
+            "5",
+        )
+        .unwrap();
+        let long = PredicateValues::new(&Value::String("hällo".to_owned()), &fields);
+        let short = PredicateValues::new(&Value::String("hi".to_owned()), &fields);
+        assert!(predicate.matches(None, Some(&long)));
+        assert!(!predicate.matches(None, Some(&short)));
+        assert!(!predicate.matches(None, None));
+    }
+
+    #[test]
+    fn test_predicate_values_typed() {
+        let exception = Exception {
+            ty: Annotated::new("ValueError".to_owned()),
+            value: Annotated::new("invalid literal".to_owned().into()),
+            ..Exception::default()
+        };
+
+        let fields = vec!["type".to_owned(), "module".to_owned()]
+            .into_iter()
+            .collect();
+        let values = PredicateValues::new(&exception, &fields);
+        assert_eq!(values.length, None);
+        assert_eq!(values.fields.len(), 1);
+        assert_eq!(values.fields["type"], "ValueError");
+
+        let values = PredicateValues::new(&Value::Array(vec![]), &fields);
+        assert_eq!(values, PredicateValues::default());
     }
 
     #[test]
@@ -373,5 +1167,25 @@ mod tests {
             SelectorSpec::from_str("$frame.**.foo.**"),
             Err(InvalidSelectorError::InvalidDeepWildcard)
         ));
+        assert!(matches!(
+            SelectorSpec::from_str(r#"$http.headers[?key~="X-*"]"#),
+            Err(InvalidSelectorError::ParseError(_))
+        ));
+        assert!(matches!(
+            SelectorSpec::from_str(r#"[?key=="X-Foo"]"#),
+            Err(InvalidSelectorError::ParseError(_))
+        ));
+        assert!(matches!(
+            SelectorSpec::from_str(r#"$frame.vars[?length=~"5"]"#),
+            Err(InvalidSelectorError::InvalidPredicate)
+        ));
+        assert!(matches!(
+            SelectorSpec::from_str("$frame.vars[?length>=x]"),
+            Err(InvalidSelectorError::ParseError(_))
+        ));
+        assert!(matches!(
+            SelectorSpec::from_str(r#"$frame.vars[?url>"5"]"#),
+            Err(InvalidSelectorError::ParseError(_))
+        ));
     }
 }
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+use relay_common::{DataCategory, UnixTimestamp};
+
+/// Discard reasons that SDKs report in client reports.
+///
+/// Reasons are sent as free-form strings, so they are validated against this list before they are
+/// emitted as outcomes.
+const KNOWN_DISCARD_REASONS: &[&str] = &[
+    "queue_overflow",
+    "cache_overflow",
+    "ratelimit_backoff",
+    "network_error",
+    "sample_rate",
+    "before_send",
+    "event_processor",
+];
+
+/// The reason reported for discarded items with a reason not listed in [`KNOWN_DISCARD_REASONS`].
+const UNKNOWN_DISCARD_REASON: &str = "unknown";
+
+/// The number of items of a data category that were discarded by the client for a reason.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct DiscardedEvent {
+    /// The reason for discarding the items, such as `sample_rate` or `queue_overflow`.
+    pub reason: String,
+    /// The data category of the discarded items.
+    pub category: DataCategory,
+    /// The number of discarded items.
+    pub quantity: u32,
+}
+
+impl DiscardedEvent {
+    /// Returns the discard reason if it is known, or `"unknown"` otherwise.
+    ///
+    /// Use this instead of `reason` to avoid passing arbitrary client input on to outcomes.
+    pub fn normalized_reason(&self) -> &'static str {
+        KNOWN_DISCARD_REASONS
+            .iter()
+            .find(|reason| **reason == self.reason)
+            .copied()
+            .unwrap_or(UNKNOWN_DISCARD_REASON)
+    }
+}
+
+/// A report of items that were discarded by the client before they were sent.
+///
+/// SDKs drop items on their own, for instance because of client-side sampling, a full transport
+/// queue, or rate limits communicated by a previous response. Client reports make these drops
+/// visible as outcomes.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ClientReport {
+    /// The timestamp of when the report was created by the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<UnixTimestamp>,
+    /// Counts of discarded items by data category and reason.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub discarded_events: Vec<DiscardedEvent>,
+}
+
+impl ClientReport {
+    /// Parses a client report from JSON.
+    pub fn parse(payload: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(payload)
+    }
+
+    /// Serializes a client report back into JSON.
+    pub fn serialize(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_report_roundtrip() {
+        let json = r#"{
+  "timestamp": "2020-02-07T15:17:00Z",
+  "discarded_events": [
+    {
+      "reason": "queue_overflow",
+      "category": "error",
+      "quantity": 42
+    },
+    {
+      "reason": "sample_rate",
+      "category": "transaction",
+      "quantity": 23
+    }
+  ]
+}"#;
+
+        let output = r#"{
+  "timestamp": 1581088620,
+  "discarded_events": [
+    {
+      "reason": "queue_overflow",
+      "category": "error",
+      "quantity": 42
+    },
+    {
+      "reason": "sample_rate",
+      "category": "transaction",
+      "quantity": 23
+    }
+  ]
+}"#;
+
+        let report = ClientReport {
+            timestamp: Some(UnixTimestamp::from_secs(1_581_088_620)),
+            discarded_events: vec![
+                DiscardedEvent {
+                    reason: "queue_overflow".into(),
+                    category: DataCategory::Error,
+                    quantity: 42,
+                },
+                DiscardedEvent {
+                    reason: "sample_rate".into(),
+                    category: DataCategory::Transaction,
+                    quantity: 23,
+                },
+            ],
+        };
+
+        let parsed = ClientReport::parse(json.as_bytes()).unwrap();
+        assert_eq_dbg!(report, parsed);
+        assert_eq_str!(output, serde_json::to_string_pretty(&report).unwrap());
+    }
+
+    #[test]
+    fn test_client_report_unknown_category() {
+        let json = r#"{"discarded_events": [{"reason": "foo", "category": "bar", "quantity": 1}]}"#;
+
+        let parsed = ClientReport::parse(json.as_bytes()).unwrap();
+        assert_eq!(parsed.timestamp, None);
+        assert_eq!(parsed.discarded_events[0].category, DataCategory::Unknown);
+    }
+
+    #[test]
+    fn test_discarded_event_normalized_reason() {
+        let mut discarded = DiscardedEvent {
+            reason: "sample_rate".into(),
+            category: DataCategory::Error,
+            quantity: 1,
+        };
+        assert_eq!(discarded.normalized_reason(), "sample_rate");
+
+        discarded.reason = "x".repeat(1000);
+        assert_eq!(discarded.normalized_reason(), "unknown");
+    }
+}
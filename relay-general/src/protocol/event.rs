@@ -10,9 +10,10 @@ use serde::{Serialize, Serializer};
 
 use crate::processor::ProcessValue;
 use crate::protocol::{
-    Breadcrumb, Breakdowns, ClientSdkInfo, Contexts, Csp, DebugMeta, Exception, ExpectCt,
-    ExpectStaple, Fingerprint, Hpkp, LenientString, Level, LogEntry, Measurements, Metrics,
-    Request, Span, Stacktrace, Tags, TemplateInfo, Thread, Timestamp, User, Values,
+    Breadcrumb, Breakdowns, ClientSdkInfo, Contexts, Crash, Csp, DebugMeta, Deprecation, Exception,
+    ExpectCt, ExpectStaple, Fingerprint, Hpkp, Intervention, LenientString, Level, LogEntry,
+    Measurements, Metrics, Nel, Request, Span, Stacktrace, Tags, TemplateInfo, Thread, Timestamp,
+    User, Values,
 };
 use crate::types::{
     Annotated, Array, Empty, ErrorKind, FromValue, IntoValue, Object, SkipSerialization, Value,
@@ -489,6 +490,23 @@ pub struct Event {
     #[metastructure(omit_from_schema)] // we only document error events for now
     pub expectstaple: Annotated<ExpectStaple>,
 
+    /// Network Error Logging (NEL) reports.
+    #[metastructure(pii = "true")]
+    #[metastructure(omit_from_schema)] // we only document error events for now
+    pub nel: Annotated<Nel>,
+
+    /// Deprecation reports of the Reporting API.
+    #[metastructure(omit_from_schema)] // we only document error events for now
+    pub deprecation: Annotated<Deprecation>,
+
+    /// Intervention reports of the Reporting API.
+    #[metastructure(omit_from_schema)] // we only document error events for now
+    pub intervention: Annotated<Intervention>,
+
+    /// Crash reports of the Reporting API.
+    #[metastructure(omit_from_schema)] // we only document error events for now
+    pub crash: Annotated<Crash>,
+
     /// Spans for tracing.
     #[metastructure(omit_from_schema)] // we only document error events for now
     pub spans: Annotated<Array<Span>>,
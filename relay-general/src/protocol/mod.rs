@@ -2,6 +2,7 @@
 
 mod breadcrumb;
 mod breakdowns;
+mod client_report;
 mod clientsdk;
 mod constants;
 mod contexts;
@@ -31,6 +32,7 @@ pub use sentry_release_parser::{validate_environment, validate_release};
 
 pub use self::breadcrumb::Breadcrumb;
 pub use self::breakdowns::Breakdowns;
+pub use self::client_report::{ClientReport, DiscardedEvent};
 pub use self::clientsdk::{ClientSdkInfo, ClientSdkPackage};
 pub use self::constants::VALID_PLATFORMS;
 pub use self::contexts::{
@@ -54,7 +56,9 @@ pub use self::metrics::{Metrics, SampleRate};
 pub use self::request::{Cookies, HeaderName, HeaderValue, Headers, Query, Request};
 #[cfg(feature = "jsonschema")]
 pub use self::schema::event_json_schema;
-pub use self::security_report::{Csp, ExpectCt, ExpectStaple, Hpkp, SecurityReportType};
+pub use self::security_report::{
+    Crash, Csp, Deprecation, ExpectCt, ExpectStaple, Hpkp, Intervention, Nel, SecurityReportType,
+};
 pub use self::session::{
     ParseSessionStatusError, SessionAggregateItem, SessionAggregates, SessionAttributes,
     SessionStatus, SessionUpdate,
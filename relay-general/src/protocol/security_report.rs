@@ -1,6 +1,8 @@
 //! Contains definitions for the security report interfaces.
 //!
-//! The security interfaces are CSP, HPKP, ExpectCT and ExpectStaple.
+//! The security interfaces are CSP, HPKP, ExpectCT and ExpectStaple. Additionally, this module
+//! contains interfaces for reports of the Reporting API, which are Network Error Logging (NEL),
+//! deprecation, intervention and crash reports.
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
@@ -478,8 +480,10 @@ pub struct Csp {
 
 impl Csp {
     pub fn apply_to_event(data: &[u8], event: &mut Event) -> Result<(), serde_json::Error> {
-        let raw_report = serde_json::from_slice::<CspReportRaw>(data)?;
-        let raw_csp = raw_report.csp_report;
+        let raw_csp = match serde_json::from_slice::<CspReportFormat>(data)? {
+            CspReportFormat::Legacy(raw_report) => raw_report.csp_report,
+            CspReportFormat::Violation(raw_report) => raw_report.into_csp_raw(),
+        };
 
         let effective_directive = raw_csp
             .effective_directive()
@@ -1039,19 +1043,513 @@ impl ExpectStaple {
     }
 }
 
+/// A single report of the Reporting API.
+///
+/// Browsers deliver these reports in batches with the `application/reports+json` content type.
+/// The `type` of a report determines the schema of its `body`.
+///
+/// See <https://w3c.github.io/reporting/#serialize-reports>
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct ReportRaw<B> {
+    #[serde(default)]
+    url: String,
+    user_agent: Option<String>,
+    body: B,
+}
+
+impl<B> ReportRaw<B> {
+    fn get_culprit(&self) -> String {
+        normalize_uri(&self.url).into_owned()
+    }
+
+    fn get_request(&self, referrer: Option<&str>) -> Request {
+        let mut headers = Vec::new();
+
+        if let Some(referrer) = referrer.filter(|r| !r.is_empty()) {
+            headers.push(Annotated::new((
+                Annotated::new(HeaderName::new("Referer")),
+                Annotated::new(HeaderValue::new(referrer)),
+            )));
+        }
+
+        if let Some(ref user_agent) = self.user_agent {
+            headers.push(Annotated::new((
+                Annotated::new(HeaderName::new("User-Agent")),
+                Annotated::new(HeaderValue::new(user_agent.clone())),
+            )));
+        }
+
+        Request {
+            url: Annotated::new(self.url.clone()),
+            headers: if headers.is_empty() {
+                Annotated::empty()
+            } else {
+                Annotated::new(Headers(PairList(headers)))
+            },
+            ..Request::default()
+        }
+    }
+}
+
+/// Body of a `csp-violation` report of the Reporting API.
+///
+/// These reports are sent for the `report-to` policy directive and converted into `CspRaw`, so
+/// that they are normalized like reports of the legacy `report-uri` directive.
+///
+/// See <https://w3c.github.io/webappsec-csp/#reporting>
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CspViolationRaw {
+    #[serde(rename = "documentURL")]
+    document_url: Option<String>,
+    referrer: Option<String>,
+    #[serde(rename = "blockedURL")]
+    blocked_url: Option<String>,
+    effective_directive: String,
+    original_policy: Option<String>,
+    source_file: Option<String>,
+    sample: Option<String>,
+    disposition: Option<String>,
+    status_code: Option<u64>,
+    line_number: Option<u64>,
+    column_number: Option<u64>,
+
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+impl ReportRaw<CspViolationRaw> {
+    fn into_csp_raw(self) -> CspRaw {
+        let body = self.body;
+
+        // Instead of the legacy `self` value, the Reporting API indicates inline resources and
+        // evaluated code with keywords. Append the matching source expression to the directive
+        // so that messages and culprits match legacy reports.
+        let (blocked_uri, source) = match body.blocked_url {
+            None => (CspRaw::default_blocked_uri(), None),
+            Some(url) => match url.as_str() {
+                "" | "inline" => (CspRaw::default_blocked_uri(), Some("'unsafe-inline'")),
+                "eval" | "wasm-eval" => (CspRaw::default_blocked_uri(), Some("'unsafe-eval'")),
+                _ => (url, None),
+            },
+        };
+
+        let violated_directive = match source {
+            Some(source) => format!("{} {}", body.effective_directive, source),
+            None => body.effective_directive,
+        };
+
+        CspRaw {
+            effective_directive: None,
+            blocked_uri,
+            document_uri: body.document_url.or(Some(self.url)),
+            original_policy: body.original_policy,
+            referrer: body.referrer,
+            status_code: body.status_code,
+            violated_directive,
+            source_file: body.source_file,
+            line_number: body.line_number,
+            column_number: body.column_number,
+            script_sample: body.sample,
+            disposition: body.disposition,
+            other: body.other,
+        }
+    }
+}
+
+/// A CSP report sent for either the legacy `report-uri` or the `report-to` policy directive.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum CspReportFormat {
+    Legacy(CspReportRaw),
+    Violation(ReportRaw<CspViolationRaw>),
+}
+
+/// Body of a `network-error` report sent by Network Error Logging.
+///
+/// See `Nel` for meaning of fields.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct NelRaw {
+    #[serde(rename = "type")]
+    ty: String,
+    phase: Option<String>,
+    elapsed_time: Option<u64>,
+    method: Option<String>,
+    protocol: Option<String>,
+    referrer: Option<String>,
+    sampling_fraction: Option<f64>,
+    server_ip: Option<String>,
+    status_code: Option<u64>,
+
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+impl ReportRaw<NelRaw> {
+    fn get_message(&self) -> String {
+        format!(
+            "Network error '{}' for '{}'",
+            self.body.ty,
+            normalize_uri(&self.url)
+        )
+    }
+
+    fn get_tags(&self) -> Tags {
+        let mut tags = vec![
+            Annotated::new(TagEntry(
+                Annotated::new("hostname".to_string()),
+                Annotated::new(normalize_uri(&self.url).into_owned()),
+            )),
+            Annotated::new(TagEntry(
+                Annotated::new("error-type".to_string()),
+                Annotated::new(self.body.ty.clone()),
+            )),
+        ];
+
+        if let Some(ref phase) = self.body.phase {
+            tags.push(Annotated::new(TagEntry(
+                Annotated::new("phase".to_string()),
+                Annotated::new(phase.clone()),
+            )));
+        }
+
+        Tags(PairList::from(tags))
+    }
+
+    fn into_protocol(self) -> Nel {
+        let body = self.body;
+
+        Nel {
+            ty: Annotated::new(body.ty),
+            phase: Annotated::from(body.phase),
+            elapsed_time: Annotated::from(body.elapsed_time),
+            method: Annotated::from(body.method),
+            protocol: Annotated::from(body.protocol),
+            referrer: Annotated::from(body.referrer),
+            sampling_fraction: Annotated::from(body.sampling_fraction),
+            server_ip: Annotated::from(body.server_ip),
+            status_code: Annotated::from(body.status_code),
+            other: body
+                .other
+                .into_iter()
+                .map(|(k, v)| (k, Annotated::from(v)))
+                .collect(),
+        }
+    }
+}
+
+/// Models the content of a Network Error Logging report.
+///
+/// See <https://w3c.github.io/network-error-logging/#generate-a-network-error-report>
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, IntoValue, ProcessValue)]
+pub struct Nel {
+    /// The type of the network error, such as `dns.name_not_resolved` or `http.error`.
+    #[metastructure(field = "type")]
+    pub ty: Annotated<String>,
+    /// The phase of the request in which the error occurred: `dns`, `connection` or
+    /// `application`.
+    pub phase: Annotated<String>,
+    /// The milliseconds between the start of the request and its completion or abortion.
+    pub elapsed_time: Annotated<u64>,
+    /// The HTTP method of the request.
+    pub method: Annotated<String>,
+    /// The network protocol of the request, such as `http/1.1` or `h2`.
+    pub protocol: Annotated<String>,
+    /// The referrer of the request.
+    #[metastructure(pii = "true")]
+    pub referrer: Annotated<String>,
+    /// The sampling rate that was applied by the user agent to send this report.
+    pub sampling_fraction: Annotated<f64>,
+    /// The IP address of the server to which the request was sent.
+    #[metastructure(pii = "true")]
+    pub server_ip: Annotated<String>,
+    /// The HTTP status code of the response, or `0` if no response was received.
+    pub status_code: Annotated<u64>,
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(pii = "true", additional_properties)]
+    pub other: Object<Value>,
+}
+
+impl Nel {
+    pub fn apply_to_event(data: &[u8], event: &mut Event) -> Result<(), serde_json::Error> {
+        let raw_report = serde_json::from_slice::<ReportRaw<NelRaw>>(data)?;
+
+        event.logentry = Annotated::new(LogEntry::from(raw_report.get_message()));
+        event.culprit = Annotated::new(raw_report.get_culprit());
+        event.tags = Annotated::new(raw_report.get_tags());
+        event.request = Annotated::new(raw_report.get_request(raw_report.body.referrer.as_deref()));
+        event.nel = Annotated::new(raw_report.into_protocol());
+
+        Ok(())
+    }
+}
+
+/// Body of a `deprecation` report of the Reporting API.
+///
+/// See `Deprecation` for meaning of fields.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeprecationRaw {
+    id: String,
+    anticipated_removal: Option<String>,
+    message: Option<String>,
+    source_file: Option<String>,
+    line_number: Option<u64>,
+    column_number: Option<u64>,
+
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+impl ReportRaw<DeprecationRaw> {
+    fn get_message(&self) -> String {
+        match self.body.message {
+            Some(ref message) if !message.is_empty() => message.clone(),
+            _ => format!("Deprecated feature '{}' used", self.body.id),
+        }
+    }
+
+    fn get_tags(&self) -> Tags {
+        Tags(PairList::from(vec![Annotated::new(TagEntry(
+            Annotated::new("deprecation".to_string()),
+            Annotated::new(self.body.id.clone()),
+        ))]))
+    }
+
+    fn into_protocol(self) -> Deprecation {
+        let body = self.body;
+
+        Deprecation {
+            id: Annotated::new(body.id),
+            anticipated_removal: Annotated::from(body.anticipated_removal),
+            message: Annotated::from(body.message),
+            source_file: Annotated::from(body.source_file),
+            line_number: Annotated::from(body.line_number),
+            column_number: Annotated::from(body.column_number),
+            other: body
+                .other
+                .into_iter()
+                .map(|(k, v)| (k, Annotated::from(v)))
+                .collect(),
+        }
+    }
+}
+
+/// Models the content of a deprecation report, which is sent when a page uses a deprecated
+/// browser API or feature.
+///
+/// See <https://wicg.github.io/deprecation-reporting/>
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, IntoValue, ProcessValue)]
+pub struct Deprecation {
+    /// The identifier of the deprecated feature.
+    pub id: Annotated<String>,
+    /// The date after which the feature is expected to be removed.
+    pub anticipated_removal: Annotated<String>,
+    /// A human-readable description of the deprecation.
+    pub message: Annotated<String>,
+    /// The URL of the resource where the deprecated feature was used.
+    pub source_file: Annotated<String>,
+    /// The line number in source-file on which the deprecated feature was used.
+    pub line_number: Annotated<u64>,
+    /// The column number in source-file on which the deprecated feature was used.
+    pub column_number: Annotated<u64>,
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(pii = "true", additional_properties)]
+    pub other: Object<Value>,
+}
+
+impl Deprecation {
+    pub fn apply_to_event(data: &[u8], event: &mut Event) -> Result<(), serde_json::Error> {
+        let raw_report = serde_json::from_slice::<ReportRaw<DeprecationRaw>>(data)?;
+
+        event.logentry = Annotated::new(LogEntry::from(raw_report.get_message()));
+        event.culprit = Annotated::new(raw_report.get_culprit());
+        event.tags = Annotated::new(raw_report.get_tags());
+        event.request = Annotated::new(raw_report.get_request(None));
+        event.deprecation = Annotated::new(raw_report.into_protocol());
+
+        Ok(())
+    }
+}
+
+/// Body of an `intervention` report of the Reporting API.
+///
+/// See `Intervention` for meaning of fields.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InterventionRaw {
+    id: String,
+    message: Option<String>,
+    source_file: Option<String>,
+    line_number: Option<u64>,
+    column_number: Option<u64>,
+
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+impl ReportRaw<InterventionRaw> {
+    fn get_message(&self) -> String {
+        match self.body.message {
+            Some(ref message) if !message.is_empty() => message.clone(),
+            _ => format!("Browser intervention '{}'", self.body.id),
+        }
+    }
+
+    fn get_tags(&self) -> Tags {
+        Tags(PairList::from(vec![Annotated::new(TagEntry(
+            Annotated::new("intervention".to_string()),
+            Annotated::new(self.body.id.clone()),
+        ))]))
+    }
+
+    fn into_protocol(self) -> Intervention {
+        let body = self.body;
+
+        Intervention {
+            id: Annotated::new(body.id),
+            message: Annotated::from(body.message),
+            source_file: Annotated::from(body.source_file),
+            line_number: Annotated::from(body.line_number),
+            column_number: Annotated::from(body.column_number),
+            other: body
+                .other
+                .into_iter()
+                .map(|(k, v)| (k, Annotated::from(v)))
+                .collect(),
+        }
+    }
+}
+
+/// Models the content of an intervention report, which is sent when the browser declines a
+/// request made by a page, for instance for security or performance reasons.
+///
+/// See <https://wicg.github.io/intervention-reporting/>
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, IntoValue, ProcessValue)]
+pub struct Intervention {
+    /// The identifier of the intervention.
+    pub id: Annotated<String>,
+    /// A human-readable description of the intervention.
+    pub message: Annotated<String>,
+    /// The URL of the resource where the intervention occurred.
+    pub source_file: Annotated<String>,
+    /// The line number in source-file on which the intervention occurred.
+    pub line_number: Annotated<u64>,
+    /// The column number in source-file on which the intervention occurred.
+    pub column_number: Annotated<u64>,
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(pii = "true", additional_properties)]
+    pub other: Object<Value>,
+}
+
+impl Intervention {
+    pub fn apply_to_event(data: &[u8], event: &mut Event) -> Result<(), serde_json::Error> {
+        let raw_report = serde_json::from_slice::<ReportRaw<InterventionRaw>>(data)?;
+
+        event.logentry = Annotated::new(LogEntry::from(raw_report.get_message()));
+        event.culprit = Annotated::new(raw_report.get_culprit());
+        event.tags = Annotated::new(raw_report.get_tags());
+        event.request = Annotated::new(raw_report.get_request(None));
+        event.intervention = Annotated::new(raw_report.into_protocol());
+
+        Ok(())
+    }
+}
+
+/// Body of a `crash` report of the Reporting API.
+///
+/// See `Crash` for meaning of fields.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct CrashRaw {
+    reason: Option<String>,
+
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+impl ReportRaw<CrashRaw> {
+    fn get_message(&self) -> String {
+        let uri = normalize_uri(&self.url);
+        match self.body.reason {
+            Some(ref reason) => format!("Page crashed on '{}' ({})", uri, reason),
+            None => format!("Page crashed on '{}'", uri),
+        }
+    }
+
+    fn get_tags(&self) -> Tags {
+        let mut tags = vec![Annotated::new(TagEntry(
+            Annotated::new("hostname".to_string()),
+            Annotated::new(normalize_uri(&self.url).into_owned()),
+        ))];
+
+        if let Some(ref reason) = self.body.reason {
+            tags.push(Annotated::new(TagEntry(
+                Annotated::new("reason".to_string()),
+                Annotated::new(reason.clone()),
+            )));
+        }
+
+        Tags(PairList::from(tags))
+    }
+
+    fn into_protocol(self) -> Crash {
+        let body = self.body;
+
+        Crash {
+            reason: Annotated::from(body.reason),
+            other: body
+                .other
+                .into_iter()
+                .map(|(k, v)| (k, Annotated::from(v)))
+                .collect(),
+        }
+    }
+}
+
+/// Models the content of a crash report, which is sent when a page crashed in the browser.
+///
+/// See <https://wicg.github.io/crash-reporting/>
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, IntoValue, ProcessValue)]
+pub struct Crash {
+    /// The reason of the crash, such as `oom` or `unresponsive`.
+    pub reason: Annotated<String>,
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(pii = "true", additional_properties)]
+    pub other: Object<Value>,
+}
+
+impl Crash {
+    pub fn apply_to_event(data: &[u8], event: &mut Event) -> Result<(), serde_json::Error> {
+        let raw_report = serde_json::from_slice::<ReportRaw<CrashRaw>>(data)?;
+
+        event.logentry = Annotated::new(LogEntry::from(raw_report.get_message()));
+        event.culprit = Annotated::new(raw_report.get_culprit());
+        event.tags = Annotated::new(raw_report.get_tags());
+        event.request = Annotated::new(raw_report.get_request(None));
+        event.crash = Annotated::new(raw_report.into_protocol());
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SecurityReportType {
     Csp,
     ExpectCt,
     ExpectStaple,
     Hpkp,
+    Nel,
+    Deprecation,
+    Intervention,
+    Crash,
 }
 
 impl SecurityReportType {
     /// Infers the type of a security report from its payload.
     ///
-    /// This looks into the JSON payload and tries to infer the type from keys. If no report
-    /// matches, an error is returned.
+    /// This looks into the JSON payload and tries to infer the type from keys. Single reports of
+    /// the Reporting API are inferred from their `type`. If no report matches, an error is
+    /// returned.
     pub fn from_json(data: &[u8]) -> Result<Option<Self>, serde_json::Error> {
         #[derive(Deserialize)]
         #[serde(rename_all = "kebab-case")]
@@ -1060,6 +1558,8 @@ impl SecurityReportType {
             known_pins: Option<IgnoredAny>,
             expect_staple_report: Option<IgnoredAny>,
             expect_ct_report: Option<IgnoredAny>,
+            #[serde(rename = "type")]
+            ty: Option<serde_json::Value>,
         }
 
         let helper: SecurityReport = serde_json::from_slice(data)?;
@@ -1073,7 +1573,14 @@ impl SecurityReportType {
         } else if helper.expect_ct_report.is_some() {
             Some(SecurityReportType::ExpectCt)
         } else {
-            None
+            match helper.ty.as_ref().and_then(serde_json::Value::as_str) {
+                Some("csp-violation") => Some(SecurityReportType::Csp),
+                Some("network-error") => Some(SecurityReportType::Nel),
+                Some("deprecation") => Some(SecurityReportType::Deprecation),
+                Some("intervention") => Some(SecurityReportType::Intervention),
+                Some("crash") => Some(SecurityReportType::Crash),
+                _ => None,
+            }
         })
     }
 }
@@ -1898,4 +2405,336 @@ mod tests {
         let report_type = SecurityReportType::from_json(hpkp_report_text.as_bytes()).unwrap();
         assert_eq!(report_type, Some(SecurityReportType::Hpkp));
     }
+    #[test]
+    fn test_csp_violation() {
+        let json = r#"{
+            "age": 53531,
+            "body": {
+                "blockedURL": "https://evil.com/script.js",
+                "disposition": "enforce",
+                "documentURL": "https://example.com/page",
+                "effectiveDirective": "script-src-elem",
+                "originalPolicy": "script-src 'self'; report-to csp-endpoint",
+                "referrer": "https://www.google.com/",
+                "sample": "",
+                "statusCode": 200
+            },
+            "type": "csp-violation",
+            "url": "https://example.com/page",
+            "user_agent": "Mozilla/5.0"
+        }"#;
+
+        let mut event = Event::default();
+        Csp::apply_to_event(json.as_bytes(), &mut event).unwrap();
+
+        assert_annotated_snapshot!(Annotated::new(event), @r###"
+        {
+          "culprit": "script-src-elem",
+          "logentry": {
+            "formatted": "Blocked 'script' from 'evil.com'"
+          },
+          "request": {
+            "url": "https://example.com/page",
+            "headers": [
+              [
+                "Referer",
+                "https://www.google.com/"
+              ]
+            ]
+          },
+          "tags": [
+            [
+              "effective-directive",
+              "script-src-elem"
+            ],
+            [
+              "blocked-uri",
+              "https://evil.com/script.js"
+            ]
+          ],
+          "csp": {
+            "effective_directive": "script-src-elem",
+            "blocked_uri": "https://evil.com/script.js",
+            "document_uri": "https://example.com/page",
+            "original_policy": "script-src 'self'; report-to csp-endpoint",
+            "referrer": "https://www.google.com/",
+            "status_code": 200,
+            "violated_directive": "script-src-elem",
+            "script_sample": "",
+            "disposition": "enforce"
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_csp_violation_inline() {
+        let json = r#"{
+            "body": {
+                "blockedURL": "inline",
+                "disposition": "report",
+                "documentURL": "https://example.com/",
+                "effectiveDirective": "style-src-elem"
+            },
+            "type": "csp-violation",
+            "url": "https://example.com/"
+        }"#;
+
+        let mut event = Event::default();
+        Csp::apply_to_event(json.as_bytes(), &mut event).unwrap();
+
+        assert_annotated_snapshot!(Annotated::new(event), @r###"
+        {
+          "culprit": "style-src-elem 'unsafe-inline'",
+          "logentry": {
+            "formatted": "Blocked 'style' or 'link' element"
+          },
+          "request": {
+            "url": "https://example.com/"
+          },
+          "tags": [
+            [
+              "effective-directive",
+              "style-src-elem"
+            ],
+            [
+              "blocked-uri",
+              "self"
+            ]
+          ],
+          "csp": {
+            "effective_directive": "style-src-elem",
+            "blocked_uri": "self",
+            "document_uri": "https://example.com/",
+            "violated_directive": "style-src-elem 'unsafe-inline'",
+            "disposition": "report"
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_nel_basic() {
+        let json = r#"{
+            "age": 0,
+            "type": "network-error",
+            "url": "https://example.com/about/",
+            "user_agent": "Mozilla/5.0",
+            "body": {
+                "elapsed_time": 823,
+                "method": "POST",
+                "phase": "dns",
+                "protocol": "http/1.1",
+                "referrer": "https://example.com/",
+                "sampling_fraction": 1.0,
+                "server_ip": "",
+                "status_code": 0,
+                "type": "dns.name_not_resolved"
+            }
+        }"#;
+
+        let mut event = Event::default();
+        Nel::apply_to_event(json.as_bytes(), &mut event).unwrap();
+
+        assert_annotated_snapshot!(Annotated::new(event), @r###"
+        {
+          "culprit": "example.com",
+          "logentry": {
+            "formatted": "Network error 'dns.name_not_resolved' for 'example.com'"
+          },
+          "request": {
+            "url": "https://example.com/about/",
+            "headers": [
+              [
+                "Referer",
+                "https://example.com/"
+              ],
+              [
+                "User-Agent",
+                "Mozilla/5.0"
+              ]
+            ]
+          },
+          "tags": [
+            [
+              "hostname",
+              "example.com"
+            ],
+            [
+              "error-type",
+              "dns.name_not_resolved"
+            ],
+            [
+              "phase",
+              "dns"
+            ]
+          ],
+          "nel": {
+            "type": "dns.name_not_resolved",
+            "phase": "dns",
+            "elapsed_time": 823,
+            "method": "POST",
+            "protocol": "http/1.1",
+            "referrer": "https://example.com/",
+            "sampling_fraction": 1.0,
+            "server_ip": "",
+            "status_code": 0
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_nel_invalid() {
+        let json = r#"{
+            "type": "network-error",
+            "url": "https://example.com/about/",
+            "body": {
+                "phase": "dns"
+            }
+        }"#;
+
+        let mut event = Event::default();
+        assert!(Nel::apply_to_event(json.as_bytes(), &mut event).is_err());
+    }
+
+    #[test]
+    fn test_deprecation_basic() {
+        let json = r#"{
+            "type": "deprecation",
+            "url": "https://example.com/",
+            "body": {
+                "id": "websql",
+                "anticipatedRemoval": "2020-01-01",
+                "message": "WebSQL is deprecated and will be removed in Chrome 97",
+                "sourceFile": "https://example.com/index.js",
+                "lineNumber": 1234,
+                "columnNumber": 42
+            }
+        }"#;
+
+        let mut event = Event::default();
+        Deprecation::apply_to_event(json.as_bytes(), &mut event).unwrap();
+
+        assert_annotated_snapshot!(Annotated::new(event), @r###"
+        {
+          "culprit": "example.com",
+          "logentry": {
+            "formatted": "WebSQL is deprecated and will be removed in Chrome 97"
+          },
+          "request": {
+            "url": "https://example.com/"
+          },
+          "tags": [
+            [
+              "deprecation",
+              "websql"
+            ]
+          ],
+          "deprecation": {
+            "id": "websql",
+            "anticipated_removal": "2020-01-01",
+            "message": "WebSQL is deprecated and will be removed in Chrome 97",
+            "source_file": "https://example.com/index.js",
+            "line_number": 1234,
+            "column_number": 42
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_intervention_basic() {
+        let json = r#"{
+            "type": "intervention",
+            "url": "https://example.com/",
+            "body": {
+                "id": "audio-no-gesture"
+            }
+        }"#;
+
+        let mut event = Event::default();
+        Intervention::apply_to_event(json.as_bytes(), &mut event).unwrap();
+
+        assert_annotated_snapshot!(Annotated::new(event), @r###"
+        {
+          "culprit": "example.com",
+          "logentry": {
+            "formatted": "Browser intervention 'audio-no-gesture'"
+          },
+          "request": {
+            "url": "https://example.com/"
+          },
+          "tags": [
+            [
+              "intervention",
+              "audio-no-gesture"
+            ]
+          ],
+          "intervention": {
+            "id": "audio-no-gesture"
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_crash_basic() {
+        let json = r#"{
+            "type": "crash",
+            "url": "https://example.com/",
+            "body": {
+                "reason": "oom"
+            }
+        }"#;
+
+        let mut event = Event::default();
+        Crash::apply_to_event(json.as_bytes(), &mut event).unwrap();
+
+        assert_annotated_snapshot!(Annotated::new(event), @r###"
+        {
+          "culprit": "example.com",
+          "logentry": {
+            "formatted": "Page crashed on 'example.com' (oom)"
+          },
+          "request": {
+            "url": "https://example.com/"
+          },
+          "tags": [
+            [
+              "hostname",
+              "example.com"
+            ],
+            [
+              "reason",
+              "oom"
+            ]
+          ],
+          "crash": {
+            "reason": "oom"
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_security_report_type_deserializer_recognizes_reporting_api_reports() {
+        let cases = [
+            ("csp-violation", Some(SecurityReportType::Csp)),
+            ("network-error", Some(SecurityReportType::Nel)),
+            ("deprecation", Some(SecurityReportType::Deprecation)),
+            ("intervention", Some(SecurityReportType::Intervention)),
+            ("crash", Some(SecurityReportType::Crash)),
+            ("unknown", None),
+        ];
+
+        for (ty, expected) in cases.iter() {
+            let json = format!(
+                r#"{{"type": "{}", "url": "https://example.com/", "body": {{}}}}"#,
+                ty
+            );
+            let report_type = SecurityReportType::from_json(json.as_bytes()).unwrap();
+            assert_eq!(report_type, *expected);
+        }
+    }
 }
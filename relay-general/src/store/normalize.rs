@@ -274,6 +274,10 @@ impl<'a> NormalizeProcessor<'a> {
             || event.expectct.value().is_some()
             || event.expectstaple.value().is_some()
             || event.hpkp.value().is_some()
+            || event.nel.value().is_some()
+            || event.deprecation.value().is_some()
+            || event.intervention.value().is_some()
+            || event.crash.value().is_some()
     }
 
     /// Backfills common security report attributes.
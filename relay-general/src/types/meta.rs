@@ -11,7 +11,7 @@ use crate::types::{IntoValue, Map, Value};
 pub type Range = (usize, usize);
 
 /// Gives an indication about the type of remark.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum RemarkType {
     /// The remark just annotates a value but the value did not change.
     #[serde(rename = "a")]
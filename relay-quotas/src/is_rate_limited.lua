@@ -5,10 +5,13 @@
 --  * [string] Key of the counter.
 --  * [string] Key of the refund counter.
 --
--- ``ARGV`` (3 per quota):
+-- ``ARGV`` (6 per quota):
 --  * [number] Quota limit. Can be ``-1`` for unlimited quotas.
 --  * [number] Absolute Expiration time as Unix timestamp (secs since 1.1.1970 ) for the key.
 --  * [number] Quantity to increment the quota by.
+--  * [number] Burst of a token bucket quota. Can be ``-1`` for fixed window quotas.
+--  * [number] Window in seconds, in which the token bucket refills by ``limit``.
+--  * [number] Current Unix timestamp in seconds.
 --
 -- For example, to check the following two quotas each with a timeout of 10 minutes from now:
 --  * Key ``foo``, refund key ``foo_refund``, limit ``10``; quantity ``5``
@@ -17,7 +20,13 @@
 -- Send these values:
 --
 --     KEYS = {"foo", "foo_refund", "bar", "bar_refund"}
---     ARGV = {10, 600 + now(), 5, 20, 600 + now(), 1}
+--     ARGV = {10, 600 + now(), 5, -1, 600, now(), 20, 600 + now(), 1, -1, 600, now()}
+--
+-- Fixed window quotas count the quantity in the counter key and subtract the
+-- refund counter. Token bucket quotas store the available ``tokens`` and the
+-- timestamp ``ts`` of the last refill in a hash at the counter key. They refill
+-- continuously at a rate of ``limit / window`` up to ``burst`` and do not
+-- support refunds.
 --
 -- The script applies the following logic:
 --  * If all checks pass, the item is accepted and the counters for all quotas
@@ -25,28 +34,48 @@
 --  * If any check fails, the item is rejected and the counters for all remain
 --    unchanged.
 --
--- The result is a Lua table/array (Redis multi bulk reply) that specifies
--- whether or not the item was *rejected* based on the provided limit.
+-- The result is a Lua table/array (Redis multi bulk reply) of integers per
+-- quota. ``0`` indicates that the item was accepted by this quota. Otherwise,
+-- the item was *rejected* based on the provided limit. For token bucket quotas,
+-- the value is the number of seconds until enough tokens have been refilled.
 assert(#KEYS % 2 == 0, "there must be 2 keys per quota")
-assert(#ARGV % 3 == 0, "there must be 3 args per quota")
-assert(#KEYS / 2 == #ARGV / 3, "incorrect number of keys and arguments provided")
+assert(#ARGV % 6 == 0, "there must be 6 args per quota")
+assert(#KEYS / 2 == #ARGV / 6, "incorrect number of keys and arguments provided")
 
 local results = {}
+local tokens = {}
 local failed = false
 local num_quotas = #KEYS / 2
 for i=0, num_quotas - 1 do
     local k = i * 2 + 1
-    local v = i * 3 + 1
+    local v = i * 6 + 1
 
     local limit = tonumber(ARGV[v])
     local quantity = tonumber(ARGV[v+2])
-    local rejected = false
+    local burst = tonumber(ARGV[v+3])
+    local rejected = 0
     -- limit=-1 means "no limit"
-    if limit >= 0 then
-        rejected = (redis.call('GET', KEYS[k]) or 0) - (redis.call('GET', KEYS[k + 1]) or 0) + quantity > limit
+    if limit >= 0 and burst >= 0 then
+        local rate = limit / tonumber(ARGV[v+4])
+        local now = tonumber(ARGV[v+5])
+        local bucket = redis.call('HMGET', KEYS[k], 'tokens', 'ts')
+        local available = tonumber(bucket[1]) or burst
+        local elapsed = math.max(0, now - (tonumber(bucket[2]) or now))
+        available = math.min(burst, available + elapsed * rate)
+
+        if quantity > available then
+            -- A quantity larger than the burst waits for the bucket to refill entirely.
+            local missing = math.min(quantity, burst) - available
+            rejected = math.max(1, math.ceil(missing / rate))
+        end
+        tokens[i + 1] = available
+    elseif limit >= 0 then
+        if (redis.call('GET', KEYS[k]) or 0) - (redis.call('GET', KEYS[k + 1]) or 0) + quantity > limit then
+            rejected = 1
+        end
     end
 
-    if rejected then
+    if rejected > 0 then
         failed = true
     end
     results[i + 1] = rejected
@@ -55,9 +84,14 @@ end
 if not failed then
     for i=0, num_quotas - 1 do
         local k = i * 2 + 1
-        local v = i * 3 + 1
+        local v = i * 6 + 1
 
-        redis.call('INCRBY', KEYS[k], ARGV[v + 2])
+        if tokens[i + 1] then
+            local remaining = tokens[i + 1] - tonumber(ARGV[v + 2])
+            redis.call('HMSET', KEYS[k], 'tokens', tostring(remaining), 'ts', ARGV[v + 5])
+        else
+            redis.call('INCRBY', KEYS[k], ARGV[v + 2])
+        end
         redis.call('EXPIREAT', KEYS[k], ARGV[v + 1])
     end
 end
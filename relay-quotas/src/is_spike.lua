@@ -0,0 +1,44 @@
+-- Check whether the volume of a project spikes above its baseline, and count
+-- the quantity if it does not.
+--
+-- ``KEYS``:
+--  * [string] Key of the counter for the current window.
+--  * [string] Keys of the counters for the previous windows that make up the
+--    baseline. Missing counters count as ``0``.
+--
+-- ``ARGV``:
+--  * [number] Quantity to increment the current counter by.
+--  * [number] Factor by which the current window may exceed the baseline.
+--  * [number] Quantity per window that is accepted regardless of the baseline.
+--  * [number] Number of windows that make up the baseline.
+--  * [number] Absolute Expiration time as Unix timestamp (secs since 1.1.1970)
+--    for the current counter.
+--
+-- The baseline is the average of the previous windows. The item is rejected if
+-- the current counter plus the quantity exceeds ``multiple`` times the
+-- baseline, but never if it is within ``min_volume``. Rejected quantities are
+-- not counted.
+--
+-- Returns ``0`` if the item was accepted, and ``1`` if it was rejected.
+assert(#KEYS >= 1, "there must be a key for the current window")
+assert(#ARGV == 5, "there must be 5 args")
+
+local quantity = tonumber(ARGV[1])
+local multiple = tonumber(ARGV[2])
+local min_volume = tonumber(ARGV[3])
+local baseline_windows = tonumber(ARGV[4])
+
+local total = 0
+for i=2, #KEYS do
+    total = total + (tonumber(redis.call('GET', KEYS[i])) or 0)
+end
+
+local threshold = math.max(math.floor(total / baseline_windows * multiple), min_volume)
+local current = tonumber(redis.call('GET', KEYS[1])) or 0
+if current + quantity > threshold then
+    return 1
+end
+
+redis.call('INCRBY', KEYS[1], quantity)
+redis.call('EXPIREAT', KEYS[1], ARGV[5])
+return 0
@@ -0,0 +1,102 @@
+#[cfg(feature = "redis")]
+use failure::Fail;
+use serde::Serialize;
+
+#[cfg(feature = "redis")]
+use relay_redis::RedisError;
+
+use crate::quota::{ItemScoping, Quota, Scoping};
+use crate::rate_limit::RateLimits;
+use crate::spike::SpikeProtection;
+
+/// An error returned by a [`RateLimiter`].
+///
+/// Without the `redis` feature, rate limiters cannot fail and this error has no variants.
+#[derive(Debug)]
+#[cfg_attr(feature = "redis", derive(Fail))]
+pub enum RateLimitingError {
+    /// Failed to communicate with Redis.
+    #[cfg(feature = "redis")]
+    #[fail(display = "failed to communicate with redis")]
+    Redis(#[cause] RedisError),
+}
+
+#[cfg(not(feature = "redis"))]
+impl std::fmt::Display for RateLimitingError {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {}
+    }
+}
+
+#[cfg(not(feature = "redis"))]
+impl std::error::Error for RateLimitingError {}
+
+/// The consumption of a quota in its current window.
+///
+/// This is returned by [`RateLimiter::quota_usage`]. For quotas with a `burst`, the window starts
+/// now and ends when the token bucket has been refilled entirely.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaUsage {
+    /// The start of the window as UNIX timestamp in seconds.
+    pub window_start: u64,
+    /// The end of the window as UNIX timestamp in seconds.
+    pub window_end: u64,
+    /// The quantity consumed within the window.
+    pub consumed: u64,
+    /// The quantity that can be consumed before the quota is exceeded, or `None` for unlimited
+    /// quotas.
+    pub remaining: Option<u64>,
+}
+
+/// A service that counts items against quotas and checks for rate limits.
+///
+/// Implementations track the consumption of all quotas that carry an `id` and a `window`. Quotas
+/// with a limit of `0` reject all matching items without being tracked.
+pub trait RateLimiter: Send + Sync {
+    /// Checks whether any of the quotas in effect for the given project and project key has been
+    /// exceeded and records consumption of the quota.
+    ///
+    /// By invoking this method, the caller signals that data is being ingested and needs to be
+    /// counted against the quota. This increment happens atomically if none of the quotas have been
+    /// exceeded. Otherwise, a rate limit is returned and data is not counted against the quotas.
+    ///
+    /// If no key is specified, then only organization-wide and project-wide quotas are checked. If
+    /// a key is specified, then key-quotas are also checked.
+    fn is_rate_limited(
+        &self,
+        quotas: &[Quota],
+        item_scoping: ItemScoping<'_>,
+        quantity: usize,
+    ) -> Result<RateLimits, RateLimitingError>;
+
+    /// Returns the current consumption of the given quotas without counting against them.
+    ///
+    /// The result contains one entry for every quota in the same order. Entries are `None` for
+    /// quotas that are not tracked, such as quotas with a limit of `0`, or quotas that are
+    /// constrained to another scope than the given one.
+    fn quota_usage(
+        &self,
+        quotas: &[Quota],
+        scoping: &Scoping,
+    ) -> Result<Vec<Option<QuotaUsage>>, RateLimitingError>;
+
+    /// Checks whether the volume of the item's project and data category spikes above its baseline
+    /// and records the quantity otherwise.
+    ///
+    /// If the quantity exceeds the threshold of [`SpikeProtection`], a rate limit for the data
+    /// category of the project is returned, which expires at the end of the current window or
+    /// after the maximum rate limit of the rate limiter. Such rate limits carry the
+    /// `spike_protection` reason code. Invalid configurations never reject items.
+    ///
+    /// Quantities rejected by spike protection are not recorded, so that spikes do not raise the
+    /// baseline. Accepted quantities are recorded immediately, even if quotas checked afterwards
+    /// reject the items. The baseline therefore reflects the volume a project sends within its
+    /// spike protection, regardless of its quotas.
+    fn check_spike(
+        &self,
+        spike_protection: &SpikeProtection,
+        item_scoping: ItemScoping<'_>,
+        quantity: usize,
+    ) -> Result<RateLimits, RateLimitingError>;
+}
@@ -0,0 +1,921 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use relay_common::{ProjectId, UnixTimestamp};
+
+use crate::limiter::{QuotaUsage, RateLimiter, RateLimitingError};
+use crate::quota::{DataCategory, ItemScoping, Quota, Scoping};
+use crate::rate_limit::{RateLimit, RateLimitScope, RateLimits, RetryAfter};
+use crate::spike::SpikeProtection;
+use crate::REJECT_ALL_SECS;
+
+/// The interval in seconds at which expired counters are removed.
+const PRUNE_INTERVAL: u64 = 60;
+
+/// Counters of a single quota in the current and the previous fixed window.
+#[derive(Clone, Copy, Debug)]
+struct SlidingWindow {
+    /// The size of the window in seconds.
+    window: u64,
+    /// The index of the current fixed window since the epoch.
+    slot: u64,
+    /// The quantity counted in the current fixed window.
+    current: u64,
+    /// The quantity counted in the previous fixed window.
+    previous: u64,
+}
+
+impl SlidingWindow {
+    fn new(window: u64, slot: u64) -> Self {
+        Self {
+            window,
+            slot,
+            current: 0,
+            previous: 0,
+        }
+    }
+
+    /// Moves the current fixed window to the given slot, discarding counts that fall outside.
+    fn advance(&mut self, slot: u64) {
+        if slot <= self.slot {
+            return;
+        }
+
+        self.previous = if slot == self.slot + 1 {
+            self.current
+        } else {
+            0
+        };
+        self.current = 0;
+        self.slot = slot;
+    }
+
+    /// Estimates the quantity within the sliding window that ends `offset` seconds into the
+    /// current fixed window.
+    ///
+    /// The previous fixed window is weighted by the share that still overlaps with the sliding
+    /// window, assuming an even distribution of its counts.
+    fn count(&self, offset: u64) -> u64 {
+        self.current + self.previous * (self.window - offset) / self.window
+    }
+
+    /// Estimates the seconds until `quantity` fits into `limit` again, starting at `offset`
+    /// seconds into the current fixed window.
+    fn retry_after(&self, limit: u64, quantity: u64, offset: u64) -> u64 {
+        let free = match limit.checked_sub(quantity) {
+            Some(free) => free,
+            // The quantity never fits, so wait for the sliding window to clear entirely.
+            None => return self.window * 2 - offset,
+        };
+
+        if self.current <= free {
+            // Wait for the previous window's share to decay far enough.
+            let allowed = (free - self.current) * self.window / self.previous.max(1);
+            self.window.saturating_sub(allowed).saturating_sub(offset)
+        } else {
+            // Wait for the next fixed window, in which the current counts decay.
+            let allowed = free * self.window / self.current;
+            self.window - offset + self.window.saturating_sub(allowed)
+        }
+    }
+}
+
+/// Tokens of a single quota with a `burst`, which refill continuously.
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    /// The maximum number of tokens.
+    burst: u64,
+    /// The number of tokens refilled per `window`.
+    limit: u64,
+    /// The refill interval in seconds.
+    window: u64,
+    /// The number of tokens available at `updated`.
+    tokens: f64,
+    /// The timestamp in seconds at which `tokens` were last refilled.
+    updated: u64,
+}
+
+impl TokenBucket {
+    fn new(burst: u64, limit: u64, window: u64, timestamp: u64) -> Self {
+        Self {
+            burst,
+            limit,
+            window,
+            tokens: burst as f64,
+            updated: timestamp,
+        }
+    }
+
+    /// Returns the number of tokens refilled per second.
+    fn rate(&self) -> f64 {
+        self.limit as f64 / self.window as f64
+    }
+
+    /// Adds the tokens refilled since the last update, up to `burst`.
+    fn refill(&mut self, timestamp: u64) {
+        if timestamp <= self.updated {
+            return;
+        }
+
+        let refilled = (timestamp - self.updated) as f64 * self.rate();
+        self.tokens = (self.tokens + refilled).min(self.burst as f64);
+        self.updated = timestamp;
+    }
+
+    /// Returns whether the bucket is full at the given timestamp, so it can be discarded.
+    fn is_full(&self, timestamp: u64) -> bool {
+        let mut bucket = *self;
+        bucket.refill(timestamp);
+        bucket.tokens >= bucket.burst as f64
+    }
+
+    /// Returns the seconds until `quantity` tokens have been refilled.
+    fn retry_after(&self, quantity: u64) -> u64 {
+        // A quantity larger than the burst never fits, so wait for the bucket to refill entirely.
+        let required = quantity.min(self.burst) as f64;
+        ((required - self.tokens).max(0.0) / self.rate()).ceil() as u64
+    }
+}
+
+/// Quantities accepted by spike protection in the most recent fixed windows.
+#[derive(Clone, Debug)]
+struct SpikeCounter {
+    /// The size of the window in seconds.
+    window: u64,
+    /// The number of windows that make up the baseline.
+    baseline_windows: u64,
+    /// The accepted quantity by index of the fixed window since the epoch.
+    slots: BTreeMap<u64, u64>,
+}
+
+impl SpikeCounter {
+    fn new(spike_protection: &SpikeProtection) -> Self {
+        Self {
+            window: spike_protection.window,
+            baseline_windows: spike_protection.baseline_windows,
+            slots: BTreeMap::new(),
+        }
+    }
+
+    /// Returns whether this counter no longer contributes to a baseline at the given timestamp.
+    fn is_expired(&self, timestamp: u64) -> bool {
+        match self.slots.keys().next_back() {
+            Some(last) => timestamp / self.window > last + self.baseline_windows,
+            None => true,
+        }
+    }
+}
+
+/// Identifies the counter of a quota by its id, the organization and the rate limiting scope.
+///
+/// Like the keys of the Redis rate limiter, counters always include the organization, since release
+/// and environment scopes are identified only by their name.
+type CounterKey = (String, u64, RateLimitScope);
+
+fn counter_key(id: &str, quota: &Quota, item_scoping: ItemScoping<'_>) -> CounterKey {
+    (
+        id.to_owned(),
+        item_scoping.organization_id,
+        RateLimitScope::for_quota(item_scoping, quota.scope),
+    )
+}
+
+type SpikeKey = (ProjectId, DataCategory);
+
+#[derive(Debug, Default)]
+struct Counters {
+    windows: HashMap<CounterKey, SlidingWindow>,
+    buckets: HashMap<CounterKey, TokenBucket>,
+    spikes: HashMap<SpikeKey, SpikeCounter>,
+    last_prune: u64,
+}
+
+impl Counters {
+    /// Removes counters that no longer contribute to their sliding window.
+    fn prune(&mut self, timestamp: UnixTimestamp) {
+        let now = timestamp.as_secs();
+        if now < self.last_prune + PRUNE_INTERVAL {
+            return;
+        }
+
+        self.windows
+            .retain(|_, counter| now / counter.window <= counter.slot + 1);
+        self.buckets.retain(|_, bucket| !bucket.is_full(now));
+        self.spikes.retain(|_, counter| !counter.is_expired(now));
+        self.last_prune = now;
+    }
+}
+
+/// A rate limiter that counts quotas in the memory of this process.
+///
+/// As opposed to [`RedisRateLimiter`](crate::RedisRateLimiter), quotas are not shared between
+/// Relay instances. Each instance enforces the full limit of every quota on its own. Quotas are
+/// counted per `id` and per instance of their scope, for example per project key.
+///
+/// Counts are tracked in sliding windows of the quota's `window` size. To keep memory bounded, the
+/// sliding window is approximated from the counts in the current and previous fixed windows.
+///
+/// Quotas with a `burst` are tracked in token buckets instead, which refill at a rate of `limit`
+/// per `window`.
+///
+/// Spike protection is tracked per project and data category in fixed windows, independently of
+/// quotas.
+///
+/// This type is cheap to clone, all clones share the same counters.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryRateLimiter {
+    counters: Arc<Mutex<Counters>>,
+    max_limit: Option<u64>,
+}
+
+impl MemoryRateLimiter {
+    /// Creates a new `MemoryRateLimiter` without any counts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum rate limit in seconds.
+    ///
+    /// By default, this rate limiter will return rate limits based on the quotas' `window` fields.
+    /// If a maximum rate limit is set, this limit is bounded.
+    pub fn max_limit(mut self, max_limit: Option<u64>) -> Self {
+        self.max_limit = max_limit;
+        self
+    }
+
+    /// Creates a rate limit bounded by `max_limit`.
+    fn retry_after(&self, mut seconds: u64) -> RetryAfter {
+        if let Some(max_limit) = self.max_limit {
+            seconds = std::cmp::min(seconds, max_limit);
+        }
+
+        RetryAfter::from_secs(seconds)
+    }
+
+    fn is_rate_limited_at(
+        &self,
+        quotas: &[Quota],
+        item_scoping: ItemScoping<'_>,
+        quantity: usize,
+        timestamp: UnixTimestamp,
+    ) -> RateLimits {
+        let quantity = quantity as u64;
+        let mut rate_limits = RateLimits::new();
+        let mut tracked_quotas = Vec::new();
+
+        for quota in quotas {
+            if !quota.matches(item_scoping) {
+                // Silently skip all quotas that do not apply to this item.
+            } else if quota.limit == Some(0) {
+                // A zero-sized quota is strongest. Do not increment any counters, as one quota has
+                // reached capacity (this is how regular quotas behave as well).
+                let retry_after = self.retry_after(REJECT_ALL_SECS);
+                rate_limits.add(RateLimit::from_quota(quota, item_scoping, retry_after));
+            } else if let (Some(id), Some(window)) = (quota.id.as_deref(), quota.window) {
+                if window > 0 {
+                    let key = counter_key(id, quota, item_scoping);
+                    tracked_quotas.push((quota, key, window));
+                }
+            }
+            // Other quotas can neither be enforced statically nor tracked due to missing fields.
+            // They are skipped for forward-compatibility.
+        }
+
+        if tracked_quotas.is_empty() || rate_limits.is_limited() {
+            return rate_limits;
+        }
+
+        // Poisoning can only occur if a thread panicked while counting. The counters remain
+        // consistent, since they are only written after all checks.
+        let mut counters = self
+            .counters
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        counters.prune(timestamp);
+
+        let now = timestamp.as_secs();
+        for (quota, key, window) in &tracked_quotas {
+            let limit = match quota.limit {
+                Some(limit) => u64::from(limit),
+                None => continue,
+            };
+
+            if let Some(burst) = quota.burst {
+                let burst = u64::from(burst);
+                let mut bucket = counters
+                    .buckets
+                    .get(key)
+                    .copied()
+                    .filter(|b| b.burst == burst && b.limit == limit && b.window == *window)
+                    .unwrap_or_else(|| TokenBucket::new(burst, limit, *window, now));
+                bucket.refill(now);
+
+                if quantity as f64 > bucket.tokens {
+                    let retry_after = self.retry_after(bucket.retry_after(quantity).max(1));
+                    rate_limits.add(RateLimit::from_quota(quota, item_scoping, retry_after));
+                }
+
+                continue;
+            }
+
+            let slot = now / window;
+            let offset = now % window;
+
+            let mut counter = counters
+                .windows
+                .get(key)
+                .copied()
+                .filter(|counter| counter.window == *window)
+                .unwrap_or_else(|| SlidingWindow::new(*window, slot));
+            counter.advance(slot);
+
+            if counter.count(offset) + quantity > limit {
+                let seconds = counter.retry_after(limit, quantity, offset);
+                let retry_after = self.retry_after(seconds.max(1));
+                rate_limits.add(RateLimit::from_quota(quota, item_scoping, retry_after));
+            }
+        }
+
+        if rate_limits.is_limited() {
+            return rate_limits;
+        }
+
+        for (quota, key, window) in tracked_quotas {
+            if let (Some(burst), Some(limit)) = (quota.burst, quota.limit) {
+                let (burst, limit) = (u64::from(burst), u64::from(limit));
+                let bucket = counters
+                    .buckets
+                    .entry(key)
+                    .or_insert_with(|| TokenBucket::new(burst, limit, window, now));
+
+                // The burst or refill rate of a quota can change with a project config update.
+                if bucket.burst != burst || bucket.limit != limit || bucket.window != window {
+                    *bucket = TokenBucket::new(burst, limit, window, now);
+                }
+
+                bucket.refill(now);
+                bucket.tokens -= quantity as f64;
+                continue;
+            }
+
+            let slot = now / window;
+            let counter = counters
+                .windows
+                .entry(key)
+                .or_insert_with(|| SlidingWindow::new(window, slot));
+
+            // The window of a quota can change with a project config update.
+            if counter.window != window {
+                *counter = SlidingWindow::new(window, slot);
+            }
+
+            counter.advance(slot);
+            counter.current += quantity;
+        }
+
+        rate_limits
+    }
+
+    fn check_spike_at(
+        &self,
+        spike_protection: &SpikeProtection,
+        item_scoping: ItemScoping<'_>,
+        quantity: usize,
+        timestamp: UnixTimestamp,
+    ) -> RateLimits {
+        let mut rate_limits = RateLimits::new();
+        if !spike_protection.is_valid() {
+            return rate_limits;
+        }
+
+        let mut counters = self
+            .counters
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        counters.prune(timestamp);
+
+        let now = timestamp.as_secs();
+        let slot = spike_protection.slot(now);
+        let first = slot.saturating_sub(spike_protection.baseline_windows);
+
+        let key = (item_scoping.project_id, item_scoping.category);
+        let counter = counters
+            .spikes
+            .entry(key)
+            .or_insert_with(|| SpikeCounter::new(spike_protection));
+
+        // The windows of spike protection can change with a project config update.
+        if counter.window != spike_protection.window
+            || counter.baseline_windows != spike_protection.baseline_windows
+        {
+            *counter = SpikeCounter::new(spike_protection);
+        }
+
+        counter.slots = counter.slots.split_off(&first);
+        let baseline_total = counter.slots.range(..slot).map(|(_, count)| count).sum();
+        let current = counter.slots.entry(slot).or_insert(0);
+
+        if *current + quantity as u64 > spike_protection.threshold(baseline_total) {
+            let retry_after = self.retry_after(spike_protection.window_remaining(now));
+            rate_limits.add(spike_protection.rate_limit(item_scoping, retry_after));
+        } else {
+            *current += quantity as u64;
+        }
+
+        rate_limits
+    }
+
+    fn quota_usage_at(
+        &self,
+        quotas: &[Quota],
+        scoping: &Scoping,
+        timestamp: UnixTimestamp,
+    ) -> Vec<Option<QuotaUsage>> {
+        let counters = self
+            .counters
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let now = timestamp.as_secs();
+        let item_scoping = scoping.item(DataCategory::Default);
+
+        let usage = |quota: &Quota| {
+            if quota.limit == Some(0) || !quota.matches_scope(item_scoping) {
+                return None;
+            }
+
+            let id = quota.id.as_deref()?;
+            let window = quota.window.filter(|window| *window > 0)?;
+            let key = counter_key(id, quota, item_scoping);
+
+            if let (Some(burst), Some(limit)) = (quota.burst, quota.limit) {
+                let (burst, limit) = (u64::from(burst), u64::from(limit));
+                let mut bucket = counters
+                    .buckets
+                    .get(&key)
+                    .copied()
+                    .filter(|b| b.burst == burst && b.limit == limit && b.window == window)
+                    .unwrap_or_else(|| TokenBucket::new(burst, limit, window, now));
+                bucket.refill(now);
+
+                let remaining = bucket.tokens as u64;
+                return Some(QuotaUsage {
+                    window_start: now,
+                    window_end: now + bucket.retry_after(burst),
+                    consumed: burst - remaining,
+                    remaining: Some(remaining),
+                });
+            }
+
+            let slot = now / window;
+            let mut counter = counters
+                .windows
+                .get(&key)
+                .copied()
+                .filter(|counter| counter.window == window)
+                .unwrap_or_else(|| SlidingWindow::new(window, slot));
+            counter.advance(slot);
+
+            let consumed = counter.count(now % window);
+            Some(QuotaUsage {
+                window_start: now.saturating_sub(window),
+                window_end: now,
+                consumed,
+                remaining: quota
+                    .limit
+                    .map(|limit| u64::from(limit).saturating_sub(consumed)),
+            })
+        };
+
+        quotas.iter().map(usage).collect()
+    }
+}
+
+impl RateLimiter for MemoryRateLimiter {
+    fn is_rate_limited(
+        &self,
+        quotas: &[Quota],
+        item_scoping: ItemScoping<'_>,
+        quantity: usize,
+    ) -> Result<RateLimits, RateLimitingError> {
+        Ok(self.is_rate_limited_at(quotas, item_scoping, quantity, UnixTimestamp::now()))
+    }
+
+    fn quota_usage(
+        &self,
+        quotas: &[Quota],
+        scoping: &Scoping,
+    ) -> Result<Vec<Option<QuotaUsage>>, RateLimitingError> {
+        Ok(self.quota_usage_at(quotas, scoping, UnixTimestamp::now()))
+    }
+
+    fn check_spike(
+        &self,
+        spike_protection: &SpikeProtection,
+        item_scoping: ItemScoping<'_>,
+        quantity: usize,
+    ) -> Result<RateLimits, RateLimitingError> {
+        let timestamp = UnixTimestamp::now();
+        Ok(self.check_spike_at(spike_protection, item_scoping, quantity, timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_common::{ProjectId, ProjectKey};
+
+    use crate::quota::{DataCategories, DataCategory, QuotaScope, ReasonCode, Scoping};
+
+    use super::*;
+
+    fn scoping() -> Scoping {
+        Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(43),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(44),
+        }
+    }
+
+    fn quota(scope: QuotaScope, limit: Option<u32>) -> Quota {
+        Quota {
+            id: Some("foo".to_owned()),
+            categories: DataCategories::new(),
+            scope,
+            scope_id: None,
+            limit,
+            window: Some(60),
+            burst: None,
+            reason_code: Some(ReasonCode::new("get_lost")),
+        }
+    }
+
+    fn is_limited(
+        limiter: &MemoryRateLimiter,
+        quotas: &[Quota],
+        scoping: &Scoping,
+        quantity: usize,
+        secs: u64,
+    ) -> bool {
+        let item_scoping = ItemScoping {
+            category: DataCategory::Error,
+            scoping,
+            release: None,
+            environment: None,
+        };
+
+        limiter
+            .is_rate_limited_at(
+                quotas,
+                item_scoping,
+                quantity,
+                UnixTimestamp::from_secs(secs),
+            )
+            .is_limited()
+    }
+
+    #[test]
+    fn test_zero_size_quota() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[Quota {
+            id: None,
+            window: None,
+            ..quota(QuotaScope::Organization, Some(0))
+        }];
+
+        assert!(is_limited(&limiter, quotas, &scoping(), 1, 120));
+    }
+
+    #[test]
+    fn test_simple_quota() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[quota(QuotaScope::Organization, Some(5))];
+        let scoping = scoping();
+
+        for _ in 0..5 {
+            assert!(!is_limited(&limiter, quotas, &scoping, 1, 120));
+        }
+
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 120));
+    }
+
+    #[test]
+    fn test_quantity() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[quota(QuotaScope::Organization, Some(5))];
+        let scoping = scoping();
+
+        assert!(!is_limited(&limiter, quotas, &scoping, 4, 120));
+        // Rejected items are not counted.
+        assert!(is_limited(&limiter, quotas, &scoping, 2, 120));
+        assert!(!is_limited(&limiter, quotas, &scoping, 1, 120));
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 120));
+    }
+
+    #[test]
+    fn test_unlimited_quota() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[quota(QuotaScope::Organization, None)];
+
+        for _ in 0..10 {
+            assert!(!is_limited(&limiter, quotas, &scoping(), 1, 120));
+        }
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[quota(QuotaScope::Organization, Some(10))];
+        let scoping = scoping();
+
+        // Fill the quota at the end of the fixed window [120, 180).
+        assert!(!is_limited(&limiter, quotas, &scoping, 10, 170));
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 170));
+
+        // A fixed window would reset at 180. The sliding window still counts 9 of the previous 10.
+        assert!(is_limited(&limiter, quotas, &scoping, 2, 186));
+        assert!(!is_limited(&limiter, quotas, &scoping, 1, 186));
+
+        // Halfway through the next window, half of the previous count remains.
+        assert!(!is_limited(&limiter, quotas, &scoping, 4, 210));
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 210));
+
+        // After two full windows, everything has expired.
+        assert!(!is_limited(&limiter, quotas, &scoping, 10, 300));
+    }
+
+    #[test]
+    fn test_scopes() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[quota(QuotaScope::Key, Some(1))];
+
+        let scoping = scoping();
+        let other_key = Scoping {
+            project_key: ProjectKey::parse("b94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            ..scoping
+        };
+
+        assert!(!is_limited(&limiter, quotas, &scoping, 1, 120));
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 120));
+        assert!(!is_limited(&limiter, quotas, &other_key, 1, 120));
+    }
+
+    #[test]
+    fn test_release_scope_per_organization() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[quota(QuotaScope::Release, Some(1))];
+
+        let scoping = scoping();
+        let other_org = Scoping {
+            organization_id: 4711,
+            ..scoping
+        };
+
+        let is_release_limited = |scoping: &Scoping| {
+            let item_scoping = scoping
+                .item(DataCategory::Error)
+                .with_release(Some("1.0.0"));
+
+            limiter
+                .is_rate_limited_at(quotas, item_scoping, 1, UnixTimestamp::from_secs(120))
+                .is_limited()
+        };
+
+        assert!(!is_release_limited(&scoping));
+        assert!(is_release_limited(&scoping));
+        // The same release name in another organization has its own counter.
+        assert!(!is_release_limited(&other_org));
+    }
+
+    #[test]
+    fn test_all_or_nothing() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[
+            quota(QuotaScope::Key, Some(5)),
+            Quota {
+                id: Some("bar".to_owned()),
+                ..quota(QuotaScope::Organization, Some(1))
+            },
+        ];
+        let scoping = scoping();
+
+        assert!(!is_limited(&limiter, quotas, &scoping, 1, 120));
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 120));
+
+        // The key quota was not counted while the organization quota rejected.
+        let key_quotas = &[quota(QuotaScope::Key, Some(5))];
+        for _ in 0..4 {
+            assert!(!is_limited(&limiter, key_quotas, &scoping, 1, 120));
+        }
+        assert!(is_limited(&limiter, key_quotas, &scoping, 1, 120));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut counter = SlidingWindow::new(60, 2);
+        counter.current = 10;
+
+        // Wait for the next window and until the 10 have decayed to 8.
+        assert_eq!(counter.retry_after(10, 2, 50), 10 + 12);
+
+        counter.advance(3);
+        assert_eq!(counter.previous, 10);
+
+        // Wait until the previous window only contributes 9.
+        assert_eq!(counter.retry_after(10, 1, 0), 6);
+        // The quantity already fits.
+        assert_eq!(counter.retry_after(10, 1, 30), 0);
+
+        // A quantity larger than the limit never fits.
+        assert_eq!(counter.retry_after(10, 11, 30), 90);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[Quota {
+            burst: Some(10),
+            ..quota(QuotaScope::Organization, Some(60))
+        }];
+        let scoping = scoping();
+
+        // A burst is absorbed up to its size, even though the limit is larger.
+        assert!(!is_limited(&limiter, quotas, &scoping, 10, 120));
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 120));
+
+        // The bucket refills at a rate of one token per second.
+        assert!(!is_limited(&limiter, quotas, &scoping, 5, 125));
+        assert!(is_limited(&limiter, quotas, &scoping, 1, 125));
+
+        // After a pause, the bucket is full again, but never exceeds the burst.
+        assert!(is_limited(&limiter, quotas, &scoping, 11, 300));
+        assert!(!is_limited(&limiter, quotas, &scoping, 10, 300));
+    }
+
+    #[test]
+    fn test_token_bucket_retry_after() {
+        let mut bucket = TokenBucket::new(10, 30, 60, 120);
+        bucket.tokens = 0.0;
+
+        // Refills at a rate of half a token per second.
+        assert_eq!(bucket.retry_after(3), 6);
+        // A quantity larger than the burst waits for the bucket to refill entirely.
+        assert_eq!(bucket.retry_after(11), 20);
+
+        bucket.refill(124);
+        assert_eq!(bucket.retry_after(2), 0);
+        assert_eq!(bucket.retry_after(3), 2);
+        assert!(bucket.is_full(140));
+    }
+
+    #[test]
+    fn test_quota_usage() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[
+            quota(QuotaScope::Organization, Some(5)),
+            Quota {
+                id: Some("bar".to_owned()),
+                burst: Some(10),
+                ..quota(QuotaScope::Key, Some(60))
+            },
+            quota(QuotaScope::Organization, Some(0)),
+        ];
+        let scoping = scoping();
+
+        assert!(!is_limited(&limiter, &quotas[..1], &scoping, 3, 120));
+        assert!(!is_limited(&limiter, &quotas[1..2], &scoping, 4, 120));
+
+        let usage = limiter.quota_usage_at(quotas, &scoping, UnixTimestamp::from_secs(122));
+        assert_eq!(
+            usage,
+            vec![
+                Some(QuotaUsage {
+                    window_start: 62,
+                    window_end: 122,
+                    consumed: 3,
+                    remaining: Some(2),
+                }),
+                // Two tokens have been refilled since.
+                Some(QuotaUsage {
+                    window_start: 122,
+                    window_end: 124,
+                    consumed: 2,
+                    remaining: Some(8),
+                }),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let limiter = MemoryRateLimiter::new();
+        let quotas = &[quota(QuotaScope::Organization, Some(5))];
+        let scoping = scoping();
+
+        assert!(!is_limited(&limiter, quotas, &scoping, 1, 120));
+        assert_eq!(limiter.counters.lock().unwrap().windows.len(), 1);
+
+        // The next call prunes before counting, so only the new counter remains.
+        let other = &[Quota {
+            id: Some("bar".to_owned()),
+            ..quota(QuotaScope::Organization, Some(5))
+        }];
+        assert!(!is_limited(&limiter, other, &scoping, 1, 300));
+        assert_eq!(limiter.counters.lock().unwrap().windows.len(), 1);
+    }
+
+    #[test]
+    fn test_check_spike() {
+        let limiter = MemoryRateLimiter::new();
+        let scoping = scoping();
+        let spike_protection = SpikeProtection {
+            multiple: 2.0,
+            min_volume: 20,
+            window: 60,
+            baseline_windows: 10,
+        };
+
+        let check = |category, quantity, secs| {
+            limiter
+                .check_spike_at(
+                    &spike_protection,
+                    scoping.item(category),
+                    quantity,
+                    UnixTimestamp::from_secs(secs),
+                )
+                .is_limited()
+        };
+
+        // Without a baseline, the minimum volume is accepted.
+        assert!(!check(DataCategory::Error, 20, 0));
+        assert!(check(DataCategory::Error, 1, 0));
+
+        // Build up a baseline of 20 per window across the next windows.
+        for window in 1..10 {
+            assert!(!check(DataCategory::Error, 20, window * 60));
+        }
+
+        // The baseline is 20 per window, so twice as much is accepted.
+        assert!(!check(DataCategory::Error, 40, 600));
+        assert!(check(DataCategory::Error, 1, 600));
+
+        // Other categories have their own baseline.
+        assert!(!check(DataCategory::Transaction, 10, 600));
+    }
+
+    #[test]
+    fn test_check_spike_rate_limit() {
+        let limiter = MemoryRateLimiter::new();
+        let scoping = scoping();
+        let spike_protection = SpikeProtection {
+            multiple: 2.0,
+            min_volume: 0,
+            window: 60,
+            baseline_windows: 10,
+        };
+
+        let item_scoping = scoping.item(DataCategory::Error);
+        let timestamp = UnixTimestamp::from_secs(75);
+        let rate_limits: Vec<RateLimit> = limiter
+            .check_spike_at(&spike_protection, item_scoping, 1, timestamp)
+            .into_iter()
+            .collect();
+
+        assert_eq!(rate_limits.len(), 1);
+        assert_eq!(
+            rate_limits[0].scope,
+            RateLimitScope::Project(scoping.project_id)
+        );
+        assert_eq!(
+            rate_limits[0].reason_code,
+            Some(ReasonCode::spike_protection())
+        );
+        // The rate limit lasts until the end of the window.
+        assert!(rate_limits[0].retry_after.remaining_seconds() <= 45);
+    }
+
+    #[test]
+    fn test_check_spike_max_limit() {
+        let limiter = MemoryRateLimiter::new().max_limit(Some(10));
+        let scoping = scoping();
+        let spike_protection = SpikeProtection {
+            multiple: 2.0,
+            min_volume: 0,
+            window: 60,
+            baseline_windows: 10,
+        };
+
+        let item_scoping = scoping.item(DataCategory::Error);
+        let timestamp = UnixTimestamp::from_secs(75);
+        let rate_limits: Vec<RateLimit> = limiter
+            .check_spike_at(&spike_protection, item_scoping, 1, timestamp)
+            .into_iter()
+            .collect();
+
+        // The rate limit is bounded by the maximum rate limit instead of the end of the window.
+        assert_eq!(rate_limits.len(), 1);
+        assert!(rate_limits[0].retry_after.remaining_seconds() <= 10);
+    }
+}
@@ -33,6 +33,8 @@ impl Scoping {
         ItemScoping {
             category,
             scoping: self,
+            release: None,
+            environment: None,
         }
     }
 }
@@ -49,6 +51,12 @@ pub struct ItemScoping<'a> {
 
     /// Scoping of the data.
     pub scoping: &'a Scoping,
+
+    /// The release of the item, if known.
+    pub release: Option<&'a str>,
+
+    /// The environment of the item, if known.
+    pub environment: Option<&'a str>,
 }
 
 impl AsRef<Scoping> for ItemScoping<'_> {
@@ -65,14 +73,37 @@ impl std::ops::Deref for ItemScoping<'_> {
     }
 }
 
-impl ItemScoping<'_> {
+impl<'a> ItemScoping<'a> {
+    /// Sets the release of the item for release-scoped quotas.
+    pub fn with_release(mut self, release: Option<&'a str>) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// Sets the environment of the item for environment-scoped quotas.
+    pub fn with_environment(mut self, environment: Option<&'a str>) -> Self {
+        self.environment = environment;
+        self
+    }
+
     /// Returns the identifier of the given scope.
+    ///
+    /// Release and environment scopes are not identified by a number. Use `scope_value` instead.
     pub fn scope_id(&self, scope: QuotaScope) -> Option<u64> {
         match scope {
             QuotaScope::Organization => Some(self.organization_id),
             QuotaScope::Project => Some(self.project_id.value()),
             QuotaScope::Key => self.key_id,
-            QuotaScope::Unknown => None,
+            QuotaScope::Release | QuotaScope::Environment | QuotaScope::Unknown => None,
+        }
+    }
+
+    /// Returns the value of the given scope for release and environment scopes.
+    pub fn scope_value(&self, scope: QuotaScope) -> Option<&'a str> {
+        match scope {
+            QuotaScope::Release => self.release,
+            QuotaScope::Environment => self.environment,
+            _ => None,
         }
     }
 
@@ -103,7 +134,9 @@ impl CategoryUnit {
             DataCategory::Default
             | DataCategory::Error
             | DataCategory::Transaction
-            | DataCategory::Security => Some(Self::Count),
+            | DataCategory::Security
+            | DataCategory::MetricBucket
+            | DataCategory::AttachmentItem => Some(Self::Count),
             DataCategory::Attachment => Some(Self::Bytes),
             DataCategory::Session => Some(Self::Batched),
             DataCategory::Unknown => None,
@@ -138,6 +171,18 @@ pub enum QuotaScope {
     /// This is a sub-scope of `Project`.
     Key,
 
+    /// The release of an item, such as the release of an event.
+    ///
+    /// This is a sub-scope of `Project`. Items without a release are not subject to quotas with
+    /// this scope.
+    Release,
+
+    /// The environment of an item, such as the environment of an event.
+    ///
+    /// This is a sub-scope of `Project`. Items without an environment are not subject to quotas
+    /// with this scope.
+    Environment,
+
     /// Any other scope that is not known by this Relay.
     #[serde(other)]
     Unknown,
@@ -150,6 +195,8 @@ impl QuotaScope {
             "organization" => Self::Organization,
             "project" => Self::Project,
             "key" => Self::Key,
+            "release" => Self::Release,
+            "environment" => Self::Environment,
             _ => Self::Unknown,
         }
     }
@@ -160,6 +207,8 @@ impl QuotaScope {
             Self::Key => "key",
             Self::Project => "project",
             Self::Organization => "organization",
+            Self::Release => "release",
+            Self::Environment => "environment",
             Self::Unknown => "unknown",
         }
     }
@@ -196,6 +245,11 @@ impl ReasonCode {
         Self(code.into())
     }
 
+    /// Returns the reason code of rate limits applied by spike protection.
+    pub fn spike_protection() -> Self {
+        Self::new("spike_protection")
+    }
+
     /// Returns the string representation of this reason code.
     pub fn as_str(&self) -> &str {
         &self.0
@@ -232,7 +286,8 @@ pub struct Quota {
     pub scope: QuotaScope,
 
     /// Identifier of the scope to apply to. If set, then this quota will only apply to the
-    /// specified scope instance (e.g. a project key). Requires `scope` to be set explicitly.
+    /// specified scope instance (e.g. a project key). Requires `scope` to be set explicitly. For
+    /// release and environment scopes, this is the name of the release or environment.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub scope_id: Option<String>,
 
@@ -247,6 +302,14 @@ pub struct Quota {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub window: Option<u64>,
 
+    /// The maximum quantity that can be accepted at once. If set, this quota is enforced with a
+    /// token bucket instead of a fixed window: The bucket holds up to `burst` tokens and refills at
+    /// a rate of `limit` tokens per `window`. Short spikes are absorbed as long as the bucket has
+    /// tokens left, while sustained throughput is capped by the refill rate. Requires `limit` and
+    /// `window`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+
     /// A machine readable reason returned when this quota is exceeded. Required in all cases except
     /// `limit=None`, since unlimited quotas can never be exceeded.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -259,7 +322,12 @@ impl Quota {
     /// There are a few conditions at which quotas are invalid:
     ///  - The quota only applies to `Unknown` data categories.
     ///  - The quota is counted (not limit `0`) but specifies categories with different units.
+    ///  - The quota specifies a `burst` without a `limit` to derive the refill rate from.
     pub fn is_valid(&self) -> bool {
+        if self.burst.is_some() && self.limit.is_none() {
+            return false;
+        }
+
         let mut units = self.categories.iter().filter_map(|c| CategoryUnit::from(c));
 
         match units.next() {
@@ -276,11 +344,21 @@ impl Quota {
 
     /// Checks whether this quota's scope matches the given item scoping.
     ///
-    /// This quota matches, if:
+    /// Release and environment quotas match, if the item has a release or environment that is
+    /// equal to the `scope_id` constraint, if any. All other quotas match, if:
     ///  - there is no `scope_id` constraint
     ///  - the `scope_id` constraint is not numeric
     ///  - the scope identifier matches the one from ascoping and the scope is known
-    fn matches_scope(&self, scoping: ItemScoping<'_>) -> bool {
+    pub(crate) fn matches_scope(&self, scoping: ItemScoping<'_>) -> bool {
+        // Release and environment quotas only apply to items that carry the respective value. The
+        // scope identifier constraint is the release or environment name itself.
+        if matches!(self.scope, QuotaScope::Release | QuotaScope::Environment) {
+            return match scoping.scope_value(self.scope) {
+                Some(value) => self.scope_id.as_deref().map_or(true, |id| id == value),
+                None => false,
+            };
+        }
+
         // Check for a scope identifier constraint. If there is no constraint, this means that the
         // quota matches any scope. In case the scope is unknown, it will be coerced to the most
         // specific scope later.
@@ -377,6 +455,31 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_parse_quota_burst() {
+        let json = r#"{
+            "id": "b",
+            "limit": 60,
+            "window": 60,
+            "burst": 20,
+            "reasonCode": "not_so_fast"
+        }"#;
+
+        let quota = serde_json::from_str::<Quota>(json).expect("parse quota");
+
+        insta::assert_ron_snapshot!(quota, @r###"
+        Quota(
+          id: Some("b"),
+          categories: [],
+          scope: organization,
+          limit: Some(60),
+          window: Some(60),
+          burst: Some(20),
+          reasonCode: Some(ReasonCode("not_so_fast")),
+        )
+        "###);
+    }
+
     #[test]
     fn test_parse_quota_project() {
         let json = r#"{
@@ -487,6 +590,7 @@ mod tests {
             scope_id: None,
             limit: Some(0),
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -502,6 +606,7 @@ mod tests {
             scope_id: None,
             limit: Some(0),
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -517,6 +622,7 @@ mod tests {
             scope_id: None,
             limit: Some(0),
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -532,6 +638,7 @@ mod tests {
             scope_id: None,
             limit: Some(1000),
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -548,6 +655,7 @@ mod tests {
             scope_id: None,
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -555,6 +663,23 @@ mod tests {
         assert!(!quota.is_valid());
     }
 
+    #[test]
+    fn test_quota_invalid_burst_unlimited() {
+        let quota = Quota {
+            id: Some("b".to_owned()),
+            categories: smallvec![DataCategory::Error],
+            scope: QuotaScope::Organization,
+            scope_id: None,
+            limit: None,
+            window: Some(60),
+            burst: Some(10),
+            reason_code: None,
+        };
+
+        // A token bucket cannot refill without a limit.
+        assert!(!quota.is_valid());
+    }
+
     #[test]
     fn test_quota_matches_no_categories() {
         let quota = Quota {
@@ -564,6 +689,7 @@ mod tests {
             scope_id: None,
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -574,7 +700,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -587,6 +715,7 @@ mod tests {
             scope_id: None,
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -597,7 +726,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -610,6 +741,7 @@ mod tests {
             scope_id: None,
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -620,7 +752,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!quota.matches(ItemScoping {
@@ -630,7 +764,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -643,6 +779,7 @@ mod tests {
             scope_id: Some("not_a_number".to_owned()),
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -653,7 +790,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -666,6 +805,7 @@ mod tests {
             scope_id: Some("42".to_owned()),
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -676,7 +816,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!quota.matches(ItemScoping {
@@ -686,7 +828,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -699,6 +843,7 @@ mod tests {
             scope_id: Some("21".to_owned()),
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -709,7 +854,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!quota.matches(ItemScoping {
@@ -719,7 +866,9 @@ mod tests {
                 project_id: ProjectId::new(0),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -732,6 +881,7 @@ mod tests {
             scope_id: Some("17".to_owned()),
             limit: None,
             window: None,
+            burst: None,
             reason_code: None,
         };
 
@@ -742,7 +892,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(17),
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!quota.matches(ItemScoping {
@@ -752,7 +904,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(0),
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!quota.matches(ItemScoping {
@@ -762,7 +916,62 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
+
+    #[test]
+    fn test_quota_matches_release_scope() {
+        let quota = Quota {
+            id: None,
+            categories: DataCategories::new(),
+            scope: QuotaScope::Release,
+            scope_id: Some("1.0.0".to_owned()),
+            limit: None,
+            window: None,
+            burst: None,
+            reason_code: None,
+        };
+
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(21),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(17),
+        };
+
+        let item_scoping = scoping.item(DataCategory::Error);
+        assert!(quota.matches(item_scoping.with_release(Some("1.0.0"))));
+        assert!(!quota.matches(item_scoping.with_release(Some("2.0.0"))));
+        assert!(!quota.matches(item_scoping));
+        assert!(!quota.matches(item_scoping.with_environment(Some("1.0.0"))));
+    }
+
+    #[test]
+    fn test_quota_matches_environment_scope_unconstrained() {
+        let quota = Quota {
+            id: None,
+            categories: DataCategories::new(),
+            scope: QuotaScope::Environment,
+            scope_id: None,
+            limit: None,
+            window: None,
+            burst: None,
+            reason_code: None,
+        };
+
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(21),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(17),
+        };
+
+        let item_scoping = scoping.item(DataCategory::Error);
+        assert!(quota.matches(item_scoping.with_environment(Some("production"))));
+        assert!(quota.matches(item_scoping.with_environment(Some("staging"))));
+        assert!(!quota.matches(item_scoping));
+    }
 }
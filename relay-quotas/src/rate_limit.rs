@@ -4,7 +4,7 @@ use std::time::{Duration, Instant};
 
 use relay_common::{ProjectId, ProjectKey};
 
-use crate::quota::{DataCategories, ItemScoping, Quota, QuotaScope, ReasonCode, Scoping};
+use crate::quota::{DataCategories, ItemScoping, Quota, QuotaScope, ReasonCode};
 use crate::REJECT_ALL_SECS;
 
 /// A monotonic expiration marker for `RateLimit`s.
@@ -101,7 +101,7 @@ impl FromStr for RetryAfter {
 /// As opposed to `QuotaScope`, which only declared the class of the scope, this also carries
 /// information about the scope instance. That is, the specific identifiers of the individual scopes
 /// that a rate limit applied to.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 #[cfg_attr(test, derive(serde::Serialize))]
 pub enum RateLimitScope {
     /// An organization with identifier.
@@ -110,15 +110,28 @@ pub enum RateLimitScope {
     Project(ProjectId),
     /// A DSN public key.
     Key(ProjectKey),
+    /// A release name.
+    Release(String),
+    /// An environment name.
+    Environment(String),
 }
 
 impl RateLimitScope {
     /// Extracts a rate limiting scope from the given item scoping for a specific quota.
-    pub fn for_quota(scoping: &Scoping, scope: QuotaScope) -> Self {
+    pub fn for_quota(scoping: ItemScoping<'_>, scope: QuotaScope) -> Self {
         match scope {
             QuotaScope::Organization => RateLimitScope::Organization(scoping.organization_id),
             QuotaScope::Project => RateLimitScope::Project(scoping.project_id),
             QuotaScope::Key => RateLimitScope::Key(scoping.project_key),
+            // Without a release or environment, assume the most specific scope:
+            QuotaScope::Release => match scoping.release {
+                Some(release) => RateLimitScope::Release(release.to_owned()),
+                None => RateLimitScope::Key(scoping.project_key),
+            },
+            QuotaScope::Environment => match scoping.environment {
+                Some(environment) => RateLimitScope::Environment(environment.to_owned()),
+                None => RateLimitScope::Key(scoping.project_key),
+            },
             // For unknown scopes, assume the most specific scope:
             QuotaScope::Unknown => RateLimitScope::Key(scoping.project_key),
         }
@@ -130,6 +143,8 @@ impl RateLimitScope {
             Self::Key(_) => QuotaScope::Key.name(),
             Self::Project(_) => QuotaScope::Project.name(),
             Self::Organization(_) => QuotaScope::Organization.name(),
+            Self::Release(_) => QuotaScope::Release.name(),
+            Self::Environment(_) => QuotaScope::Environment.name(),
         }
     }
 }
@@ -154,7 +169,7 @@ pub struct RateLimit {
 
 impl RateLimit {
     /// Creates a new rate limit for the given `Quota`.
-    pub fn from_quota(quota: &Quota, scoping: &Scoping, retry_after: RetryAfter) -> Self {
+    pub fn from_quota(quota: &Quota, scoping: ItemScoping<'_>, retry_after: RetryAfter) -> Self {
         Self {
             categories: quota.categories.clone(),
             scope: RateLimitScope::for_quota(scoping, quota.scope),
@@ -174,6 +189,10 @@ impl RateLimit {
             RateLimitScope::Organization(org_id) => scoping.organization_id == org_id,
             RateLimitScope::Project(project_id) => scoping.project_id == project_id,
             RateLimitScope::Key(ref key) => scoping.project_key == *key,
+            RateLimitScope::Release(ref release) => scoping.release == Some(release.as_str()),
+            RateLimitScope::Environment(ref environment) => {
+                scoping.environment == Some(environment.as_str())
+            }
         }
     }
 }
@@ -261,7 +280,7 @@ impl RateLimits {
         for quota in quotas {
             if quota.limit == Some(0) && quota.matches(scoping) {
                 let retry_after = RetryAfter::from_secs(REJECT_ALL_SECS);
-                applied_limits.add(RateLimit::from_quota(quota, &*scoping, retry_after));
+                applied_limits.add(RateLimit::from_quota(quota, scoping, retry_after));
             }
         }
 
@@ -343,7 +362,7 @@ impl<'a> IntoIterator for &'a RateLimits {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::quota::DataCategory;
+    use crate::quota::{DataCategory, Scoping};
     use smallvec::smallvec;
 
     #[test]
@@ -407,7 +426,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!rate_limit.matches(ItemScoping {
@@ -417,7 +438,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -437,7 +460,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!rate_limit.matches(ItemScoping {
@@ -447,7 +472,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -467,7 +494,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!rate_limit.matches(ItemScoping {
@@ -477,7 +506,9 @@ mod tests {
                 project_id: ProjectId::new(0),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
@@ -499,7 +530,9 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
 
         assert!(!rate_limit.matches(ItemScoping {
@@ -509,10 +542,60 @@ mod tests {
                 project_id: ProjectId::new(21),
                 project_key: ProjectKey::parse("deadbeefdeadbeefdeadbeefdeadbeef").unwrap(),
                 key_id: None,
-            }
+            },
+            release: None,
+            environment: None,
         }));
     }
 
+    #[test]
+    fn test_rate_limit_matches_release() {
+        let rate_limit = RateLimit {
+            categories: DataCategories::new(),
+            scope: RateLimitScope::Release("1.0.0".to_owned()),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(1),
+        };
+
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(21),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: None,
+        };
+
+        let item_scoping = scoping.item(DataCategory::Error);
+        assert!(rate_limit.matches(item_scoping.with_release(Some("1.0.0"))));
+        assert!(!rate_limit.matches(item_scoping.with_release(Some("2.0.0"))));
+        assert!(!rate_limit.matches(item_scoping.with_environment(Some("1.0.0"))));
+        assert!(!rate_limit.matches(item_scoping));
+    }
+
+    #[test]
+    fn test_rate_limit_scope_for_environment() {
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(21),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: None,
+        };
+
+        let item_scoping = scoping.item(DataCategory::Error);
+        assert_eq!(
+            RateLimitScope::for_quota(
+                item_scoping.with_environment(Some("production")),
+                QuotaScope::Environment
+            ),
+            RateLimitScope::Environment("production".to_owned())
+        );
+
+        // Without an environment, the rate limit falls back to the project key.
+        assert_eq!(
+            RateLimitScope::for_quota(item_scoping, QuotaScope::Environment),
+            RateLimitScope::Key(scoping.project_key)
+        );
+    }
+
     #[test]
     fn test_rate_limits_add_replacement() {
         let mut rate_limits = RateLimits::new();
@@ -746,6 +829,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
             },
+            release: None,
+            environment: None,
         });
 
         // Check that the error limit is applied
@@ -793,6 +878,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: None,
             },
+            release: None,
+            environment: None,
         };
 
         let quotas = &[Quota {
@@ -802,6 +889,7 @@ mod tests {
             scope_id: Some("42".to_owned()),
             limit: Some(0),
             window: None,
+            burst: None,
             reason_code: Some(ReasonCode::new("zero")),
         }];
 
@@ -1,14 +1,15 @@
 use std::fmt;
 use std::sync::Arc;
 
-use failure::Fail;
-
 use relay_common::UnixTimestamp;
 use relay_log::protocol::value;
-use relay_redis::{redis::Script, RedisError, RedisPool};
+use relay_redis::redis::{self, Script};
+use relay_redis::{RedisError, RedisPool};
 
-use crate::quota::{ItemScoping, Quota, QuotaScope};
+use crate::limiter::{QuotaUsage, RateLimiter, RateLimitingError};
+use crate::quota::{DataCategory, ItemScoping, Quota, QuotaScope, Scoping};
 use crate::rate_limit::{RateLimit, RateLimits, RetryAfter};
+use crate::spike::SpikeProtection;
 use crate::REJECT_ALL_SECS;
 
 /// The `grace` period allows accomodating for clock drift in TTL
@@ -16,18 +17,25 @@ use crate::REJECT_ALL_SECS;
 /// metrics may not be in sync with the computer running this code.
 const GRACE: u64 = 60;
 
-/// An error returned by `RedisRateLimiter`.
-#[derive(Debug, Fail)]
-pub enum RateLimitingError {
-    /// Failed to communicate with Redis.
-    #[fail(display = "failed to communicate with redis")]
-    Redis(#[cause] RedisError),
-}
-
 fn load_lua_script() -> Script {
     Script::new(include_str!("is_rate_limited.lua"))
 }
 
+fn load_spike_script() -> Script {
+    Script::new(include_str!("is_spike.lua"))
+}
+
+/// Returns the key of the spike protection counter for the given item and window.
+fn get_spike_key(item_scoping: ItemScoping<'_>, slot: u64) -> String {
+    format!(
+        "spike:{{{org}}}{project}:{category}:{slot}",
+        org = item_scoping.organization_id,
+        project = item_scoping.project_id,
+        category = item_scoping.category,
+        slot = slot,
+    )
+}
+
 fn get_refunded_quota_key(counter_key: &str) -> String {
     format!("r:{}", counter_key)
 }
@@ -82,6 +90,16 @@ impl<'a> RedisQuota<'a> {
         self.limit.map(i64::from).unwrap_or(-1)
     }
 
+    /// Returns the burst of a token bucket quota, which requires a limit.
+    fn burst(&self) -> Option<u32> {
+        self.limit.and(self.quota.burst)
+    }
+
+    /// Returns the burst value for Redis (`-1` for fixed window quotas, otherwise the burst).
+    fn burst_arg(&self) -> i64 {
+        self.burst().map(i64::from).unwrap_or(-1)
+    }
+
     fn shift(&self) -> u64 {
         self.scoping.organization_id % self.window
     }
@@ -91,6 +109,12 @@ impl<'a> RedisQuota<'a> {
     }
 
     fn expiry(&self) -> UnixTimestamp {
+        if let (Some(burst), Some(limit)) = (self.burst(), self.limit) {
+            // Token buckets expire once they have been refilled entirely.
+            let refill = (u64::from(burst) * self.window + u64::from(limit) - 1) / u64::from(limit);
+            return UnixTimestamp::from_secs(self.timestamp.as_secs() + refill);
+        }
+
         let next_slot = self.slot() + 1;
         let next_start = next_slot * self.window + self.shift();
         UnixTimestamp::from_secs(next_start)
@@ -98,10 +122,20 @@ impl<'a> RedisQuota<'a> {
 
     fn key(&self) -> String {
         // The subscope id is only formatted into the key if the quota is not organization-scoped.
-        // The organization id is always included.
+        // The organization id is always included. Release and environment scopes are identified by
+        // their name.
         let subscope = match self.quota.scope {
             QuotaScope::Organization => None,
-            scope => self.scoping.scope_id(scope),
+            scope @ QuotaScope::Release | scope @ QuotaScope::Environment => {
+                self.scoping.scope_value(scope).map(str::to_owned)
+            }
+            scope => self.scoping.scope_id(scope).map(|id| id.to_string()),
+        };
+
+        // Token buckets are not bound to a fixed window and use a single key.
+        let slot = match self.burst() {
+            Some(_) => "tb".to_owned(),
+            None => self.slot().to_string(),
         };
 
         format!(
@@ -109,7 +143,7 @@ impl<'a> RedisQuota<'a> {
             id = self.prefix,
             org = self.scoping.organization_id,
             subscope = OptionalDisplay(subscope),
-            slot = self.slot(),
+            slot = slot,
         )
     }
 }
@@ -136,6 +170,7 @@ impl std::ops::Deref for RedisQuota<'_> {
 pub struct RedisRateLimiter {
     pool: RedisPool,
     script: Arc<Script>,
+    spike_script: Arc<Script>,
     max_limit: Option<u64>,
 }
 
@@ -145,6 +180,7 @@ impl RedisRateLimiter {
         RedisRateLimiter {
             pool,
             script: Arc::new(load_lua_script()),
+            spike_script: Arc::new(load_spike_script()),
             max_limit: None,
         }
     }
@@ -158,16 +194,18 @@ impl RedisRateLimiter {
         self
     }
 
-    /// Checks whether any of the quotas in effect for the given project and project key has been
-    /// exceeded and records consumption of the quota.
-    ///
-    /// By invoking this method, the caller signals that data is being ingested and needs to be
-    /// counted against the quota. This increment happens atomically if none of the quotas have been
-    /// exceeded. Otherwise, a rate limit is returned and data is not counted against the quotas.
-    ///
-    /// If no key is specified, then only organization-wide and project-wide quotas are checked. If
-    /// a key is specified, then key-quotas are also checked.
-    pub fn is_rate_limited(
+    /// Creates a rate limit bounded by `max_limit`.
+    fn retry_after(&self, mut seconds: u64) -> RetryAfter {
+        if let Some(max_limit) = self.max_limit {
+            seconds = std::cmp::min(seconds, max_limit);
+        }
+
+        RetryAfter::from_secs(seconds)
+    }
+}
+
+impl RateLimiter for RedisRateLimiter {
+    fn is_rate_limited(
         &self,
         quotas: &[Quota],
         item_scoping: ItemScoping<'_>,
@@ -187,7 +225,7 @@ impl RedisRateLimiter {
                 // increment any keys, as one quota has reached capacity (this is how regular quotas
                 // behave as well).
                 let retry_after = self.retry_after(REJECT_ALL_SECS);
-                rate_limits.add(RateLimit::from_quota(quota, &*item_scoping, retry_after));
+                rate_limits.add(RateLimit::from_quota(quota, item_scoping, retry_after));
             } else if let Some(quota) = RedisQuota::new(quota, item_scoping, timestamp) {
                 // Remaining quotas are expected to be trackable in Redis.
                 let key = quota.key();
@@ -199,6 +237,9 @@ impl RedisRateLimiter {
                 invocation.arg(quota.limit());
                 invocation.arg(quota.expiry().as_secs() + GRACE);
                 invocation.arg(quantity);
+                invocation.arg(quota.burst_arg());
+                invocation.arg(quota.window);
+                invocation.arg(timestamp.as_secs());
 
                 tracked_quotas.push(quota);
             } else {
@@ -218,28 +259,153 @@ impl RedisRateLimiter {
         }
 
         let mut client = self.pool.client().map_err(RateLimitingError::Redis)?;
-        let rejections: Vec<bool> = invocation
+        let rejections: Vec<u64> = invocation
             .invoke(&mut client.connection())
             .map_err(RedisError::Redis)
             .map_err(RateLimitingError::Redis)?;
 
-        for (quota, is_rejected) in tracked_quotas.iter().zip(rejections) {
-            if is_rejected {
-                let retry_after = self.retry_after((quota.expiry() - timestamp).as_secs());
-                rate_limits.add(RateLimit::from_quota(&*quota, &*item_scoping, retry_after));
+        for (quota, rejection) in tracked_quotas.iter().zip(rejections) {
+            if rejection > 0 {
+                // Token buckets report the time until enough tokens have been refilled.
+                let seconds = match quota.burst() {
+                    Some(_) => rejection,
+                    None => (quota.expiry() - timestamp).as_secs(),
+                };
+
+                let retry_after = self.retry_after(seconds);
+                rate_limits.add(RateLimit::from_quota(&*quota, item_scoping, retry_after));
             }
         }
 
         Ok(rate_limits)
     }
 
-    /// Creates a rate limit bounded by `max_limit`.
-    fn retry_after(&self, mut seconds: u64) -> RetryAfter {
-        if let Some(max_limit) = self.max_limit {
-            seconds = std::cmp::min(seconds, max_limit);
+    fn quota_usage(
+        &self,
+        quotas: &[Quota],
+        scoping: &Scoping,
+    ) -> Result<Vec<Option<QuotaUsage>>, RateLimitingError> {
+        let timestamp = UnixTimestamp::now();
+        let item_scoping = scoping.item(DataCategory::Default);
+
+        let tracked_quotas: Vec<_> = quotas
+            .iter()
+            .map(|quota| {
+                if quota.limit == Some(0) || !quota.matches_scope(item_scoping) {
+                    return None;
+                }
+
+                RedisQuota::new(quota, item_scoping, timestamp)
+            })
+            .collect();
+
+        if tracked_quotas.iter().all(Option::is_none) {
+            return Ok(vec![None; quotas.len()]);
         }
 
-        RetryAfter::from_secs(seconds)
+        // Read two values per quota: The counter and its refund counter for fixed windows, or the
+        // remaining tokens and the time of the last refill for token buckets.
+        let mut pipeline = redis::pipe();
+        for quota in tracked_quotas.iter().flatten() {
+            let key = quota.key();
+            if quota.burst().is_some() {
+                pipeline.cmd("HMGET").arg(&key).arg("tokens").arg("ts");
+            } else {
+                pipeline
+                    .cmd("MGET")
+                    .arg(&key)
+                    .arg(get_refunded_quota_key(&key));
+            }
+        }
+
+        let mut client = self.pool.client().map_err(RateLimitingError::Redis)?;
+        let values: Vec<(Option<f64>, Option<f64>)> = pipeline
+            .query(&mut client.connection())
+            .map_err(RedisError::Redis)
+            .map_err(RateLimitingError::Redis)?;
+
+        let mut values = values.into_iter();
+        let now = timestamp.as_secs();
+
+        let usage = tracked_quotas
+            .iter()
+            .map(|quota| {
+                let quota = quota.as_ref()?;
+                let (first, second) = values.next()?;
+
+                if let (Some(burst), Some(limit)) = (quota.burst(), quota.limit) {
+                    // Refill the bucket like the script does, without storing it.
+                    let (burst, rate) = (f64::from(burst), f64::from(limit) / quota.window as f64);
+                    let elapsed = (now as f64 - second.unwrap_or(now as f64)).max(0.0);
+                    let tokens = (first.unwrap_or(burst) + elapsed * rate).min(burst);
+                    let remaining = tokens as u64;
+
+                    return Some(QuotaUsage {
+                        window_start: now,
+                        window_end: now + ((burst - tokens).max(0.0) / rate).ceil() as u64,
+                        consumed: burst as u64 - remaining,
+                        remaining: Some(remaining),
+                    });
+                }
+
+                let consumed = (first.unwrap_or(0.0) - second.unwrap_or(0.0)).max(0.0) as u64;
+                let window_end = quota.expiry().as_secs();
+                Some(QuotaUsage {
+                    window_start: window_end - quota.window,
+                    window_end,
+                    consumed,
+                    remaining: quota
+                        .limit
+                        .map(|limit| u64::from(limit).saturating_sub(consumed)),
+                })
+            })
+            .collect();
+
+        Ok(usage)
+    }
+
+    fn check_spike(
+        &self,
+        spike_protection: &SpikeProtection,
+        item_scoping: ItemScoping<'_>,
+        quantity: usize,
+    ) -> Result<RateLimits, RateLimitingError> {
+        let mut rate_limits = RateLimits::new();
+        if !spike_protection.is_valid() {
+            return Ok(rate_limits);
+        }
+
+        let now = UnixTimestamp::now().as_secs();
+        let slot = spike_protection.slot(now);
+        let first = slot.saturating_sub(spike_protection.baseline_windows);
+
+        let mut invocation = self.spike_script.prepare_invoke();
+        invocation.key(get_spike_key(item_scoping, slot));
+        for baseline_slot in first..slot {
+            invocation.key(get_spike_key(item_scoping, baseline_slot));
+        }
+
+        // Keep the counter until it no longer contributes to the baseline.
+        let expiry = (slot + spike_protection.baseline_windows + 1) * spike_protection.window;
+
+        invocation.arg(quantity);
+        invocation.arg(spike_protection.multiple);
+        invocation.arg(spike_protection.min_volume);
+        invocation.arg(spike_protection.baseline_windows);
+        invocation.arg(expiry + GRACE);
+
+        let mut client = self.pool.client().map_err(RateLimitingError::Redis)?;
+        let rejected: u64 = invocation
+            .invoke(&mut client.connection())
+            .map_err(RedisError::Redis)
+            .map_err(RateLimitingError::Redis)?;
+
+        if rejected > 0 {
+            let retry_after = self.retry_after(spike_protection.window_remaining(now));
+            rate_limits.add(spike_protection.rate_limit(item_scoping, retry_after));
+        }
+
+        Ok(rate_limits)
     }
 }
 
@@ -250,6 +416,7 @@ mod tests {
 
     use relay_common::{ProjectId, ProjectKey};
     use relay_redis::redis::Commands;
+    use smallvec::smallvec;
 
     use crate::quota::{DataCategories, DataCategory, ReasonCode, Scoping};
     use crate::rate_limit::RateLimitScope;
@@ -263,6 +430,7 @@ mod tests {
         RedisRateLimiter {
             pool: RedisPool::single(&url).unwrap(),
             script: Arc::new(load_lua_script()),
+            spike_script: Arc::new(load_spike_script()),
             max_limit: None,
         }
     }
@@ -277,6 +445,7 @@ mod tests {
                 scope_id: None,
                 limit: Some(0),
                 window: None,
+                burst: None,
                 reason_code: Some(ReasonCode::new("get_lost")),
             },
             Quota {
@@ -286,6 +455,7 @@ mod tests {
                 scope_id: None,
                 limit: None,
                 window: Some(42),
+                burst: None,
                 reason_code: Some(ReasonCode::new("unlimited")),
             },
         ];
@@ -298,6 +468,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(44),
             },
+            release: None,
+            environment: None,
         };
 
         let rate_limits: Vec<RateLimit> = build_rate_limiter()
@@ -326,6 +498,7 @@ mod tests {
             scope_id: None,
             limit: Some(5),
             window: Some(60),
+            burst: None,
             reason_code: Some(ReasonCode::new("get_lost")),
         }];
 
@@ -337,6 +510,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(44),
             },
+            release: None,
+            environment: None,
         };
 
         let rate_limiter = build_rate_limiter();
@@ -374,6 +549,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(44),
             },
+            release: None,
+            environment: None,
         };
 
         let rate_limits: Vec<RateLimit> = build_rate_limiter()
@@ -395,6 +572,7 @@ mod tests {
                 scope_id: None,
                 limit: None,
                 window: Some(1),
+                burst: None,
                 reason_code: Some(ReasonCode::new("project_quota0")),
             },
             Quota {
@@ -404,6 +582,7 @@ mod tests {
                 scope_id: None,
                 limit: Some(1),
                 window: Some(1),
+                burst: None,
                 reason_code: Some(ReasonCode::new("project_quota1")),
             },
         ];
@@ -416,6 +595,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(44),
             },
+            release: None,
+            environment: None,
         };
 
         let rate_limiter = build_rate_limiter();
@@ -452,6 +633,7 @@ mod tests {
             scope_id: None,
             limit: Some(500),
             window: Some(60),
+            burst: None,
             reason_code: Some(ReasonCode::new("get_lost")),
         }];
 
@@ -463,6 +645,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(44),
             },
+            release: None,
+            environment: None,
         };
 
         let rate_limiter = build_rate_limiter();
@@ -498,6 +682,7 @@ mod tests {
             scope: QuotaScope::Project,
             scope_id: Some("42".to_owned()),
             window: Some(2),
+            burst: None,
             limit: Some(0),
             reason_code: None,
         };
@@ -510,6 +695,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(4711),
             },
+            release: None,
+            environment: None,
         };
 
         let timestamp = UnixTimestamp::from_secs(123_123_123);
@@ -517,6 +704,35 @@ mod tests {
         assert_eq!(redis_quota.key(), "quota:foo{69420}42:61561561");
     }
 
+    #[test]
+    fn test_get_redis_key_release() {
+        let quota = Quota {
+            id: Some("foo".to_owned()),
+            categories: DataCategories::new(),
+            scope: QuotaScope::Release,
+            scope_id: None,
+            window: Some(2),
+            burst: None,
+            limit: Some(0),
+            reason_code: None,
+        };
+
+        let scoping = Scoping {
+            organization_id: 69420,
+            project_id: ProjectId::new(42),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(4711),
+        };
+
+        let item_scoping = scoping
+            .item(DataCategory::Error)
+            .with_release(Some("app@1.0.0"));
+
+        let timestamp = UnixTimestamp::from_secs(123_123_123);
+        let redis_quota = RedisQuota::new(&quota, item_scoping, timestamp).unwrap();
+        assert_eq!(redis_quota.key(), "quota:foo{69420}app@1.0.0:61561561");
+    }
+
     #[test]
     fn test_get_redis_key_unscoped() {
         let quota = Quota {
@@ -525,6 +741,7 @@ mod tests {
             scope: QuotaScope::Organization,
             scope_id: None,
             window: Some(10),
+            burst: None,
             limit: Some(0),
             reason_code: None,
         };
@@ -537,6 +754,8 @@ mod tests {
                 project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
                 key_id: Some(4711),
             },
+            release: None,
+            environment: None,
         };
 
         let timestamp = UnixTimestamp::from_secs(234_531);
@@ -576,9 +795,15 @@ mod tests {
             .arg(1) // limit
             .arg(now + 60) // expiry
             .arg(1) // quantity
+            .arg(-1) // burst
+            .arg(60) // window
+            .arg(now) // timestamp
             .arg(2) // limit
             .arg(now + 120) // expiry
-            .arg(1); // quantity
+            .arg(1) // quantity
+            .arg(-1) // burst
+            .arg(120) // window
+            .arg(now); // timestamp
 
         // The item should not be rate limited by either key.
         assert_eq!(
@@ -624,7 +849,10 @@ mod tests {
             .key(&baz) // refund key
             .arg(1) // limit
             .arg(now + 60) // expiry
-            .arg(1); // quantity
+            .arg(1) // quantity
+            .arg(-1) // burst
+            .arg(60) // window
+            .arg(now); // timestamp
 
         // increment
         assert_eq!(
@@ -644,7 +872,10 @@ mod tests {
             .key(&apple) // refund key
             .arg(1) // limit
             .arg(now + 60) // expiry
-            .arg(1); // quantity
+            .arg(1) // quantity
+            .arg(-1) // burst
+            .arg(60) // window
+            .arg(now); // timestamp
 
         // test that refund key is used
         assert_eq!(
@@ -652,4 +883,231 @@ mod tests {
             vec![false]
         );
     }
+
+    #[test]
+    fn test_is_rate_limited_script_token_bucket() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap();
+
+        let rate_limiter = build_rate_limiter();
+        let mut client = rate_limiter.pool.client().expect("get client");
+        let mut conn = client.connection();
+
+        let key = format!("tb___{}", now);
+        let r_key = format!("r:tb___{}", now);
+
+        let script = load_lua_script();
+
+        let mut invoke = |quantity: u64, timestamp: u64| {
+            script
+                .prepare_invoke()
+                .key(&key) // key
+                .key(&r_key) // refund key
+                .arg(30) // limit
+                .arg(timestamp + 20) // expiry
+                .arg(quantity) // quantity
+                .arg(10) // burst
+                .arg(60) // window
+                .arg(timestamp) // timestamp
+                .invoke::<Vec<u64>>(&mut conn)
+                .unwrap()
+        };
+
+        // The burst is absorbed at once.
+        assert_eq!(invoke(10, now), vec![0]);
+
+        // The bucket refills at a rate of half a token per second.
+        assert_eq!(invoke(3, now), vec![6]);
+        assert_eq!(invoke(3, now + 4), vec![2]);
+        assert_eq!(invoke(2, now + 4), vec![0]);
+
+        // A quantity larger than the burst waits for the bucket to refill entirely.
+        assert_eq!(invoke(11, now + 4), vec![20]);
+    }
+
+    #[test]
+    fn test_token_bucket_quota() {
+        let quotas = &[Quota {
+            id: Some(format!("test_token_bucket_quota_{:?}", SystemTime::now())),
+            categories: DataCategories::new(),
+            scope: QuotaScope::Organization,
+            scope_id: None,
+            limit: Some(60),
+            window: Some(3600),
+            burst: Some(5),
+            reason_code: Some(ReasonCode::new("get_lost")),
+        }];
+
+        let scoping = ItemScoping {
+            category: DataCategory::Error,
+            scoping: &Scoping {
+                organization_id: 42,
+                project_id: ProjectId::new(43),
+                project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                key_id: Some(44),
+            },
+            release: None,
+            environment: None,
+        };
+
+        let rate_limiter = build_rate_limiter();
+
+        // The bucket holds 5 tokens, even though the limit is larger.
+        for i in 0..6 {
+            let rate_limits: Vec<RateLimit> = rate_limiter
+                .is_rate_limited(quotas, scoping, 1)
+                .expect("rate limiting failed")
+                .into_iter()
+                .collect();
+
+            if i >= 5 {
+                assert_eq!(rate_limits.len(), 1);
+                // One token refills every minute.
+                assert!(rate_limits[0].retry_after.remaining_seconds() <= 60);
+            } else {
+                assert_eq!(rate_limits, vec![]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_spike() {
+        let spike_protection = SpikeProtection {
+            multiple: 2.0,
+            min_volume: 5,
+            window: 3600,
+            baseline_windows: 2,
+        };
+
+        // Use a unique project, since counters are not scoped by an id like quotas.
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .subsec_nanos();
+
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(u64::from(nanos)),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(44),
+        };
+
+        let rate_limiter = build_rate_limiter();
+
+        // Without a baseline, the minimum volume is accepted.
+        for i in 0..6 {
+            let rate_limits: Vec<RateLimit> = rate_limiter
+                .check_spike(&spike_protection, scoping.item(DataCategory::Error), 1)
+                .expect("spike protection failed")
+                .into_iter()
+                .collect();
+
+            if i >= 5 {
+                assert_eq!(
+                    rate_limits,
+                    vec![RateLimit {
+                        categories: smallvec![DataCategory::Error],
+                        scope: RateLimitScope::Project(scoping.project_id),
+                        reason_code: Some(ReasonCode::spike_protection()),
+                        retry_after: rate_limits[0].retry_after,
+                    }]
+                );
+            } else {
+                assert_eq!(rate_limits, vec![]);
+            }
+        }
+
+        // Other data categories are counted separately.
+        let rate_limits = rate_limiter
+            .check_spike(
+                &spike_protection,
+                scoping.item(DataCategory::Transaction),
+                1,
+            )
+            .expect("spike protection failed");
+        assert!(!rate_limits.is_limited());
+    }
+
+    #[test]
+    fn test_quota_usage() {
+        let quotas = &[
+            Quota {
+                id: Some(format!("test_quota_usage_{:?}", SystemTime::now())),
+                categories: DataCategories::new(),
+                scope: QuotaScope::Organization,
+                scope_id: None,
+                limit: Some(5),
+                window: Some(60),
+                burst: None,
+                reason_code: Some(ReasonCode::new("get_lost")),
+            },
+            Quota {
+                id: None,
+                categories: DataCategories::new(),
+                scope: QuotaScope::Organization,
+                scope_id: None,
+                limit: Some(0),
+                window: None,
+                burst: None,
+                reason_code: Some(ReasonCode::new("get_lost")),
+            },
+        ];
+
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(43),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(44),
+        };
+
+        let rate_limiter = build_rate_limiter();
+        let rate_limits = rate_limiter
+            .is_rate_limited(&quotas[..1], scoping.item(DataCategory::Error), 3)
+            .expect("rate limiting failed");
+        assert!(!rate_limits.is_limited());
+
+        let usage = rate_limiter
+            .quota_usage(quotas, &scoping)
+            .expect("quota usage failed");
+
+        let fixed = usage[0].as_ref().expect("usage of tracked quota");
+        assert_eq!(fixed.consumed, 3);
+        assert_eq!(fixed.remaining, Some(2));
+        assert_eq!(fixed.window_end - fixed.window_start, 60);
+        assert_eq!(usage[1], None);
+    }
+
+    #[test]
+    fn test_get_redis_key_token_bucket() {
+        let quota = Quota {
+            id: Some("foo".to_owned()),
+            categories: DataCategories::new(),
+            scope: QuotaScope::Organization,
+            scope_id: None,
+            window: Some(10),
+            burst: Some(5),
+            limit: Some(10),
+            reason_code: None,
+        };
+
+        let scoping = ItemScoping {
+            category: DataCategory::Error,
+            scoping: &Scoping {
+                organization_id: 69420,
+                project_id: ProjectId::new(42),
+                project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                key_id: Some(4711),
+            },
+            release: None,
+            environment: None,
+        };
+
+        let timestamp = UnixTimestamp::from_secs(234_531);
+        let redis_quota = RedisQuota::new(&quota, scoping, timestamp).unwrap();
+        assert_eq!(redis_quota.key(), "quota:foo{69420}:tb");
+        // The bucket refills entirely after 5 seconds.
+        assert_eq!(redis_quota.expiry().as_secs(), 234_536);
+    }
 }
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use smallvec::smallvec;
+
+use crate::quota::{ItemScoping, ReasonCode};
+use crate::rate_limit::{RateLimit, RateLimitScope, RetryAfter};
+
+fn default_min_volume() -> u64 {
+    100
+}
+
+fn default_window() -> u64 {
+    60
+}
+
+fn default_baseline_windows() -> u64 {
+    10
+}
+
+/// Configuration for adaptive rate limits that protect against spikes in a project's volume.
+///
+/// Spike protection counts the quantity accepted for every data category of a project in fixed
+/// windows of `window` seconds. The average of the previous `baseline_windows` windows forms the
+/// baseline of the project. Once the quantity in the current window would exceed `multiple` times
+/// the baseline, all further items of this category are rejected until the window ends.
+///
+/// Since the baseline is low for projects with little or no history, a quantity of `min_volume`
+/// per window is always accepted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpikeProtection {
+    /// The factor by which the quantity of a window may exceed the baseline.
+    pub multiple: f64,
+
+    /// The quantity per window that is accepted regardless of the baseline. Defaults to `100`.
+    #[serde(default = "default_min_volume")]
+    pub min_volume: u64,
+
+    /// The size of a window in seconds. Defaults to `60`.
+    #[serde(default = "default_window")]
+    pub window: u64,
+
+    /// The number of previous windows that make up the baseline. Defaults to `10`.
+    #[serde(default = "default_baseline_windows")]
+    pub baseline_windows: u64,
+}
+
+impl SpikeProtection {
+    /// Returns whether this configuration can be enforced.
+    ///
+    /// Spike protection requires a positive `multiple`, as well as a `window` and a number of
+    /// `baseline_windows` greater than zero. Invalid configurations are ignored by rate limiters.
+    pub fn is_valid(&self) -> bool {
+        self.multiple.is_finite()
+            && self.multiple > 0.0
+            && self.window > 0
+            && self.baseline_windows > 0
+    }
+
+    /// Returns the index of the window containing the given timestamp.
+    pub(crate) fn slot(&self, timestamp: u64) -> u64 {
+        timestamp / self.window
+    }
+
+    /// Returns the maximum quantity accepted within a window.
+    ///
+    /// `baseline_total` is the total quantity accepted within the previous `baseline_windows`.
+    pub(crate) fn threshold(&self, baseline_total: u64) -> u64 {
+        let baseline = baseline_total as f64 / self.baseline_windows as f64;
+        ((baseline * self.multiple) as u64).max(self.min_volume)
+    }
+
+    /// Returns the seconds until the window containing the given timestamp ends.
+    pub(crate) fn window_remaining(&self, timestamp: u64) -> u64 {
+        self.window - timestamp % self.window
+    }
+
+    /// Creates the rate limit for a spike of the given item.
+    ///
+    /// The rate limit should last until the window ends, see [`window_remaining`], but rate
+    /// limiters may bound it further.
+    ///
+    /// [`window_remaining`]: Self::window_remaining
+    pub(crate) fn rate_limit(
+        &self,
+        item_scoping: ItemScoping<'_>,
+        retry_after: RetryAfter,
+    ) -> RateLimit {
+        RateLimit {
+            categories: smallvec![item_scoping.category],
+            scope: RateLimitScope::Project(item_scoping.project_id),
+            reason_code: Some(ReasonCode::spike_protection()),
+            retry_after,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spike_protection() {
+        let json = r#"{"multiple": 2.5}"#;
+        let spike_protection = serde_json::from_str::<SpikeProtection>(json).unwrap();
+
+        assert!(spike_protection.is_valid());
+        assert_eq!(spike_protection.min_volume, 100);
+        assert_eq!(spike_protection.window, 60);
+        assert_eq!(spike_protection.baseline_windows, 10);
+    }
+
+    #[test]
+    fn test_spike_protection_invalid() {
+        let json = r#"{"multiple": 2, "window": 0}"#;
+        let spike_protection = serde_json::from_str::<SpikeProtection>(json).unwrap();
+        assert!(!spike_protection.is_valid());
+
+        let json = r#"{"multiple": 0}"#;
+        let spike_protection = serde_json::from_str::<SpikeProtection>(json).unwrap();
+        assert!(!spike_protection.is_valid());
+    }
+
+    #[test]
+    fn test_spike_protection_threshold() {
+        let spike_protection = SpikeProtection {
+            multiple: 2.5,
+            min_volume: 100,
+            window: 60,
+            baseline_windows: 10,
+        };
+
+        // Without history, the minimum volume applies.
+        assert_eq!(spike_protection.threshold(0), 100);
+        // 10 windows with 200 each on average.
+        assert_eq!(spike_protection.threshold(2000), 500);
+    }
+}
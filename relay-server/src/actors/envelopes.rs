@@ -7,24 +7,33 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use actix::prelude::*;
-use chrono::{DateTime, Duration as SignedDuration, Utc};
+use chrono::{DateTime, Duration as SignedDuration, TimeZone, Utc};
 use failure::Fail;
 use futures::{future, prelude::*};
 use serde_json::Value as SerdeValue;
 
 use relay_common::{clone, metric, ProjectId, ProjectKey, UnixTimestamp};
-use relay_config::{Config, RelayMode};
-use relay_general::pii::{PiiAttachmentsProcessor, PiiProcessor};
+use relay_config::{Config, RateLimiterKind, RelayMode};
+use relay_filter::FilterStatKey;
+use relay_general::pii::{
+    PiiAttachmentsProcessor, PiiProcessor, PiiStats, ScrubEncodings, BUILTIN_RULES,
+};
 use relay_general::processor::{process_value, ProcessingState};
 use relay_general::protocol::{
-    self, Breadcrumb, Csp, Event, EventId, EventType, ExpectCt, ExpectStaple, Hpkp, IpAddr,
-    LenientString, Metrics, SecurityReportType, SessionUpdate, Timestamp, UserReport, Values,
+    self, Breadcrumb, ClientReport, Crash, Csp, Deprecation, Event, EventId, EventType, ExpectCt,
+    ExpectStaple, Hpkp, Intervention, IpAddr, LenientString, Metrics, Nel, SecurityReportType,
+    SessionUpdate, Tags, Timestamp, UserReport, Values,
 };
 use relay_general::store::ClockDriftProcessor;
-use relay_general::types::{Annotated, Array, FromValue, Object, ProcessingAction, Value};
+use relay_general::types::{
+    Annotated, Array, FromValue, Object, ProcessingAction, RemarkType, Value,
+};
 use relay_log::LogError;
 use relay_metrics::{Bucket, Metric};
-use relay_quotas::{DataCategory, RateLimits, Scoping};
+use relay_quotas::{
+    DataCategory, MemoryRateLimiter, Quota, QuotaUsage, RateLimiter, RateLimitingError, RateLimits,
+    Scoping,
+};
 use relay_redis::RedisPool;
 use relay_sampling::{RuleId, SamplingResult};
 
@@ -39,24 +48,30 @@ use crate::envelope::{self, AttachmentType, ContentType, Envelope, Item, ItemTyp
 use crate::extractors::{PartialDsn, RequestMeta};
 use crate::http::{HttpError, RequestBuilder};
 use crate::metrics::{RelayCounters, RelayHistograms, RelaySets, RelayTimers};
-use crate::service::ServerError;
-use crate::utils::{self, ChunkedFormDataAggregator, EnvelopeSummary, FormDataIter, FutureExt};
+use crate::service::{ServerError, ServerErrorKind};
+use crate::utils::{
+    self, ChunkedFormDataAggregator, EnvelopeLimiter, EnvelopeSummary, ErrorSpikeCounter,
+    FormDataIter, FutureExt,
+};
 
 #[cfg(feature = "processing")]
 use {
     crate::actors::store::{StoreEnvelope, StoreError, StoreForwarder},
-    crate::service::ServerErrorKind,
-    crate::utils::EnvelopeLimiter,
     failure::ResultExt,
-    relay_filter::FilterStatKey,
     relay_general::store::{GeoIpLookup, StoreConfig, StoreProcessor},
     relay_metrics::{DurationPrecision, MetricUnit, MetricValue},
-    relay_quotas::{RateLimitingError, RedisRateLimiter},
+    relay_quotas::RedisRateLimiter,
 };
 
 /// The minimum clock drift for correction to apply.
 const MINIMUM_CLOCK_DRIFT: Duration = Duration::from_secs(55 * 60);
 
+/// The maximum quantity of a single entry in a client report.
+///
+/// SDKs send client reports periodically, so larger quantities are implausible and are dropped
+/// instead of being emitted as outcomes.
+const MAX_CLIENT_DISCARD_QUANTITY: u32 = 1_000_000;
+
 #[derive(Debug, Fail)]
 pub enum QueueEnvelopeError {
     #[fail(display = "Too many envelopes (event_buffer_size reached)")]
@@ -108,7 +123,6 @@ enum ProcessingError {
     #[fail(display = "submission rejected with reason: {:?}", _0)]
     Rejected(DiscardReason),
 
-    #[cfg(feature = "processing")]
     #[fail(display = "event filtered with reason: {:?}", _0)]
     EventFiltered(FilterStatKey),
 
@@ -125,7 +139,6 @@ enum ProcessingError {
     #[fail(display = "envelope items were rate limited")]
     RateLimited(RateLimits),
 
-    #[cfg(feature = "processing")]
     #[fail(display = "failed to apply quotas")]
     QuotasFailed(#[cause] RateLimitingError),
 
@@ -156,7 +169,6 @@ impl ProcessingError {
             // Processing-only outcomes (Sentry-internal Relays)
             #[cfg(feature = "processing")]
             Self::InvalidUnrealReport(_) => Some(Outcome::Invalid(DiscardReason::ProcessUnreal)),
-            #[cfg(feature = "processing")]
             Self::EventFiltered(ref filter_stat_key) => Some(Outcome::Filtered(*filter_stat_key)),
             Self::TraceSampled(rule_id) => Some(Outcome::FilteredSampling(rule_id)),
             Self::EventSampled(rule_id) => Some(Outcome::FilteredSampling(rule_id)),
@@ -167,11 +179,10 @@ impl ProcessingError {
             | Self::ProjectFailed(_)
             | Self::Timeout
             | Self::ProcessingFailed(_)
+            | Self::QuotasFailed(_)
             | Self::MissingProjectId => Some(Outcome::Invalid(DiscardReason::Internal)),
             #[cfg(feature = "processing")]
-            Self::StoreFailed(_) | Self::QuotasFailed(_) => {
-                Some(Outcome::Invalid(DiscardReason::Internal))
-            }
+            Self::StoreFailed(_) => Some(Outcome::Invalid(DiscardReason::Internal)),
 
             // Rate limiting outcomes are emitted at the source.
             Self::RateLimited(_) => None,
@@ -212,12 +223,13 @@ struct ProcessEnvelopeState {
     /// resulting item.
     sample_rates: Option<Value>,
 
-    /// Rate limits returned in processing mode.
+    /// Rate limits returned by the rate limiter.
     ///
-    /// The rate limiter is invoked in processing mode, after which the resulting limits are stored
-    /// in this field. Note that there can be rate limits even if the envelope still carries items.
+    /// If a rate limiter is configured, it is invoked during processing, after which the resulting
+    /// limits are stored in this field. Note that there can be rate limits even if the envelope
+    /// still carries items.
     ///
-    /// These are always empty in non-processing mode, since the rate limiter is not invoked.
+    /// These are always empty if Relay does not enforce quotas on its own.
     rate_limits: RateLimits,
 
     /// Metrics extracted from items in the envelope.
@@ -276,7 +288,6 @@ impl ProcessEnvelopeState {
     }
 
     /// Removes the event payload from this processing state.
-    #[cfg(feature = "processing")]
     fn remove_event(&mut self) {
         self.event = Annotated::empty();
     }
@@ -448,24 +459,113 @@ fn extract_session_metrics(session: &SessionUpdate, target: &mut Vec<Metric>) {
     }
 }
 
+/// Records inbound filters in dry-run mode that matched the event.
+///
+/// Emits a metric for every matching filter and adds a `dry_run_filter` tag to the event listing
+/// all of them.
+fn track_dry_run_matches(event: &mut Event, filter_stat_keys: &[FilterStatKey]) {
+    if filter_stat_keys.is_empty() {
+        return;
+    }
+
+    for filter_stat_key in filter_stat_keys {
+        metric!(
+            counter(RelayCounters::EventFilteredDryRun) += 1,
+            filter = filter_stat_key.name(),
+        );
+    }
+
+    let names = filter_stat_keys
+        .iter()
+        .map(|filter_stat_key| filter_stat_key.name())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    event
+        .tags
+        .get_or_insert_with(Tags::default)
+        .0
+        .insert("dry_run_filter".to_owned(), Annotated::new(names));
+}
+
+/// Emits metrics for the data redacted by PII rules.
+///
+/// Builtin rules are tagged with their identifier. All other rules are defined per project and are
+/// tagged as `custom` to bound the number of distinct tag values.
+fn track_pii_stats(stats: &PiiStats) {
+    for (rule_id, remark_type, redaction_stats) in stats.iter() {
+        let rule_id = if BUILTIN_RULES.contains(&rule_id) {
+            rule_id
+        } else {
+            "custom"
+        };
+
+        let remark_type = match remark_type {
+            RemarkType::Annotated => "annotated",
+            RemarkType::Removed => "removed",
+            RemarkType::Substituted => "substituted",
+            RemarkType::Masked => "masked",
+            RemarkType::Pseudonymized => "pseudonymized",
+            RemarkType::Encrypted => "encrypted",
+        };
+
+        metric!(
+            counter(RelayCounters::PiiScrubbedFields) += redaction_stats.fields as i64,
+            rule = rule_id,
+            remark_type = remark_type,
+        );
+        metric!(
+            counter(RelayCounters::PiiScrubbedBytes) += redaction_stats.bytes as i64,
+            rule = rule_id,
+            remark_type = remark_type,
+        );
+    }
+}
+
+/// Creates the rate limiter for project quotas based on the configuration.
+///
+/// Without explicit configuration, processing Relays count quotas in Redis if it is available.
+fn create_rate_limiter(
+    config: &Config,
+    redis: Option<&RedisPool>,
+) -> Result<Option<Arc<dyn RateLimiter>>, ServerError> {
+    let max_limit = config.max_rate_limit();
+
+    match (config.rate_limiter(), redis) {
+        (Some(RateLimiterKind::Memory), _) => {
+            let rate_limiter = MemoryRateLimiter::new().max_limit(max_limit);
+            Ok(Some(Arc::new(rate_limiter)))
+        }
+        #[cfg(feature = "processing")]
+        (Some(RateLimiterKind::Redis), Some(pool)) | (None, Some(pool)) => {
+            let rate_limiter = RedisRateLimiter::new(pool.clone()).max_limit(max_limit);
+            Ok(Some(Arc::new(rate_limiter)))
+        }
+        (Some(RateLimiterKind::Redis), _) => {
+            relay_log::error!("the redis rate limiter requires processing and a redis config");
+            Err(ServerErrorKind::ConfigError.into())
+        }
+        (None, _) => Ok(None),
+    }
+}
+
 /// Synchronous service for processing envelopes.
 pub struct EnvelopeProcessor {
     config: Arc<Config>,
-    #[cfg(feature = "processing")]
-    rate_limiter: Option<RedisRateLimiter>,
+    error_spike_counter: ErrorSpikeCounter,
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
     #[cfg(feature = "processing")]
     geoip_lookup: Option<Arc<GeoIpLookup>>,
 }
 
 impl EnvelopeProcessor {
     /// Starts a multi-threaded envelope processor.
-    pub fn start(
-        config: Arc<Config>,
-        _redis: Option<RedisPool>,
-    ) -> Result<Addr<Self>, ServerError> {
+    pub fn start(config: Arc<Config>, redis: Option<RedisPool>) -> Result<Addr<Self>, ServerError> {
         let thread_count = config.cpu_concurrency();
         relay_log::info!("starting {} envelope processing workers", thread_count);
 
+        let rate_limiter = create_rate_limiter(&config, redis.as_ref())?;
+
         #[cfg(feature = "processing")]
         {
             let geoip_lookup = match config.geoip_path() {
@@ -475,13 +575,15 @@ impl EnvelopeProcessor {
                 None => None,
             };
 
-            let rate_limiter =
-                _redis.map(|pool| RedisRateLimiter::new(pool).max_limit(config.max_rate_limit()));
+            let error_spike_counter = match redis {
+                Some(ref pool) => ErrorSpikeCounter::redis(pool.clone()),
+                None => ErrorSpikeCounter::memory(config.error_spike_cache_size()),
+            };
 
             Ok(SyncArbiter::start(
                 thread_count,
                 clone!(config, || {
-                    EnvelopeProcessor::new(config.clone())
+                    EnvelopeProcessor::new(config.clone(), error_spike_counter.clone())
                         .with_rate_limiter(rate_limiter.clone())
                         .with_geoip_lookup(geoip_lookup.clone())
                 }),
@@ -489,26 +591,32 @@ impl EnvelopeProcessor {
         }
 
         #[cfg(not(feature = "processing"))]
-        Ok(SyncArbiter::start(
-            thread_count,
-            clone!(config, || EnvelopeProcessor::new(config.clone())),
-        ))
+        {
+            let error_spike_counter = ErrorSpikeCounter::memory(config.error_spike_cache_size());
+
+            Ok(SyncArbiter::start(
+                thread_count,
+                clone!(config, || {
+                    EnvelopeProcessor::new(config.clone(), error_spike_counter.clone())
+                        .with_rate_limiter(rate_limiter.clone())
+                }),
+            ))
+        }
     }
 
     #[inline]
-    fn new(config: Arc<Config>) -> Self {
+    fn new(config: Arc<Config>, error_spike_counter: ErrorSpikeCounter) -> Self {
         Self {
             config,
-            #[cfg(feature = "processing")]
+            error_spike_counter,
             rate_limiter: None,
             #[cfg(feature = "processing")]
             geoip_lookup: None,
         }
     }
 
-    #[cfg(feature = "processing")]
     #[inline]
-    fn with_rate_limiter(mut self, rate_limiter: Option<RedisRateLimiter>) -> Self {
+    fn with_rate_limiter(mut self, rate_limiter: Option<Arc<dyn RateLimiter>>) -> Self {
         self.rate_limiter = rate_limiter;
         self
     }
@@ -668,6 +776,79 @@ impl EnvelopeProcessor {
         });
     }
 
+    /// Extracts outcomes from all client reports in the envelope, if any.
+    ///
+    /// Client reports contain counts of items that the SDK discarded before sending them. In
+    /// processing mode, client reports are removed from the envelope and emitted as outcomes with
+    /// the `client_discard` outcome. Otherwise, they are forwarded to the upstream unchanged.
+    fn process_client_reports(&self, state: &mut ProcessEnvelopeState) {
+        if !self.config.processing_enabled() {
+            return;
+        }
+
+        let received = state.received_at;
+        let max_age = SignedDuration::seconds(self.config.max_secs_in_past());
+        let max_future = SignedDuration::seconds(self.config.max_secs_in_future());
+
+        let scoping = state.project_state.scope_request(state.envelope.meta());
+        let remote_addr = state.envelope.meta().remote_addr();
+        let outcome_producer = OutcomeProducer::from_registry();
+
+        state.envelope.retain_items(|item| {
+            if item.ty() != ItemType::ClientReport {
+                return true;
+            }
+
+            let report = match ClientReport::parse(&item.payload()) {
+                Ok(report) => report,
+                Err(error) => {
+                    relay_log::trace!("skipping invalid client report: {}", LogError(&error));
+                    return false;
+                }
+            };
+
+            let timestamp = match report.timestamp {
+                Some(timestamp) => match Utc.timestamp_opt(timestamp.as_secs() as i64, 0) {
+                    chrono::LocalResult::Single(timestamp) => timestamp,
+                    _ => {
+                        relay_log::trace!("skipping client report with invalid timestamp");
+                        return false;
+                    }
+                },
+                None => received,
+            };
+
+            if (received - timestamp) > max_age || (timestamp - received) > max_future {
+                relay_log::trace!("skipping client report with timestamp out of range");
+                return false;
+            }
+
+            for discarded in report.discarded_events {
+                if discarded.category == DataCategory::Unknown || discarded.quantity == 0 {
+                    continue;
+                }
+
+                if discarded.quantity > MAX_CLIENT_DISCARD_QUANTITY {
+                    relay_log::trace!("skipping client report entry with excessive quantity");
+                    continue;
+                }
+
+                outcome_producer.do_send(TrackOutcome {
+                    timestamp,
+                    scoping,
+                    outcome: Outcome::ClientDiscard(discarded.normalized_reason()),
+                    event_id: None,
+                    remote_addr,
+                    category: discarded.category,
+                    quantity: discarded.quantity as usize,
+                });
+            }
+
+            // Client reports are fully converted into outcomes and never forwarded to the store.
+            false
+        });
+    }
+
     /// Creates and initializes the processing state.
     ///
     /// This applies defaults to the envelope and initializes empty rate limits.
@@ -766,6 +947,10 @@ impl EnvelopeProcessor {
             SecurityReportType::ExpectCt => ExpectCt::apply_to_event(data, &mut event),
             SecurityReportType::ExpectStaple => ExpectStaple::apply_to_event(data, &mut event),
             SecurityReportType::Hpkp => Hpkp::apply_to_event(data, &mut event),
+            SecurityReportType::Nel => Nel::apply_to_event(data, &mut event),
+            SecurityReportType::Deprecation => Deprecation::apply_to_event(data, &mut event),
+            SecurityReportType::Intervention => Intervention::apply_to_event(data, &mut event),
+            SecurityReportType::Crash => Crash::apply_to_event(data, &mut event),
         };
 
         if let Err(json_error) = apply_result {
@@ -789,12 +974,17 @@ impl EnvelopeProcessor {
         }
 
         // Explicitly set the event type. This is required so that a `Security` item can be created
-        // instead of a regular `Event` item.
+        // instead of a regular `Event` item. Reports of the Reporting API other than CSP violations
+        // do not have an event type in Sentry, so they are ingested as default events.
         event.ty = Annotated::new(match report_type {
             SecurityReportType::Csp => EventType::Csp,
             SecurityReportType::ExpectCt => EventType::ExpectCt,
             SecurityReportType::ExpectStaple => EventType::ExpectStaple,
             SecurityReportType::Hpkp => EventType::Hpkp,
+            SecurityReportType::Nel
+            | SecurityReportType::Deprecation
+            | SecurityReportType::Intervention
+            | SecurityReportType::Crash => EventType::Default,
         });
 
         Ok((Annotated::new(event), len))
@@ -964,6 +1154,7 @@ impl EnvelopeProcessor {
             ItemType::Sessions => false,
             ItemType::Metrics => false,
             ItemType::MetricBuckets => false,
+            ItemType::ClientReport => false,
         }
     }
 
@@ -1221,14 +1412,67 @@ impl EnvelopeProcessor {
 
         let client_ip = state.envelope.meta().client_addr();
         let filter_settings = &state.project_state.config.filter_settings;
+        let mut dry_run_matches = Vec::new();
 
-        metric!(timer(RelayTimers::EventProcessingFiltering), {
-            relay_filter::should_filter(event, client_ip, filter_settings)
-                .map_err(ProcessingError::EventFiltered)
-        })
+        let result = metric!(timer(RelayTimers::EventProcessingFiltering), {
+            relay_filter::should_filter(event, client_ip, filter_settings, &mut dry_run_matches)
+        });
+
+        track_dry_run_matches(event, &dry_run_matches);
+        result.map_err(ProcessingError::EventFiltered)
+    }
+
+    /// Drops error events that exceed the configured rate for their fingerprint.
+    ///
+    /// In processing mode, counts are shared between Relays through Redis. Otherwise, this Relay
+    /// counts fingerprints in memory. If counting fails, the event is accepted.
+    fn filter_error_spikes(&self, state: &mut ProcessEnvelopeState) -> Result<(), ProcessingError> {
+        let config = &state.project_state.config.filter_settings.error_spikes;
+        let max_per_minute = match config.max_per_minute {
+            Some(max_per_minute) => max_per_minute,
+            None => return Ok(()),
+        };
+
+        let event = match state.event.value_mut() {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+
+        if !config.environments.applies_to(event.environment.as_str()) {
+            return Ok(());
+        }
+
+        let fingerprint = match relay_filter::error_spikes::fingerprint(event) {
+            Some(fingerprint) => fingerprint,
+            None => return Ok(()),
+        };
+
+        let timestamp =
+            UnixTimestamp::from_datetime(state.received_at).unwrap_or_else(UnixTimestamp::now);
+        let count =
+            match self
+                .error_spike_counter
+                .increment(state.project_id, &fingerprint, timestamp)
+            {
+                Ok(count) => count,
+                Err(error) => {
+                    relay_log::error!("failed to count error spikes: {}", LogError(&error));
+                    return Ok(());
+                }
+            };
+
+        if count <= max_per_minute {
+            return Ok(());
+        }
+
+        if config.dry_run {
+            track_dry_run_matches(event, &[FilterStatKey::ErrorSpike]);
+            return Ok(());
+        }
+
+        Err(ProcessingError::EventFiltered(FilterStatKey::ErrorSpike))
     }
 
-    #[cfg(feature = "processing")]
     fn enforce_quotas(&self, state: &mut ProcessEnvelopeState) -> Result<(), ProcessingError> {
         let rate_limiter = match self.rate_limiter.as_ref() {
             Some(rate_limiter) => rate_limiter,
@@ -1237,7 +1481,8 @@ impl EnvelopeProcessor {
 
         let project_state = &state.project_state;
         let quotas = project_state.config.quotas.as_slice();
-        if quotas.is_empty() {
+        let spike_protection = project_state.config.spike_protection.as_ref();
+        if quotas.is_empty() && spike_protection.is_none() {
             return Ok(());
         }
 
@@ -1247,7 +1492,20 @@ impl EnvelopeProcessor {
         // When invoking the rate limiter, capture if the event item has been rate limited to also
         // remove it from the processing state eventually.
         let mut envelope_limiter = EnvelopeLimiter::new(|item_scope, quantity| {
-            let limits = rate_limiter.is_rate_limited(quotas, item_scope, quantity)?;
+            // Check for spikes first, so that items rejected by spike protection do not count
+            // against the quotas of the organization. Conversely, items that pass spike protection
+            // remain in its baseline even if quotas reject them, see `RateLimiter::check_spike`.
+            let mut limits = match spike_protection {
+                Some(spike_protection) => {
+                    rate_limiter.check_spike(spike_protection, item_scope, quantity)?
+                }
+                None => RateLimits::new(),
+            };
+
+            if !limits.is_limited() {
+                limits = rate_limiter.is_rate_limited(quotas, item_scope, quantity)?;
+            }
+
             remove_event |= Some(item_scope.category) == event_category && limits.is_limited();
             Ok(limits)
         });
@@ -1258,6 +1516,13 @@ impl EnvelopeProcessor {
             envelope_limiter.assume_event(category);
         }
 
+        // By default, release and environment quotas use the trace context of the envelope. Since
+        // the event has been parsed at this stage, prefer the values from the event payload.
+        if let Some(event) = state.event.value() {
+            envelope_limiter.assume_release(event.release.as_str().map(str::to_owned));
+            envelope_limiter.assume_environment(event.environment.as_str().map(str::to_owned));
+        }
+
         // Fetch scoping again from the project state. This is a rather cheap operation at this
         // point and it is easier than passing scoping through all layers of `process_envelope`.
         let scoping = project_state.scope_request(state.envelope.meta());
@@ -1308,15 +1573,21 @@ impl EnvelopeProcessor {
         metric!(timer(RelayTimers::EventProcessingPii), {
             if let Some(ref config) = config.pii_config {
                 let compiled = config.compiled();
-                let mut processor = PiiProcessor::new(&compiled);
+                let mut processor = PiiProcessor::new(&compiled).with_stats();
                 process_value(event, &mut processor, ProcessingState::root())
                     .map_err(ProcessingError::ProcessingFailed)?;
+                if let Some(stats) = processor.stats() {
+                    track_pii_stats(stats);
+                }
             }
             if let Some(ref config) = *config.datascrubbing_settings.pii_config() {
                 let compiled = config.compiled();
-                let mut processor = PiiProcessor::new(&compiled);
+                let mut processor = PiiProcessor::new(&compiled).with_stats();
                 process_value(event, &mut processor, ProcessingState::root())
                     .map_err(ProcessingError::ProcessingFailed)?;
+                if let Some(stats) = processor.stats() {
+                    track_pii_stats(stats);
+                }
             }
         });
 
@@ -1329,18 +1600,41 @@ impl EnvelopeProcessor {
     /// attachment types. When special attachments are detected, these are scrubbed with custom
     /// logic; otherwise the entire attachment is treated as a single binary blob.
     fn scrub_attachments(&self, state: &mut ProcessEnvelopeState) {
-        let envelope = &mut state.envelope;
-        if let Some(ref config) = state.project_state.config.pii_config {
-            let minidump = envelope
-                .get_item_by_mut(|item| item.attachment_type() == Some(AttachmentType::Minidump));
+        let config = match state.project_state.config.pii_config {
+            Some(ref config) => config,
+            None => return,
+        };
 
-            if let Some(item) = minidump {
-                let filename = item.filename().unwrap_or_default();
-                let mut payload = item.payload().to_vec();
+        let compiled = config.compiled();
+        let processor = PiiAttachmentsProcessor::new(&compiled);
+        let max_size = self.config.max_attachment_scrub_size();
 
-                let compiled = config.compiled();
-                let processor = PiiAttachmentsProcessor::new(&compiled);
+        for item in state.envelope.items_mut() {
+            if item.ty() != ItemType::Attachment {
+                continue;
+            }
 
+            let attachment_type = item.attachment_type().unwrap_or_default();
+            match attachment_type {
+                AttachmentType::Attachment
+                | AttachmentType::Minidump
+                | AttachmentType::AppleCrashReport
+                | AttachmentType::UnrealLogs => (),
+                _ => continue,
+            }
+
+            if item.len() > max_size {
+                metric!(
+                    counter(RelayCounters::AttachmentScrubbingSkipped) += 1,
+                    reason = "too_large",
+                );
+                continue;
+            }
+
+            let filename = item.filename().unwrap_or_default();
+            let mut payload = item.payload().to_vec();
+
+            let modified = if attachment_type == AttachmentType::Minidump {
                 // Minidump scrubbing can fail if the minidump cannot be parsed. In this case, we
                 // must be conservative and treat it as a plain attachment. Under extreme
                 // conditions, this could destroy stack memory.
@@ -1351,6 +1645,7 @@ impl EnvelopeProcessor {
                             timer(RelayTimers::MinidumpScrubbing) = start.elapsed(),
                             status = if modified { "ok" } else { "n/a" },
                         );
+                        modified
                     }
                     Err(scrub_error) => {
                         metric!(
@@ -1359,15 +1654,48 @@ impl EnvelopeProcessor {
                         );
                         relay_log::warn!("failed to scrub minidump: {}", LogError(&scrub_error));
                         metric!(timer(RelayTimers::AttachmentScrubbing), {
-                            processor.scrub_attachment(filename, &mut payload);
+                            processor.scrub_attachment(filename, &mut payload)
                         })
                     }
                 }
+            } else if attachment_type == AttachmentType::AppleCrashReport {
+                metric!(timer(RelayTimers::AttachmentScrubbing), {
+                    processor.scrub_apple_crash_report(filename, &mut payload)
+                })
+            } else if attachment_type == AttachmentType::UnrealLogs {
+                metric!(timer(RelayTimers::AttachmentScrubbing), {
+                    processor.scrub_unreal_logs(filename, &mut payload)
+                })
+            } else if item.content_type() == Some(&ContentType::Json) {
+                // JSON attachments are scrubbed structurally, so that selectors can address
+                // fields within the document and redactions cannot break its syntax. If the
+                // attachment is not valid JSON, fall back to scrubbing it as plain text.
+                metric!(timer(RelayTimers::AttachmentScrubbing), {
+                    match processor.scrub_json_attachment(filename, &mut payload) {
+                        Ok(modified) => modified,
+                        Err(error) => {
+                            relay_log::debug!("failed to parse JSON attachment: {}", error);
+                            let encodings = ScrubEncodings::for_attachment(None, &payload);
+                            processor.scrub_encoded_attachment(filename, &mut payload, encodings)
+                        }
+                    }
+                })
+            } else {
+                let content_type = item.content_type().map(ContentType::as_str);
+                let encodings = ScrubEncodings::for_attachment(content_type, &payload);
+                metric!(timer(RelayTimers::AttachmentScrubbing), {
+                    processor.scrub_encoded_attachment(filename, &mut payload, encodings)
+                })
+            };
 
+            if modified {
                 let content_type = item
                     .content_type()
-                    .unwrap_or(&ContentType::Minidump)
-                    .clone();
+                    .cloned()
+                    .unwrap_or(match attachment_type {
+                        AttachmentType::Minidump => ContentType::Minidump,
+                        _ => ContentType::OctetStream,
+                    });
 
                 item.set_payload(content_type, payload);
             }
@@ -1431,6 +1759,7 @@ impl EnvelopeProcessor {
 
         self.process_sessions(&mut state);
         self.process_user_reports(&mut state);
+        self.process_client_reports(&mut state);
 
         if state.creates_event() {
             if_processing!({
@@ -1452,11 +1781,11 @@ impl EnvelopeProcessor {
                 self.extract_transaction_metrics(&mut state)?;
                 self.filter_event(&mut state)?;
             });
+
+            self.filter_error_spikes(&mut state)?;
         }
 
-        if_processing!({
-            self.enforce_quotas(&mut state)?;
-        });
+        self.enforce_quotas(&mut state)?;
 
         if state.has_event() {
             self.scrub_event(&mut state)?;
@@ -1551,6 +1880,9 @@ impl Handler<ProcessEnvelope> for EnvelopeProcessor {
 ///    dropped together on parsing failure.
 ///  - Other items will be ignored with an error message.
 ///
+/// Metric buckets are subject to quotas of the `metric_bucket` data category. They are checked
+/// against cached rate limits when they are added to the aggregator and again when it flushes.
+///
 /// Additionally, processing applies clock drift correction using the system clock of this Relay, if
 /// the Envelope specifies the [`sent_at`](Envelope::sent_at) header.
 struct ProcessMetrics {
@@ -1707,6 +2039,16 @@ impl EnvelopeManager {
 
         relay_log::trace!("sending envelope to sentry endpoint");
         let http_encoding = self.config.http_encoding();
+
+        // Remember the release and environment to scope release and environment rate limits.
+        let (release, environment) = match envelope.trace_context() {
+            Some(trace_context) => (
+                trace_context.release.clone(),
+                trace_context.environment.clone(),
+            ),
+            None => (None, None),
+        };
+
         let request = SendRequest::post(format!("/api/{}/envelope/", scoping.project_id)).build(
             move |mut builder: RequestBuilder| {
                 // Override the `sent_at` timestamp. Since the envelope went through basic
@@ -1745,7 +2087,11 @@ impl EnvelopeManager {
             .map_err(SendEnvelopeError::ScheduleFailed)
             .and_then(move |result| {
                 if let Err(UpstreamRequestError::RateLimited(upstream_limits)) = result {
-                    let limits = upstream_limits.scope(&scoping);
+                    let item_scoping = scoping
+                        .item(DataCategory::Default)
+                        .with_release(release.as_deref())
+                        .with_environment(environment.as_deref());
+                    let limits = upstream_limits.scope(item_scoping);
                     ProjectCache::from_registry()
                         .do_send(UpdateRateLimits::new(project_key, limits.clone()));
                     Err(SendEnvelopeError::RateLimited(limits))
@@ -1794,6 +2140,8 @@ impl Default for EnvelopeManager {
 ///   `EnvelopeManager`.
 /// - Sessions and Session batches are always queued separately. If they occur in the same envelope
 ///   as an event, they are split off.
+/// - Security reports each create a separate event. If an envelope contains multiple reports, all
+///   but one are queued in separate envelopes with a new event identifier.
 /// - Metrics are directly sent to the `EnvelopeProcessor`, bypassing the manager's queue and going
 ///   straight into metrics aggregation. See [`ProcessMetrics`] for a full description.
 ///
@@ -1856,6 +2204,23 @@ impl Handler<QueueEnvelope> for EnvelopeManager {
             });
         }
 
+        // Every security report creates a separate event. Queue additional reports, such as from
+        // batched requests of the Reporting API, in envelopes with their own event identifier.
+        let is_report = |item: &Item| item.ty() == ItemType::RawSecurity;
+        let report_count = envelope.items().filter(|item| is_report(item)).count();
+        for _ in 1..report_count {
+            if let Some(report_envelope) = envelope.split_item_by(is_report) {
+                relay_log::trace!("queueing separate envelope for security report");
+                self.active_envelopes += 1;
+                context.notify(HandleEnvelope {
+                    envelope: report_envelope,
+                    project_key,
+                    sampling_project_key,
+                    start_time,
+                });
+            }
+        }
+
         // Split the envelope into event-related items and other items. This allows to fast-track:
         //  1. Envelopes with only session items. They only require rate limiting.
         //  2. Event envelope processing can bail out if the event is filtered or rate limited,
@@ -2104,13 +2469,25 @@ impl Handler<HandleEnvelope> for EnvelopeManager {
                         outcome_producer.do_send(TrackOutcome {
                             timestamp,
                             scoping: *scoping.borrow(),
-                            outcome,
+                            outcome: outcome.clone(),
                             event_id,
                             remote_addr,
                             category: DataCategory::Attachment,
                             quantity: envelope_summary.attachment_quantity,
                         });
                     }
+
+                    if envelope_summary.attachment_item_quantity > 0 {
+                        outcome_producer.do_send(TrackOutcome {
+                            timestamp,
+                            scoping: *scoping.borrow(),
+                            outcome,
+                            event_id,
+                            remote_addr,
+                            category: DataCategory::AttachmentItem,
+                            quantity: envelope_summary.attachment_item_quantity,
+                        });
+                    }
                 }
             })
             .then(move |x, slf, _| {
@@ -2205,6 +2582,120 @@ impl Handler<GetCapturedEnvelope> for EnvelopeManager {
     }
 }
 
+/// Queries the consumption of quotas from the configured rate limiter.
+///
+/// This does not count against the quotas. Responds with one entry per quota, which is `None` if
+/// the quota is not tracked or there is no rate limiter. Responds with `Err` if the rate limiter
+/// could not be queried.
+pub struct GetQuotaUsage {
+    /// The quotas to query.
+    pub quotas: Vec<Quota>,
+    /// The scoping of the project and its key.
+    pub scoping: Scoping,
+}
+
+impl Message for GetQuotaUsage {
+    type Result = Result<Vec<Option<QuotaUsage>>, ()>;
+}
+
+impl Handler<GetQuotaUsage> for EnvelopeProcessor {
+    type Result = Result<Vec<Option<QuotaUsage>>, ()>;
+
+    fn handle(&mut self, message: GetQuotaUsage, _context: &mut Self::Context) -> Self::Result {
+        let GetQuotaUsage { quotas, scoping } = message;
+
+        let rate_limiter = match self.rate_limiter {
+            Some(ref rate_limiter) => rate_limiter,
+            None => return Ok(vec![None; quotas.len()]),
+        };
+
+        rate_limiter
+            .quota_usage(&quotas, &scoping)
+            .map_err(|error| relay_log::error!("failed to query quota usage: {}", LogError(&error)))
+    }
+}
+
+impl Handler<GetQuotaUsage> for EnvelopeManager {
+    type Result = ResponseFuture<Vec<Option<QuotaUsage>>, ()>;
+
+    fn handle(&mut self, message: GetQuotaUsage, _context: &mut Self::Context) -> Self::Result {
+        // Rate limiters may block on network requests, so query them on the processor.
+        let future = self
+            .processor
+            .send(message)
+            .map_err(|_| relay_log::error!("failed to query quota usage: processor mailbox full"))
+            .flatten();
+
+        Box::new(future)
+    }
+}
+
+/// Enforces project quotas on metric buckets with the configured rate limiter.
+///
+/// Unlike the cached rate limits checked by the project, this counts the buckets against the
+/// `metric_bucket` quotas. Responds with the rate limits that apply to the buckets, which are empty
+/// if the buckets are accepted or there is no rate limiter. Responds with `Err` if the rate limiter
+/// could not be queried.
+pub struct RateLimitMetricBuckets {
+    /// The quotas of the project.
+    pub quotas: Vec<Quota>,
+    /// The scoping of the project and its key.
+    pub scoping: Scoping,
+    /// The number of buckets to count against the quotas.
+    pub quantity: usize,
+}
+
+impl Message for RateLimitMetricBuckets {
+    type Result = Result<RateLimits, ()>;
+}
+
+impl Handler<RateLimitMetricBuckets> for EnvelopeProcessor {
+    type Result = Result<RateLimits, ()>;
+
+    fn handle(
+        &mut self,
+        message: RateLimitMetricBuckets,
+        _context: &mut Self::Context,
+    ) -> Self::Result {
+        let RateLimitMetricBuckets {
+            quotas,
+            scoping,
+            quantity,
+        } = message;
+
+        let rate_limiter = match self.rate_limiter {
+            Some(ref rate_limiter) if !quotas.is_empty() => rate_limiter,
+            _ => return Ok(RateLimits::new()),
+        };
+
+        let item_scoping = scoping.item(DataCategory::MetricBucket);
+        rate_limiter
+            .is_rate_limited(&quotas, item_scoping, quantity)
+            .map_err(|error| {
+                relay_log::error!("failed to rate limit metrics: {}", LogError(&error))
+            })
+    }
+}
+
+impl Handler<RateLimitMetricBuckets> for EnvelopeManager {
+    type Result = ResponseFuture<RateLimits, ()>;
+
+    fn handle(
+        &mut self,
+        message: RateLimitMetricBuckets,
+        _context: &mut Self::Context,
+    ) -> Self::Result {
+        // Rate limiters may block on network requests, so query them on the processor.
+        let future = self
+            .processor
+            .send(message)
+            .map_err(|_| relay_log::error!("failed to rate limit metrics: processor mailbox full"))
+            .flatten();
+
+        Box::new(future)
+    }
+}
+
 /// Checks if the Event includes unprintable fields.
 
 #[cfg(feature = "processing")]
@@ -2378,7 +2869,8 @@ mod tests {
 
     #[test]
     fn test_user_report_invalid() {
-        let processor = EnvelopeProcessor::new(Arc::new(Default::default()));
+        let processor =
+            EnvelopeProcessor::new(Arc::new(Default::default()), ErrorSpikeCounter::memory(1));
         let event_id = EventId::new();
 
         let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
@@ -117,6 +117,12 @@ pub enum Outcome {
     /// Reserved but unused in Sentry.
     #[allow(dead_code)]
     Abuse,
+
+    /// The event has been discarded by the client, as reported in a client report.
+    ///
+    /// The reason is one of the discard reasons known to Relay, such as `sample_rate`, or `unknown`.
+    /// See `DiscardedEvent::normalized_reason`.
+    ClientDiscard(&'static str),
 }
 
 impl Outcome {
@@ -127,6 +133,7 @@ impl Outcome {
             Outcome::RateLimited(_) => 2,
             Outcome::Invalid(_) => 3,
             Outcome::Abuse => 4,
+            Outcome::ClientDiscard(_) => 5,
         }
     }
 
@@ -141,6 +148,7 @@ impl Outcome {
                 .as_ref()
                 .map(|code| Cow::Owned(code.as_str().into())),
             Outcome::Abuse => None,
+            Outcome::ClientDiscard(reason) => Some(Cow::Borrowed(reason)),
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -17,10 +17,10 @@ use relay_filter::{matches_any_origin, FiltersConfig};
 use relay_general::pii::{DataScrubbingConfig, PiiConfig};
 use relay_general::store::BreakdownsConfig;
 use relay_metrics::{self, Aggregator, Bucket, Metric};
-use relay_quotas::{Quota, RateLimits, Scoping};
+use relay_quotas::{DataCategory, Quota, QuotaUsage, RateLimits, Scoping, SpikeProtection};
 use relay_sampling::SamplingConfig;
 
-use crate::actors::outcome::DiscardReason;
+use crate::actors::outcome::{DiscardReason, Outcome, OutcomeProducer, TrackOutcome};
 use crate::actors::project_cache::{
     CheckEnvelopeResponse, CheckedEnvelope, ProjectCache, ProjectError, ProjectStateResponse,
     UpdateProjectState,
@@ -78,6 +78,9 @@ pub struct ProjectConfig {
     pub event_retention: Option<u16>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub quotas: Vec<Quota>,
+    /// Configuration for adaptive rate limits during spikes in the project's volume.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spike_protection: Option<SpikeProtection>,
     /// Configuration for sampling traces, if not present there will be no sampling.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dynamic_sampling: Option<SamplingConfig>,
@@ -100,6 +103,7 @@ impl Default for ProjectConfig {
             datascrubbing_settings: DataScrubbingConfig::default(),
             event_retention: None,
             quotas: Vec::new(),
+            spike_protection: None,
             dynamic_sampling: None,
             breakdowns_v2: None,
             features: BTreeSet::new(),
@@ -464,6 +468,34 @@ enum AggregatorState {
     Unavailable,
 }
 
+/// Returns the number of buckets that the aggregator creates for the given metrics.
+///
+/// Metrics with the same name, type, unit, tags and bucket timestamp are merged into one bucket.
+fn count_metric_buckets(metrics: &[Metric], bucket_interval: u64) -> usize {
+    let keys: HashSet<_> = metrics
+        .iter()
+        .map(|metric| {
+            let timestamp = metric.timestamp.as_secs() / bucket_interval;
+            let ty = metric.value.ty();
+            (&metric.name, ty, metric.unit, &metric.tags, timestamp)
+        })
+        .collect();
+
+    keys.len()
+}
+
+/// Minimum interval between two refreshes of the cached quota usage of a project.
+const QUOTA_USAGE_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Consumption of project quotas cached for advisory quota headers.
+#[derive(Clone, Debug)]
+pub struct QuotaUsageSnapshot {
+    /// The quotas of the project state at the time of the snapshot.
+    pub quotas: Vec<Quota>,
+    /// The consumption of each quota, or `None` if it is not tracked.
+    pub usage: Vec<Option<QuotaUsage>>,
+}
+
 /// Structure representing organization and project configuration for a project key.
 ///
 /// This structure no longer uniquely identifies a project. Instead, it identifies a project key.
@@ -476,6 +508,8 @@ pub struct Project {
     state: Option<Arc<ProjectState>>,
     state_channel: Option<StateChannel>,
     rate_limits: RateLimits,
+    quota_usage: Option<QuotaUsageSnapshot>,
+    quota_usage_requested_at: Option<Instant>,
     last_no_cache: Instant,
 }
 
@@ -490,6 +524,8 @@ impl Project {
             state: None,
             state_channel: None,
             rate_limits: RateLimits::new(),
+            quota_usage: None,
+            quota_usage_requested_at: None,
             last_no_cache: Instant::now(),
         }
     }
@@ -498,6 +534,37 @@ impl Project {
         self.rate_limits.merge(rate_limits);
     }
 
+    /// Returns the active cached rate limits of this project.
+    pub fn rate_limits(&mut self) -> &RateLimits {
+        self.rate_limits.clean_expired();
+        &self.rate_limits
+    }
+
+    /// Returns the cached consumption of project quotas, if it has been fetched.
+    pub fn quota_usage(&self) -> Option<&QuotaUsageSnapshot> {
+        self.quota_usage.as_ref()
+    }
+
+    /// Returns `true` if the cached quota usage should be refreshed and marks it as requested.
+    ///
+    /// Refreshes are requested at most once per [`QUOTA_USAGE_REFRESH_INTERVAL`].
+    pub fn request_quota_usage(&mut self) -> bool {
+        let now = Instant::now();
+
+        match self.quota_usage_requested_at {
+            Some(requested_at) if now - requested_at < QUOTA_USAGE_REFRESH_INTERVAL => false,
+            _ => {
+                self.quota_usage_requested_at = Some(now);
+                true
+            }
+        }
+    }
+
+    /// Replaces the cached consumption of project quotas.
+    pub fn update_quota_usage(&mut self, snapshot: QuotaUsageSnapshot) {
+        self.quota_usage = Some(snapshot);
+    }
+
     /// Returns a reference to the project state if available.
     pub fn state(&self) -> Option<&ProjectState> {
         self.state.as_deref()
@@ -543,17 +610,62 @@ impl Project {
     }
 
     pub fn merge_buckets(&mut self, buckets: Vec<Bucket>) {
+        if !self.check_metric_buckets(buckets.len()) {
+            return;
+        }
+
         if let Some(aggregator) = self.get_or_create_aggregator() {
             aggregator.do_send(relay_metrics::MergeBuckets::new(buckets));
         }
     }
 
     pub fn insert_metrics(&mut self, metrics: Vec<Metric>) {
+        let bucket_interval = self.config.aggregator_config().bucket_interval;
+        if !self.check_metric_buckets(count_metric_buckets(&metrics, bucket_interval)) {
+            return;
+        }
+
         if let Some(aggregator) = self.get_or_create_aggregator() {
             aggregator.do_send(relay_metrics::InsertMetrics::new(metrics));
         }
     }
 
+    /// Checks cached rate limits and quotas for the given number of metric buckets.
+    ///
+    /// Returns `true` if the buckets may be aggregated or sent. Otherwise, the buckets are rate
+    /// limited, a [`TrackOutcome`] is emitted for the entire quantity, and the caller must drop
+    /// them. If the project state has not been loaded yet, buckets are accepted since they will be
+    /// checked again when the aggregator flushes them.
+    pub fn check_metric_buckets(&mut self, quantity: usize) -> bool {
+        let scoping = match self.scoping() {
+            Some(scoping) if quantity > 0 => scoping,
+            _ => return true,
+        };
+
+        self.rate_limits.clean_expired();
+
+        let quotas = self.state().map(|s| s.get_quotas()).unwrap_or(&[]);
+        let item_scoping = scoping.item(DataCategory::MetricBucket);
+        let rate_limits = self.rate_limits.check_with_quotas(quotas, item_scoping);
+
+        let longest = match rate_limits.longest() {
+            Some(limit) => limit,
+            None => return true,
+        };
+
+        OutcomeProducer::from_registry().do_send(TrackOutcome {
+            timestamp: Utc::now(),
+            scoping,
+            outcome: Outcome::RateLimited(longest.reason_code.clone()),
+            event_id: None,
+            remote_addr: None,
+            category: DataCategory::MetricBucket,
+            quantity,
+        });
+
+        false
+    }
+
     /// Updates the aggregator based on updates to the project state.
     ///
     /// Changes to the aggregator depend on the project state:
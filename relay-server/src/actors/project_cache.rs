@@ -4,23 +4,27 @@ use std::time::Instant;
 
 use actix::prelude::*;
 use actix_web::ResponseError;
+use chrono::Utc;
 use failure::Fail;
-use futures::{future, Future};
+use futures::future::{self, Either};
+use futures::Future;
 
 use relay_common::{metric, ProjectKey};
 use relay_config::{Config, RelayMode};
 use relay_metrics::{self, AggregateMetricsError, Bucket, FlushBuckets, Metric};
-use relay_quotas::{RateLimits, Scoping};
+use relay_quotas::{DataCategory, RateLimits, Scoping};
 use relay_redis::RedisPool;
 
-use crate::actors::envelopes::{EnvelopeManager, SendMetrics};
-use crate::actors::outcome::DiscardReason;
-use crate::actors::project::{Outdated, Project, ProjectState};
+use crate::actors::envelopes::{
+    EnvelopeManager, GetQuotaUsage, RateLimitMetricBuckets, SendMetrics,
+};
+use crate::actors::outcome::{DiscardReason, Outcome, OutcomeProducer, TrackOutcome};
+use crate::actors::project::{Outdated, Project, ProjectState, QuotaUsageSnapshot};
 use crate::actors::project_local::LocalProjectSource;
 use crate::actors::project_upstream::UpstreamProjectSource;
 use crate::envelope::Envelope;
 use crate::metrics::{RelayCounters, RelayHistograms, RelayTimers};
-use crate::utils::{ActorResponse, Response};
+use crate::utils::{self, ActorResponse, Response};
 
 #[cfg(feature = "processing")]
 use {crate::actors::project_redis::RedisProjectSource, relay_common::clone};
@@ -367,6 +371,117 @@ impl Handler<GetCachedProjectState> for ProjectCache {
     }
 }
 
+/// Returns the project state along with the project's scoping and cached rate limits.
+///
+/// Responds with `None` if the project is not in the cache, its state has not been loaded yet or
+/// does not contain a project identifier. This neither fetches the project state nor adds unknown
+/// project keys to the cache.
+#[derive(Debug)]
+pub struct GetProjectLimits {
+    project_key: ProjectKey,
+}
+
+impl GetProjectLimits {
+    pub fn new(project_key: ProjectKey) -> Self {
+        Self { project_key }
+    }
+}
+
+/// The response of [`GetProjectLimits`].
+#[derive(Debug)]
+pub struct ProjectLimits {
+    /// The project state containing quotas.
+    pub state: Arc<ProjectState>,
+    /// The scoping of the project and its key.
+    pub scoping: Scoping,
+    /// Active rate limits cached by this project.
+    pub rate_limits: RateLimits,
+}
+
+impl Message for GetProjectLimits {
+    type Result = Option<ProjectLimits>;
+}
+
+impl Handler<GetProjectLimits> for ProjectCache {
+    type Result = Option<ProjectLimits>;
+
+    fn handle(&mut self, message: GetProjectLimits, _context: &mut Context<Self>) -> Self::Result {
+        let project = self.projects.get_mut(&message.project_key)?;
+
+        Some(ProjectLimits {
+            state: project.state_clone()?,
+            scoping: project.scoping()?,
+            rate_limits: project.rate_limits().clone(),
+        })
+    }
+}
+
+/// Returns the advisory `X-Sentry-Quota-Remaining` header for a project.
+///
+/// The header is computed from cached data only: the active rate limits of the project and the
+/// last snapshot of its quota consumption. If the snapshot is missing or outdated, a refresh is
+/// scheduled in the background, which does not delay the response. Responds with `None` if the
+/// project state has not been loaded yet or does not contain a project identifier.
+#[derive(Debug)]
+pub struct GetQuotaRemaining {
+    project_key: ProjectKey,
+}
+
+impl GetQuotaRemaining {
+    pub fn new(project_key: ProjectKey) -> Self {
+        Self { project_key }
+    }
+}
+
+impl Message for GetQuotaRemaining {
+    type Result = Option<String>;
+}
+
+impl Handler<GetQuotaRemaining> for ProjectCache {
+    type Result = Option<String>;
+
+    fn handle(&mut self, message: GetQuotaRemaining, context: &mut Context<Self>) -> Self::Result {
+        let project_key = message.project_key;
+        let project = self.get_or_create_project(project_key);
+
+        let state = project.state_clone()?;
+        let scoping = project.scoping()?;
+        let rate_limits = project.rate_limits().clone();
+
+        let header = match project.quota_usage() {
+            Some(snapshot) => {
+                utils::format_quota_remaining(&snapshot.quotas, &snapshot.usage, &rate_limits)
+            }
+            None => utils::format_quota_remaining(&[], &[], &rate_limits),
+        };
+
+        if project.request_quota_usage() {
+            let quotas = state.get_quotas().to_vec();
+            let message = GetQuotaUsage {
+                quotas: quotas.clone(),
+                scoping,
+            };
+
+            EnvelopeManager::from_registry()
+                .send(message)
+                .map_err(drop)
+                .flatten()
+                .into_actor(self)
+                .then(move |result, slf, _context| {
+                    if let Ok(usage) = result {
+                        let snapshot = QuotaUsageSnapshot { quotas, usage };
+                        let project = slf.get_or_create_project(project_key);
+                        project.update_quota_usage(snapshot);
+                    }
+                    fut::ok(())
+                })
+                .spawn(context);
+        }
+
+        Some(header)
+    }
+}
+
 /// Checks the envelope against project configuration and rate limits.
 ///
 /// When `fetched`, then the project state is ensured to be up to date. When `cached`, an outdated
@@ -585,19 +700,63 @@ impl Handler<FlushBuckets> for ProjectCache {
             return Box::new(future::ok(()));
         }
 
+        let quotas = state.get_quotas().to_vec();
+
+        // Drop buckets that are rate limited. Outcomes are emitted by the project.
+        let buckets = message.into_buckets();
+        if !project.check_metric_buckets(buckets.len()) {
+            return Box::new(future::ok(()));
+        }
+
+        // Count the buckets against the project's quotas if this Relay enforces quotas. If the
+        // rate limiter fails, the buckets are forwarded and checked again by the upstream.
+        let quantity = buckets.len();
         let future = EnvelopeManager::from_registry()
-            .send(SendMetrics {
-                buckets: message.into_buckets(),
+            .send(RateLimitMetricBuckets {
+                quotas,
                 scoping,
-                project_key,
+                quantity,
             })
-            .then(move |send_result| match send_result {
-                Ok(Ok(())) => Ok(()),
-                Ok(Err(buckets)) => Err(buckets),
-                Err(_) => {
-                    relay_log::error!("dropped metric buckets: envelope manager mailbox full");
-                    Ok(())
+            .then(move |result| {
+                let rate_limits = match result {
+                    Ok(Ok(rate_limits)) => rate_limits,
+                    _ => RateLimits::new(),
+                };
+
+                if let Some(longest) = rate_limits.longest() {
+                    OutcomeProducer::from_registry().do_send(TrackOutcome {
+                        timestamp: Utc::now(),
+                        scoping,
+                        outcome: Outcome::RateLimited(longest.reason_code.clone()),
+                        event_id: None,
+                        remote_addr: None,
+                        category: DataCategory::MetricBucket,
+                        quantity,
+                    });
+
+                    ProjectCache::from_registry()
+                        .do_send(UpdateRateLimits::new(project_key, rate_limits));
+                    return Either::A(future::ok(()));
                 }
+
+                let future = EnvelopeManager::from_registry()
+                    .send(SendMetrics {
+                        buckets,
+                        scoping,
+                        project_key,
+                    })
+                    .then(|send_result| match send_result {
+                        Ok(Ok(())) => Ok(()),
+                        Ok(Err(buckets)) => Err(buckets),
+                        Err(_) => {
+                            relay_log::error!(
+                                "dropped metric buckets: envelope manager mailbox full"
+                            );
+                            Ok(())
+                        }
+                    });
+
+                Either::B(future)
             });
 
         Box::new(future)
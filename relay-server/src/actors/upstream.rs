@@ -38,7 +38,7 @@ use relay_common::{metric, tryf, RetryBackoff};
 use relay_config::{Config, RelayMode};
 use relay_log::LogError;
 use relay_quotas::{
-    DataCategories, QuotaScope, RateLimit, RateLimitScope, RateLimits, RetryAfter, Scoping,
+    DataCategories, ItemScoping, QuotaScope, RateLimit, RateLimitScope, RateLimits, RetryAfter,
 };
 
 use crate::http::{HttpError, Request, RequestBuilder, Response, StatusCode};
@@ -183,8 +183,11 @@ impl UpstreamRateLimits {
         self
     }
 
-    /// Creates a scoped rate limit instance based on the provided `Scoping`.
-    pub fn scope(self, scoping: &Scoping) -> RateLimits {
+    /// Creates a scoped rate limit instance based on the provided `ItemScoping`.
+    ///
+    /// The release and environment of the item scoping are used for release and environment rate
+    /// limits. The data category of the item scoping is ignored.
+    pub fn scope(self, scoping: ItemScoping<'_>) -> RateLimits {
         // Try to parse the `X-Sentry-Rate-Limits` header in the most lenient way possible. If
         // anything goes wrong, skip over the invalid parts.
         let mut rate_limits = utils::parse_rate_limits(scoping, &self.rate_limits);
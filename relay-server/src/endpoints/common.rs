@@ -8,10 +8,11 @@ use actix_web::http::{header, StatusCode};
 use actix_web::middleware::cors::{Cors, CorsBuilder};
 use actix_web::{HttpRequest, HttpResponse, ResponseError};
 use failure::Fail;
+use futures::future::{self, Either};
 use futures::prelude::*;
 use serde::Deserialize;
 
-use relay_common::{clone, metric, tryf, DataCategory, ProjectId};
+use relay_common::{clone, metric, tryf, DataCategory, ProjectId, ProjectKey};
 use relay_config::Config;
 use relay_general::protocol::{EventId, EventType};
 use relay_log::LogError;
@@ -20,7 +21,7 @@ use relay_sampling::RuleId;
 
 use crate::actors::envelopes::{EnvelopeManager, QueueEnvelope, QueueEnvelopeError};
 use crate::actors::outcome::{DiscardReason, Outcome, OutcomeProducer, TrackOutcome};
-use crate::actors::project_cache::{CheckEnvelope, ProjectCache, ProjectError};
+use crate::actors::project_cache::{CheckEnvelope, GetQuotaRemaining, ProjectCache, ProjectError};
 use crate::body::StorePayloadError;
 use crate::envelope::{AttachmentType, Envelope, EnvelopeError, ItemType, Items};
 use crate::extractors::RequestMeta;
@@ -353,6 +354,7 @@ fn check_envelope_size_limits(config: &Config, envelope: &Envelope) -> bool {
             ItemType::UserReport => (),
             ItemType::Metrics => (),
             ItemType::MetricBuckets => (),
+            ItemType::ClientReport => (),
         }
     }
 
@@ -361,6 +363,33 @@ fn check_envelope_size_limits(config: &Config, envelope: &Envelope) -> bool {
         && session_count <= config.max_session_count()
 }
 
+/// Adds the advisory `X-Sentry-Quota-Remaining` header to an accepted response.
+///
+/// The header is computed from data cached in the project cache and never waits for the rate
+/// limiter. If the project has not been loaded, the response is returned unchanged.
+fn add_quota_headers(
+    project_key: ProjectKey,
+    mut response: HttpResponse,
+) -> impl Future<Item = HttpResponse, Error = BadStoreRequest> {
+    ProjectCache::from_registry()
+        .send(GetQuotaRemaining::new(project_key))
+        .then(move |result| {
+            let value = result
+                .ok()
+                .flatten()
+                .filter(|string| !string.is_empty())
+                .and_then(|string| header::HeaderValue::from_str(&string).ok());
+
+            if let Some(value) = value {
+                response
+                    .headers_mut()
+                    .insert(utils::QUOTA_REMAINING_HEADER, value);
+            }
+
+            Ok(response)
+        })
+}
+
 /// Handles Sentry events.
 ///
 /// Sentry events may come either directly from a http request ( the store endpoint calls this
@@ -401,6 +430,7 @@ where
     let config = request.state().config();
     let processing_enabled = config.processing_enabled();
     let is_internal = config.processing_internal_projects().contains(&project_id);
+    let quota_headers = config.quota_headers();
 
     let scoping = Rc::new(RefCell::new(meta.get_partial_scoping()));
     let event_id = Rc::new(RefCell::new(None));
@@ -475,9 +505,14 @@ where
         })
         .and_then(move |(event_id, rate_limits)| {
             if rate_limits.is_limited() {
-                Err(BadStoreRequest::RateLimited(rate_limits))
+                return Either::A(future::err(BadStoreRequest::RateLimited(rate_limits)));
+            }
+
+            let response = create_response(event_id);
+            if quota_headers {
+                Either::B(add_quota_headers(project_key, response))
             } else {
-                Ok(create_response(event_id))
+                Either::A(future::ok(response))
             }
         })
         .or_else(move |error: BadStoreRequest| {
@@ -503,13 +538,25 @@ where
                     outcome_producer.do_send(TrackOutcome {
                         timestamp,
                         scoping: *scoping.borrow(),
-                        outcome,
+                        outcome: outcome.clone(),
                         event_id: *event_id.borrow(),
                         remote_addr,
                         category: DataCategory::Attachment,
                         quantity: envelope_summary.attachment_quantity,
                     });
                 }
+
+                if envelope_summary.attachment_item_quantity > 0 {
+                    outcome_producer.do_send(TrackOutcome {
+                        timestamp,
+                        scoping: *scoping.borrow(),
+                        outcome,
+                        event_id: *event_id.borrow(),
+                        remote_addr,
+                        category: DataCategory::AttachmentItem,
+                        quantity: envelope_summary.attachment_item_quantity,
+                    });
+                }
             }
 
             if !emit_rate_limit && matches!(error, BadStoreRequest::RateLimited(_)) {
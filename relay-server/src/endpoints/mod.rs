@@ -15,6 +15,7 @@ mod minidump;
 mod outcomes;
 mod project_configs;
 mod public_keys;
+mod quotas;
 mod security_report;
 mod statics;
 mod store;
@@ -25,6 +26,7 @@ pub fn configure_app(app: ServiceApp) -> ServiceApp {
         // Internal routes pointing to /api/relay
         .configure(healthcheck::configure_app)
         .configure(events::configure_app)
+        .configure(quotas::configure_app)
         .handler("/api/relay", statics::not_found)
         // Web API routes pointing to /api/0
         .configure(project_configs::configure_app)
@@ -0,0 +1,104 @@
+//! Returns the consumption of project quotas.
+
+use actix_web::actix::*;
+use actix_web::{http::Method, HttpRequest, HttpResponse, Path};
+use futures::future::{self, Either, Future};
+use serde::Serialize;
+
+use relay_common::ProjectKey;
+use relay_quotas::{DataCategories, Quota, QuotaUsage, RateLimit, ReasonCode};
+
+use crate::actors::envelopes::{EnvelopeManager, GetQuotaUsage};
+use crate::actors::project_cache::{GetProjectLimits, ProjectCache};
+use crate::service::{ServiceApp, ServiceState};
+
+/// A quota from the project config and its consumption in the current window.
+#[derive(Debug, Serialize)]
+struct QuotaUsageInfo {
+    #[serde(flatten)]
+    quota: Quota,
+    /// The consumption of the quota, or `None` if it is not tracked by this Relay.
+    usage: Option<QuotaUsage>,
+}
+
+/// An active rate limit cached by this Relay.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RateLimitInfo {
+    categories: DataCategories,
+    scope: &'static str,
+    reason_code: Option<ReasonCode>,
+    retry_after: u64,
+}
+
+impl From<&RateLimit> for RateLimitInfo {
+    fn from(rate_limit: &RateLimit) -> Self {
+        Self {
+            categories: rate_limit.categories.clone(),
+            scope: rate_limit.scope.name(),
+            reason_code: rate_limit.reason_code.clone(),
+            retry_after: rate_limit.retry_after.remaining_seconds(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotaUsageResponse {
+    quotas: Vec<QuotaUsageInfo>,
+    rate_limits: Vec<RateLimitInfo>,
+}
+
+/// Returns the quota consumption of a project.
+///
+/// Only projects that are already in the project cache are reported, and unknown project keys
+/// respond with `404`. The endpoint is disabled unless `limits.quota_endpoint` is set.
+fn get_quota_usage(
+    (request, project_key): (HttpRequest<ServiceState>, Path<ProjectKey>),
+) -> ResponseFuture<HttpResponse, MailboxError> {
+    if !request.state().config().quota_endpoint() {
+        return Box::new(future::ok(HttpResponse::NotFound().finish()));
+    }
+
+    let future = ProjectCache::from_registry()
+        .send(GetProjectLimits::new(project_key.into_inner()))
+        .and_then(|limits| {
+            let limits = match limits {
+                Some(limits) => limits,
+                None => return Either::A(future::ok(HttpResponse::NotFound().finish())),
+            };
+
+            let quotas = limits.state.get_quotas().to_vec();
+            let rate_limits = limits.rate_limits.iter().map(RateLimitInfo::from).collect();
+
+            let message = GetQuotaUsage {
+                quotas: quotas.clone(),
+                scoping: limits.scoping,
+            };
+
+            let future = EnvelopeManager::from_registry()
+                .send(message)
+                .map(move |result| match result {
+                    Ok(usage) => HttpResponse::Ok().json(QuotaUsageResponse {
+                        quotas: quotas
+                            .into_iter()
+                            .zip(usage)
+                            .map(|(quota, usage)| QuotaUsageInfo { quota, usage })
+                            .collect(),
+                        rate_limits,
+                    }),
+                    Err(()) => HttpResponse::ServiceUnavailable().finish(),
+                });
+
+            Either::B(future)
+        });
+
+    Box::new(future)
+}
+
+pub fn configure_app(app: ServiceApp) -> ServiceApp {
+    app.resource("/api/relay/quotas/{project_key}/", |r| {
+        r.name("internal-quotas");
+        r.method(Method::GET).with(get_quota_usage);
+    })
+}
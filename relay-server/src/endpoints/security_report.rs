@@ -2,6 +2,7 @@
 
 use actix_web::actix::ResponseFuture;
 use actix_web::{pred, HttpMessage, HttpRequest, HttpResponse, Query, Request};
+use bytes::Bytes;
 use futures::Future;
 use serde::Deserialize;
 
@@ -19,6 +20,29 @@ struct SecurityReportParams {
     sentry_environment: Option<String>,
 }
 
+/// Returns `true` if the payload is a batch of reports sent by the Reporting API.
+///
+/// Legacy security reports are always sent as individual JSON objects, whereas the Reporting API
+/// sends a JSON array of reports with the `application/reports+json` content type.
+fn is_report_batch(data: &[u8]) -> bool {
+    data.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[')
+}
+
+/// Splits a batch of reports into the payloads of individual reports.
+fn split_report_batch(data: &[u8]) -> Result<Vec<Bytes>, BadStoreRequest> {
+    let reports: Vec<serde_json::Value> =
+        serde_json::from_slice(data).map_err(BadStoreRequest::InvalidJson)?;
+
+    reports
+        .iter()
+        .map(|report| {
+            serde_json::to_vec(report)
+                .map(Bytes::from)
+                .map_err(BadStoreRequest::InvalidJson)
+        })
+        .collect()
+}
+
 fn extract_envelope(
     request: &HttpRequest<ServiceState>,
     meta: RequestMeta,
@@ -32,19 +56,28 @@ fn extract_envelope(
                 return Err(BadStoreRequest::EmptyBody);
             }
 
-            let mut report_item = Item::new(ItemType::RawSecurity);
-            report_item.set_payload(ContentType::Json, data);
+            let reports = if is_report_batch(&data) {
+                split_report_batch(&data)?
+            } else {
+                vec![data]
+            };
 
-            if let Some(sentry_release) = params.sentry_release {
-                report_item.set_header("sentry_release", sentry_release);
-            }
+            let mut envelope = Envelope::from_request(Some(EventId::new()), meta);
 
-            if let Some(sentry_environment) = params.sentry_environment {
-                report_item.set_header("sentry_environment", sentry_environment);
-            }
+            for report in reports {
+                let mut report_item = Item::new(ItemType::RawSecurity);
+                report_item.set_payload(ContentType::Json, report);
 
-            let mut envelope = Envelope::from_request(Some(EventId::new()), meta);
-            envelope.add_item(report_item);
+                if let Some(ref sentry_release) = params.sentry_release {
+                    report_item.set_header("sentry_release", sentry_release.clone());
+                }
+
+                if let Some(ref sentry_environment) = params.sentry_environment {
+                    report_item.set_header("sentry_environment", sentry_environment.clone());
+                }
+
+                envelope.add_item(report_item);
+            }
 
             Ok(envelope)
         });
@@ -93,6 +126,7 @@ impl pred::Predicate<ServiceState> for SecurityReportFilter {
                 | ("application", "expect-ct-report", None)
                 | ("application", "expect-ct-report", Some("json"))
                 | ("application", "expect-staple-report", None)
+                | ("application", "reports", Some("json"))
         )
     }
 }
@@ -100,6 +100,8 @@ pub enum ItemType {
     Metrics,
     /// Buckets of preaggregated metrics encoded as JSON.
     MetricBuckets,
+    /// Counts of items discarded by the client, encoded as JSON.
+    ClientReport,
 }
 
 impl ItemType {
@@ -130,6 +132,7 @@ impl fmt::Display for ItemType {
             Self::Sessions => write!(f, "aggregated sessions"),
             Self::Metrics => write!(f, "metrics"),
             Self::MetricBuckets => write!(f, "metric buckets"),
+            Self::ClientReport => write!(f, "client report"),
         }
     }
 }
@@ -548,13 +551,14 @@ impl Item {
             | ItemType::Session
             | ItemType::Sessions
             | ItemType::Metrics
-            | ItemType::MetricBuckets => false,
+            | ItemType::MetricBuckets
+            | ItemType::ClientReport => false,
         }
     }
 
     /// Determines whether the given item requires an event with identifier.
     ///
-    /// This is true for all items except session health events.
+    /// This is true for all items except session health events, metrics and client reports.
     pub fn requires_event(&self) -> bool {
         match self.ty() {
             ItemType::Event => true,
@@ -569,6 +573,7 @@ impl Item {
             ItemType::Sessions => false,
             ItemType::Metrics => false,
             ItemType::MetricBuckets => false,
+            ItemType::ClientReport => false,
         }
     }
 }
@@ -848,6 +853,26 @@ impl Envelope {
         })
     }
 
+    /// Splits off the first item matching the predicate into a new envelope.
+    ///
+    /// Returns `None` if no item matches. The returned envelope assumes the same headers, but
+    /// receives a new event identifier. This allows to process items that create separate events,
+    /// such as batched security reports.
+    pub fn split_item_by<F>(&mut self, f: F) -> Option<Self>
+    where
+        F: FnMut(&Item) -> bool,
+    {
+        let item = self.take_item_by(f)?;
+
+        let mut headers = self.headers.clone();
+        headers.event_id = Some(EventId::new());
+
+        let mut items = Items::new();
+        items.push(item);
+
+        Some(Envelope { headers, items })
+    }
+
     pub fn trace_context(&self) -> Option<&TraceContext> {
         match &self.headers.trace {
             Option::None => None,
@@ -1426,4 +1451,26 @@ mod tests {
             assert_eq!(item.ty(), ItemType::Attachment);
         }
     }
+
+    #[test]
+    fn test_split_item_by() {
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta());
+        envelope.add_item(Item::new(ItemType::RawSecurity));
+        envelope.add_item(Item::new(ItemType::RawSecurity));
+
+        let split_envelope = envelope
+            .split_item_by(|item| item.ty() == ItemType::RawSecurity)
+            .expect("split_item_by returns an Envelope");
+
+        assert_eq!(split_envelope.len(), 1);
+        assert_eq!(envelope.len(), 1);
+
+        // The split envelope receives a new event id.
+        assert!(split_envelope.event_id().is_some());
+        assert_ne!(split_envelope.event_id(), envelope.event_id());
+
+        // Does not split when no item matches.
+        let split_opt = envelope.split_item_by(|item| item.ty() == ItemType::Session);
+        assert!(split_opt.is_none());
+    }
 }
@@ -165,7 +165,6 @@ pub enum RelayTimers {
     /// Not all events reach this point. After an event is rate limited for the first time, the rate
     /// limit is cached. Events coming in after this will be discarded earlier in the request queue
     /// and do not reach the processing queue.
-    #[cfg(feature = "processing")]
     EventProcessingRateLimiting,
     /// Time in milliseconds spent in data scrubbing for the current event. Data scrubbing happens
     /// last before serializing the event back to JSON.
@@ -277,7 +276,6 @@ impl TimerMetric for RelayTimers {
             RelayTimers::EventProcessingProcess => "event_processing.process",
             #[cfg(feature = "processing")]
             RelayTimers::EventProcessingFiltering => "event_processing.filtering",
-            #[cfg(feature = "processing")]
             RelayTimers::EventProcessingRateLimiting => "event_processing.rate_limiting",
             RelayTimers::EventProcessingPii => "event_processing.pii",
             RelayTimers::EventProcessingSerialization => "event_processing.serialization",
@@ -303,6 +301,14 @@ pub enum RelayCounters {
     /// some SDKs may send corrupted values.
     #[cfg(feature = "processing")]
     EventCorrupted,
+    /// Number of events matched by an inbound filter configured in dry-run mode.
+    ///
+    /// Dry-run filters do not drop events. Instead, this metric counts how many events would have
+    /// been filtered, and the event receives a `dry_run_filter` tag listing the matching filters.
+    ///
+    /// This metric is tagged with:
+    ///  - `filter`: The identifier of the filter that matched, e.g. `legacy-browsers`.
+    EventFilteredDryRun,
     /// Number of envelopes accepted in the current time slot.
     ///
     /// This represents requests that have successfully passed rate limits and filters, and have
@@ -437,6 +443,24 @@ pub enum RelayCounters {
     /// An event has been preliminarily accepted in the store endpoint for one of the configured
     /// "internal" projects.
     InternalCapturedEventEndpoint,
+    /// Number of attachments that were not scrubbed for PII.
+    ///
+    /// This metric is tagged with:
+    ///  - `reason`: Why the attachment was skipped. Currently, this is only `too_large` for
+    ///    attachments exceeding the `limits.max_attachment_scrub_size` option.
+    AttachmentScrubbingSkipped,
+    /// Number of event fields modified by PII rules.
+    ///
+    /// This metric is tagged with:
+    ///  - `rule`: The ID of a builtin rule as reported in `_meta` remarks, for instance `@ip`.
+    ///    Custom rules of projects are reported as `custom`.
+    ///  - `remark_type`: The type of the remark: `removed`, `substituted`, `masked`,
+    ///    `pseudonymized` or `encrypted`.
+    PiiScrubbedFields,
+    /// Number of bytes of event data redacted by PII rules.
+    ///
+    /// This metric is tagged with the same tags as `pii.scrubbed.fields`.
+    PiiScrubbedBytes,
 }
 
 impl CounterMetric for RelayCounters {
@@ -444,6 +468,7 @@ impl CounterMetric for RelayCounters {
         match self {
             #[cfg(feature = "processing")]
             RelayCounters::EventCorrupted => "event.corrupted",
+            RelayCounters::EventFilteredDryRun => "event.filtered_dry_run",
             RelayCounters::EnvelopeAccepted => "event.accepted",
             RelayCounters::EnvelopeRejected => "event.rejected",
             #[cfg(feature = "processing")]
@@ -465,6 +490,9 @@ impl CounterMetric for RelayCounters {
             #[cfg(feature = "processing")]
             RelayCounters::InternalCapturedEventStoreActor => "internal.captured.event.store_actor",
             RelayCounters::InternalCapturedEventEndpoint => "internal.captured.event.endpoint",
+            RelayCounters::AttachmentScrubbingSkipped => "scrubbing.attachments.skipped",
+            RelayCounters::PiiScrubbedFields => "pii.scrubbed.fields",
+            RelayCounters::PiiScrubbedBytes => "pii.scrubbed.bytes",
         }
     }
 }
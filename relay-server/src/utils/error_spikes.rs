@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use failure::Fail;
+use lru::LruCache;
+use parking_lot::Mutex;
+use uuid::Uuid;
+
+use relay_common::{ProjectId, UnixTimestamp};
+
+use relay_redis::RedisError;
+#[cfg(feature = "processing")]
+use relay_redis::{redis, RedisPool};
+
+/// The window in which events with the same fingerprint are counted, in seconds.
+const WINDOW_SECS: u64 = 60;
+
+/// Additional time to keep counters in Redis, accounting for clock drift between Relays.
+#[cfg(feature = "processing")]
+const GRACE_SECS: u64 = 60;
+
+/// An error returned by [`ErrorSpikeCounter`].
+#[derive(Debug, Fail)]
+#[cfg_attr(not(feature = "processing"), allow(dead_code))]
+pub enum ErrorSpikeError {
+    /// Failed to communicate with Redis.
+    #[fail(display = "failed to communicate with redis")]
+    Redis(#[cause] RedisError),
+}
+
+/// A fixed-window counter for a single fingerprint.
+#[derive(Clone, Copy, Debug)]
+struct WindowCount {
+    slot: u64,
+    count: u64,
+}
+
+type CounterKey = (ProjectId, Uuid);
+
+#[derive(Clone)]
+enum CounterInner {
+    Memory(Arc<Mutex<LruCache<CounterKey, WindowCount>>>),
+    #[cfg(feature = "processing")]
+    Redis(RedisPool),
+}
+
+/// Counts events by their fingerprint for the error spikes filter.
+///
+/// Events are counted in fixed windows of one minute per project. In processing mode, counters are
+/// shared between Relays through Redis. Otherwise, they are kept in an in-memory LRU cache with a
+/// bounded number of fingerprints, so that rarely occurring fingerprints are evicted first.
+///
+/// This type is cheap to clone, all clones share the same counters.
+#[derive(Clone)]
+pub struct ErrorSpikeCounter {
+    inner: CounterInner,
+}
+
+impl ErrorSpikeCounter {
+    /// Creates a counter that tracks up to `capacity` fingerprints in memory.
+    pub fn memory(capacity: usize) -> Self {
+        let cache = LruCache::new(capacity);
+        Self {
+            inner: CounterInner::Memory(Arc::new(Mutex::new(cache))),
+        }
+    }
+
+    /// Creates a counter that stores counts in Redis.
+    #[cfg(feature = "processing")]
+    pub fn redis(pool: RedisPool) -> Self {
+        Self {
+            inner: CounterInner::Redis(pool),
+        }
+    }
+
+    /// Counts an event with the given fingerprint and returns the number of events with this
+    /// fingerprint in the current window, including this event.
+    pub fn increment(
+        &self,
+        project_id: ProjectId,
+        fingerprint: &str,
+        timestamp: UnixTimestamp,
+    ) -> Result<u64, ErrorSpikeError> {
+        // Fingerprints can be arbitrarily long. Use a stable hash to bound the key size, which
+        // also needs to be consistent across Relays sharing the same Redis.
+        let hash = Uuid::new_v5(&Uuid::NAMESPACE_OID, fingerprint.as_bytes());
+        let slot = timestamp.as_secs() / WINDOW_SECS;
+
+        match self.inner {
+            CounterInner::Memory(ref cache) => {
+                let mut cache = cache.lock();
+                let key = (project_id, hash);
+
+                if let Some(window) = cache.get_mut(&key) {
+                    if window.slot == slot {
+                        window.count += 1;
+                    } else {
+                        *window = WindowCount { slot, count: 1 };
+                    }
+
+                    return Ok(window.count);
+                }
+
+                cache.put(key, WindowCount { slot, count: 1 });
+                Ok(1)
+            }
+            #[cfg(feature = "processing")]
+            CounterInner::Redis(ref pool) => {
+                let key = format!("spike:{}:{}:{}", project_id, hash.to_simple(), slot);
+                let mut client = pool.client().map_err(ErrorSpikeError::Redis)?;
+
+                let (count,): (u64,) = redis::pipe()
+                    .atomic()
+                    .incr(&key, 1)
+                    .expire(&key, (WINDOW_SECS + GRACE_SECS) as usize)
+                    .ignore()
+                    .query(&mut client.connection())
+                    .map_err(RedisError::Redis)
+                    .map_err(ErrorSpikeError::Redis)?;
+
+                Ok(count)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_counter() {
+        let counter = ErrorSpikeCounter::memory(10);
+        let project_id = ProjectId::new(42);
+        let timestamp = UnixTimestamp::from_secs(120);
+
+        assert_eq!(counter.increment(project_id, "a", timestamp).unwrap(), 1);
+        assert_eq!(counter.increment(project_id, "a", timestamp).unwrap(), 2);
+        assert_eq!(counter.increment(project_id, "b", timestamp).unwrap(), 1);
+        assert_eq!(
+            counter
+                .increment(ProjectId::new(43), "a", timestamp)
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_memory_counter_next_window() {
+        let counter = ErrorSpikeCounter::memory(10);
+        let project_id = ProjectId::new(42);
+
+        let timestamp = UnixTimestamp::from_secs(120);
+        assert_eq!(counter.increment(project_id, "a", timestamp).unwrap(), 1);
+        assert_eq!(counter.increment(project_id, "a", timestamp).unwrap(), 2);
+
+        let timestamp = UnixTimestamp::from_secs(180);
+        assert_eq!(counter.increment(project_id, "a", timestamp).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_memory_counter_evicts() {
+        let counter = ErrorSpikeCounter::memory(1);
+        let project_id = ProjectId::new(42);
+        let timestamp = UnixTimestamp::from_secs(120);
+
+        assert_eq!(counter.increment(project_id, "a", timestamp).unwrap(), 1);
+        assert_eq!(counter.increment(project_id, "b", timestamp).unwrap(), 1);
+        assert_eq!(counter.increment(project_id, "a", timestamp).unwrap(), 1);
+    }
+}
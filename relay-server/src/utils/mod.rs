@@ -2,6 +2,7 @@ mod actix;
 mod api;
 mod dynamic_sampling;
 mod error_boundary;
+mod error_spikes;
 mod multipart;
 mod param_parser;
 mod rate_limits;
@@ -21,6 +22,7 @@ pub use self::actix::*;
 pub use self::api::*;
 pub use self::dynamic_sampling::*;
 pub use self::error_boundary::*;
+pub use self::error_spikes::*;
 pub use self::multipart::*;
 pub use self::param_parser::*;
 pub use self::rate_limits::*;
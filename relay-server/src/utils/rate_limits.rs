@@ -3,8 +3,8 @@ use std::fmt::{self, Write};
 use actix::SystemService;
 
 use relay_quotas::{
-    DataCategories, DataCategory, ItemScoping, QuotaScope, RateLimit, RateLimitScope, RateLimits,
-    ReasonCode, Scoping,
+    DataCategories, DataCategory, ItemScoping, Quota, QuotaScope, QuotaUsage, RateLimit,
+    RateLimitScope, RateLimits, ReasonCode, Scoping,
 };
 
 use crate::actors::outcome::{Outcome, OutcomeProducer, TrackOutcome};
@@ -13,7 +13,37 @@ use crate::envelope::{Envelope, Item, ItemType};
 /// Name of the rate limits header.
 pub const RATE_LIMITS_HEADER: &str = "X-Sentry-Rate-Limits";
 
+/// Name of the advisory quota remaining header.
+pub const QUOTA_REMAINING_HEADER: &str = "X-Sentry-Quota-Remaining";
+
+/// Writes a semicolon-separated list of categories to the header.
+fn write_categories(header: &mut String, categories: &DataCategories) {
+    for (index, category) in categories.iter().enumerate() {
+        if index > 0 {
+            header.push(';');
+        }
+        write!(header, "{}", category).ok();
+    }
+}
+
+/// Marks rate limits that apply only to the release or environment of the rejected request.
+///
+/// Parsers that do not support these scopes require a numeric delay and skip such entries, instead
+/// of applying them to the entire key.
+const SCOPED_LIMIT_MARKER: char = '~';
+
+/// Returns `true` if the rate limit applies to a release or environment.
+fn is_scoped_limit(scope: &RateLimitScope) -> bool {
+    matches!(
+        scope,
+        RateLimitScope::Release(_) | RateLimitScope::Environment(_)
+    )
+}
+
 /// Formats the `X-Sentry-Rate-Limits` header.
+///
+/// Release and environment rate limits are prefixed with [`SCOPED_LIMIT_MARKER`], for example
+/// `~60:error:release`, so that clients and Relays that do not know these scopes ignore them.
 pub fn format_rate_limits(rate_limits: &RateLimits) -> String {
     let mut header = String::new();
 
@@ -22,15 +52,12 @@ pub fn format_rate_limits(rate_limits: &RateLimits) -> String {
             header.push_str(", ");
         }
 
-        write!(header, "{}:", rate_limit.retry_after.remaining_seconds()).ok();
-
-        for (index, category) in rate_limit.categories.iter().enumerate() {
-            if index > 0 {
-                header.push(';');
-            }
-            write!(header, "{}", category).ok();
+        if is_scoped_limit(&rate_limit.scope) {
+            header.push(SCOPED_LIMIT_MARKER);
         }
 
+        write!(header, "{}:", rate_limit.retry_after.remaining_seconds()).ok();
+        write_categories(&mut header, &rate_limit.categories);
         write!(header, ":{}", rate_limit.scope.name()).ok();
 
         if let Some(ref reason_code) = rate_limit.reason_code {
@@ -41,16 +68,67 @@ pub fn format_rate_limits(rate_limits: &RateLimits) -> String {
     header
 }
 
+/// Formats the advisory `X-Sentry-Quota-Remaining` header.
+///
+/// The header contains a comma-separated list of `remaining:categories:scope` entries. Quotas with
+/// known consumption report their remaining quantity in the current window. Active rate limits
+/// report a remaining quantity of `0`, since all matching items are dropped until they expire.
+///
+/// Release and environment quotas and rate limits are omitted. Like in the `X-Sentry-Rate-Limits`
+/// header, clients that do not know these scopes would apply them to the entire key.
+pub fn format_quota_remaining(
+    quotas: &[Quota],
+    usage: &[Option<QuotaUsage>],
+    rate_limits: &RateLimits,
+) -> String {
+    let mut header = String::new();
+
+    let remaining = quotas.iter().zip(usage).filter_map(|(quota, usage)| {
+        if let QuotaScope::Release | QuotaScope::Environment = quota.scope {
+            return None;
+        }
+
+        let remaining = usage.as_ref()?.remaining?;
+        Some((remaining, &quota.categories, quota.scope.name()))
+    });
+
+    let limited = rate_limits
+        .iter()
+        .filter(|rate_limit| !is_scoped_limit(&rate_limit.scope))
+        .map(|rate_limit| (0, &rate_limit.categories, rate_limit.scope.name()));
+
+    for (remaining, categories, scope) in remaining.chain(limited) {
+        if !header.is_empty() {
+            header.push_str(", ");
+        }
+
+        write!(header, "{}:", remaining).ok();
+        write_categories(&mut header, categories);
+        write!(header, ":{}", scope).ok();
+    }
+
+    header
+}
+
 /// Parses the `X-Sentry-Rate-Limits` header.
-pub fn parse_rate_limits(scoping: &Scoping, string: &str) -> RateLimits {
+///
+/// Release and environment rate limits do not carry the name of the release or environment in the
+/// header. They are marked with [`SCOPED_LIMIT_MARKER`] and apply to the release and environment of
+/// the given item scoping. If the item scoping does not specify them, these rate limits are skipped.
+pub fn parse_rate_limits(scoping: ItemScoping<'_>, string: &str) -> RateLimits {
     let mut rate_limits = RateLimits::new();
 
     for limit in string.split(',') {
-        let limit = limit.trim();
+        let mut limit = limit.trim();
         if limit.is_empty() {
             continue;
         }
 
+        let scoped = limit.starts_with(SCOPED_LIMIT_MARKER);
+        if scoped {
+            limit = &limit[SCOPED_LIMIT_MARKER.len_utf8()..];
+        }
+
         let mut components = limit.split(':');
 
         let retry_after = match components.next().and_then(|s| s.parse().ok()) {
@@ -67,6 +145,9 @@ pub fn parse_rate_limits(scoping: &Scoping, string: &str) -> RateLimits {
 
         let quota_scope = QuotaScope::from_name(components.next().unwrap_or(""));
         let scope = RateLimitScope::for_quota(scoping, quota_scope);
+        if scoped != is_scoped_limit(&scope) {
+            continue;
+        }
 
         let reason_code = components.next().map(ReasonCode::new);
 
@@ -102,13 +183,14 @@ fn infer_event_category(item: &Item) -> Option<DataCategory> {
         ItemType::MetricBuckets => None,
         ItemType::FormData => None,
         ItemType::UserReport => None,
+        ItemType::ClientReport => None,
     }
 }
 
 /// A summary of `Envelope` contents.
 ///
-/// Summarizes the contained event, size of attachments, session updates, and whether there are
-/// plain attachments. This is used for efficient rate limiting or outcome handling.
+/// Summarizes the contained event, size and number of attachments, session updates, and whether
+/// there are plain attachments. This is used for efficient rate limiting or outcome handling.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, Default)]
 pub struct EnvelopeSummary {
@@ -118,6 +200,9 @@ pub struct EnvelopeSummary {
     /// The quantity of all attachments combined in bytes.
     pub attachment_quantity: usize,
 
+    /// The number of all attachments.
+    pub attachment_item_quantity: usize,
+
     /// The number of all session updates.
     pub session_quantity: usize,
 
@@ -150,7 +235,10 @@ impl EnvelopeSummary {
             }
 
             match item.ty() {
-                ItemType::Attachment => summary.attachment_quantity += item.len().max(1),
+                ItemType::Attachment => {
+                    summary.attachment_quantity += item.len().max(1);
+                    summary.attachment_item_quantity += 1;
+                }
                 ItemType::Session => summary.session_quantity += 1,
                 _ => (),
             }
@@ -222,6 +310,8 @@ pub struct Enforcement {
     event: CategoryLimit,
     /// The combined attachment item rate limit.
     attachments: CategoryLimit,
+    /// The rate limit on the number of attachment items.
+    attachment_items: CategoryLimit,
     /// The combined session item rate limit.
     sessions: CategoryLimit,
 }
@@ -232,7 +322,8 @@ impl Enforcement {
     /// Relay generally does not emit outcomes for sessions, so those are skipped.
     pub fn track_outcomes(self, envelope: &Envelope, scoping: &Scoping) {
         // Do not report outcomes for sessions.
-        for limit in std::array::IntoIter::new([self.event, self.attachments]) {
+        let limits = [self.event, self.attachments, self.attachment_items];
+        for limit in std::array::IntoIter::new(limits) {
             if limit.is_active() {
                 let timestamp = relay_common::instant_to_date_time(envelope.meta().start_time());
                 OutcomeProducer::from_registry().do_send(TrackOutcome {
@@ -254,6 +345,8 @@ impl Enforcement {
 /// The `check` function is called with the following rules:
 ///  - Once for a single event, if present in the envelope.
 ///  - Once for all comprised attachments, unless the event was rate limited.
+///  - Once for the number of comprised attachments, unless the event or attachments were rate
+///    limited.
 ///  - Once for all comprised sessions.
 ///
 /// Items violating the rate limit are removed from the envelope. This follows a set of rules:
@@ -263,6 +356,8 @@ impl Enforcement {
 pub struct EnvelopeLimiter<F> {
     check: F,
     event_category: Option<DataCategory>,
+    release: Option<String>,
+    environment: Option<String>,
 }
 
 impl<E, F> EnvelopeLimiter<F>
@@ -274,6 +369,8 @@ where
         Self {
             check,
             event_category: None,
+            release: None,
+            environment: None,
         }
     }
 
@@ -282,11 +379,24 @@ where
     /// This ensures that rate limits for the given data category are checked even if there is no
     /// matching item in the envelope. Other items are handled according to the rules as if the
     /// event item were present.
-    #[cfg(feature = "processing")]
     pub fn assume_event(&mut self, category: DataCategory) {
         self.event_category = Some(category);
     }
 
+    /// Assume the given release for release-scoped quotas.
+    ///
+    /// If not set, the release is taken from the trace context of the envelope.
+    pub fn assume_release(&mut self, release: Option<String>) {
+        self.release = release;
+    }
+
+    /// Assume the given environment for environment-scoped quotas.
+    ///
+    /// If not set, the environment is taken from the trace context of the envelope.
+    pub fn assume_environment(&mut self, environment: Option<String>) {
+        self.environment = environment;
+    }
+
     /// Process rate limits for the envelope, removing offending items and returning applied limits.
     ///
     /// Returns a tuple of `Enforcement` and `RateLimits`:
@@ -340,6 +450,15 @@ where
             summary.event_category = Some(event_category);
         }
 
+        if let Some(trace_context) = envelope.trace_context() {
+            if self.release.is_none() {
+                self.release = trace_context.release.clone();
+            }
+            if self.environment.is_none() {
+                self.environment = trace_context.environment.clone();
+            }
+        }
+
         let (enforcement, rate_limits) = self.execute(&summary, scoping)?;
         envelope.retain_items(|item| self.retain_item(item, &enforcement));
         Ok((enforcement, rate_limits))
@@ -353,8 +472,17 @@ where
         let mut rate_limits = RateLimits::new();
         let mut enforcement = Enforcement::default();
 
+        let release = self.release.as_deref();
+        let environment = self.environment.as_deref();
+        let item_scoping = |category: DataCategory| {
+            scoping
+                .item(category)
+                .with_release(release)
+                .with_environment(environment)
+        };
+
         if let Some(category) = summary.event_category {
-            let event_limits = (&mut self.check)(scoping.item(category), 1)?;
+            let event_limits = (&mut self.check)(item_scoping(category), 1)?;
             let longest = event_limits.longest();
             enforcement.event = CategoryLimit::new(category, 1, longest);
 
@@ -364,17 +492,41 @@ where
                 summary.attachment_quantity,
                 longest,
             );
+            enforcement.attachment_items = CategoryLimit::new(
+                DataCategory::AttachmentItem,
+                summary.attachment_item_quantity,
+                longest,
+            );
 
             rate_limits.merge(event_limits);
         }
 
         if !enforcement.event.is_active() && summary.attachment_quantity > 0 {
-            let item_scoping = scoping.item(DataCategory::Attachment);
-            let attachment_limits = (&mut self.check)(item_scoping, summary.attachment_quantity)?;
+            let mut attachment_limits = (&mut self.check)(
+                item_scoping(DataCategory::AttachmentItem),
+                summary.attachment_item_quantity,
+            )?;
+
+            // Only consume the size quota if the number of attachments is within quota, since the
+            // size is the more valuable quota. If either is limited, all attachments are removed and
+            // both quantities are reported with the same reason.
+            if !attachment_limits.is_limited() {
+                attachment_limits = (&mut self.check)(
+                    item_scoping(DataCategory::Attachment),
+                    summary.attachment_quantity,
+                )?;
+            }
+
+            let longest = attachment_limits.longest();
             enforcement.attachments = CategoryLimit::new(
                 DataCategory::Attachment,
                 summary.attachment_quantity,
-                attachment_limits.longest(),
+                longest,
+            );
+            enforcement.attachment_items = CategoryLimit::new(
+                DataCategory::AttachmentItem,
+                summary.attachment_item_quantity,
+                longest,
             );
 
             // Only record rate limits for plain attachments. For all other attachments, it's
@@ -386,8 +538,10 @@ where
         }
 
         if summary.session_quantity > 0 {
-            let item_scoping = scoping.item(DataCategory::Session);
-            let session_limits = (&mut self.check)(item_scoping, summary.session_quantity)?;
+            let session_limits = (&mut self.check)(
+                item_scoping(DataCategory::Session),
+                summary.session_quantity,
+            )?;
             enforcement.sessions = CategoryLimit::new(
                 DataCategory::Session,
                 summary.session_quantity,
@@ -428,6 +582,8 @@ impl<F> fmt::Debug for EnvelopeLimiter<F> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("EnvelopeLimiter")
             .field("event_category", &self.event_category)
+            .field("release", &self.release)
+            .field("environment", &self.environment)
             .finish()
     }
 }
@@ -441,7 +597,7 @@ mod tests {
     use smallvec::smallvec;
 
     use relay_common::{ProjectId, ProjectKey};
-    use relay_quotas::RetryAfter;
+    use relay_quotas::{MemoryRateLimiter, RateLimiter, RetryAfter};
 
     use crate::envelope::{AttachmentType, ContentType};
 
@@ -470,6 +626,103 @@ mod tests {
         assert_eq!(formatted, expected);
     }
 
+    #[test]
+    fn test_format_rate_limits_release_environment() {
+        let mut rate_limits = RateLimits::new();
+
+        rate_limits.add(RateLimit {
+            categories: DataCategories::new(),
+            scope: RateLimitScope::Release("1.0.0".to_owned()),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(42),
+        });
+
+        rate_limits.add(RateLimit {
+            categories: smallvec![DataCategory::Error],
+            scope: RateLimitScope::Project(ProjectId::new(21)),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(4711),
+        });
+
+        let formatted = format_rate_limits(&rate_limits);
+        assert_eq!(formatted, "~42::release, 4711:error:project");
+
+        // Parsers without support for these scopes require a numeric delay and skip the entry.
+        assert!("~42".parse::<RetryAfter>().is_err());
+    }
+
+    #[test]
+    fn test_format_quota_remaining() {
+        let quotas = vec![
+            Quota {
+                id: Some("o".to_owned()),
+                categories: DataCategories::new(),
+                scope: QuotaScope::Organization,
+                scope_id: None,
+                limit: Some(100),
+                window: Some(60),
+                burst: None,
+                reason_code: None,
+            },
+            Quota {
+                id: Some("p".to_owned()),
+                categories: smallvec![DataCategory::Error, DataCategory::Default],
+                scope: QuotaScope::Project,
+                scope_id: None,
+                limit: Some(10),
+                window: Some(60),
+                burst: None,
+                reason_code: None,
+            },
+            Quota {
+                id: Some("untracked".to_owned()),
+                categories: DataCategories::new(),
+                scope: QuotaScope::Key,
+                scope_id: None,
+                limit: Some(10),
+                window: Some(60),
+                burst: None,
+                reason_code: None,
+            },
+        ];
+
+        let usage = vec![
+            Some(QuotaUsage {
+                window_start: 0,
+                window_end: 60,
+                consumed: 58,
+                remaining: Some(42),
+            }),
+            Some(QuotaUsage {
+                window_start: 0,
+                window_end: 60,
+                consumed: 3,
+                remaining: Some(7),
+            }),
+            None,
+        ];
+
+        let mut rate_limits = RateLimits::new();
+        rate_limits.add(RateLimit {
+            categories: smallvec![DataCategory::Transaction],
+            scope: RateLimitScope::Project(ProjectId::new(21)),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(4711),
+        });
+
+        // Release rate limits are not reported.
+        rate_limits.add(RateLimit {
+            categories: smallvec![DataCategory::Error],
+            scope: RateLimitScope::Release("1.0.0".to_owned()),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(42),
+        });
+
+        let formatted = format_quota_remaining(&quotas, &usage, &rate_limits);
+        let expected = "42::organization, 7:error;default:project, 0:transaction:project";
+        assert_eq!(formatted, expected);
+    }
+
     #[test]
     fn test_parse_invalid_rate_limits() {
         let scoping = Scoping {
@@ -479,9 +732,9 @@ mod tests {
             key_id: Some(17),
         };
 
-        assert!(parse_rate_limits(&scoping, "").is_ok());
-        assert!(parse_rate_limits(&scoping, "invalid").is_ok());
-        assert!(parse_rate_limits(&scoping, ",,,").is_ok());
+        assert!(parse_rate_limits(scoping.item(DataCategory::Default), "").is_ok());
+        assert!(parse_rate_limits(scoping.item(DataCategory::Default), "invalid").is_ok());
+        assert!(parse_rate_limits(scoping.item(DataCategory::Default), ",,,").is_ok());
     }
 
     #[test]
@@ -497,7 +750,9 @@ mod tests {
         let formatted =
             "42::organization:my_limit, invalid, 4711:foobar;transaction;security:project";
         let rate_limits: Vec<RateLimit> =
-            parse_rate_limits(&scoping, formatted).into_iter().collect();
+            parse_rate_limits(scoping.item(DataCategory::Default), formatted)
+                .into_iter()
+                .collect();
 
         assert_eq!(
             rate_limits,
@@ -537,7 +792,9 @@ mod tests {
         // contains "foobar", an unknown scope that should be mapped to Unknown
         let formatted = "42:foo;bar:organization";
         let rate_limits: Vec<RateLimit> =
-            parse_rate_limits(&scoping, formatted).into_iter().collect();
+            parse_rate_limits(scoping.item(DataCategory::Default), formatted)
+                .into_iter()
+                .collect();
 
         assert_eq!(
             rate_limits,
@@ -550,6 +807,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_rate_limits_release_environment() {
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(21),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(17),
+        };
+
+        let item_scoping = scoping
+            .item(DataCategory::Default)
+            .with_release(Some("1.0.0"))
+            .with_environment(Some("production"));
+
+        let formatted = "~42:error:release, ~4711:transaction:environment";
+        let rate_limits = parse_rate_limits(item_scoping, formatted);
+        assert_eq!(format_rate_limits(&rate_limits), formatted);
+
+        let rate_limits: Vec<RateLimit> = rate_limits.into_iter().collect();
+
+        assert_eq!(
+            rate_limits,
+            vec![
+                RateLimit {
+                    categories: smallvec![DataCategory::Error],
+                    scope: RateLimitScope::Release("1.0.0".to_owned()),
+                    reason_code: None,
+                    retry_after: rate_limits[0].retry_after,
+                },
+                RateLimit {
+                    categories: smallvec![DataCategory::Transaction],
+                    scope: RateLimitScope::Environment("production".to_owned()),
+                    reason_code: None,
+                    retry_after: rate_limits[1].retry_after,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rate_limits_release_without_scoping() {
+        let scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(21),
+            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+            key_id: Some(17),
+        };
+
+        // Without a release, the rate limit must not fall back to the key.
+        let rate_limits = parse_rate_limits(scoping.item(DataCategory::Error), "~42:error:release");
+        assert!(!rate_limits.is_limited());
+    }
+
     macro_rules! envelope {
         ($( $item_type:ident $( :: $attachment_type:ident )? ),*) => {{
             let bytes = "{\"dsn\":\"https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42\"}";
@@ -695,10 +1005,87 @@ mod tests {
         assert!(limits.is_limited());
         assert_eq!(envelope.len(), 1);
         mock.assert_call(DataCategory::Error, Some(1));
+        mock.assert_call(DataCategory::AttachmentItem, Some(2));
         mock.assert_call(DataCategory::Attachment, Some(20));
         mock.assert_call(DataCategory::Session, None);
     }
 
+    #[test]
+    fn test_enforce_limit_attachment_items() {
+        let mut envelope = envelope![Attachment, Attachment];
+
+        let mut mock = MockLimiter::default().deny(DataCategory::AttachmentItem);
+        let (_, limits) = EnvelopeLimiter::new(|s, q| mock.check(s, q))
+            .enforce(&mut envelope, &scoping())
+            .unwrap();
+
+        assert!(limits.is_limited());
+        assert!(envelope.is_empty());
+        mock.assert_call(DataCategory::AttachmentItem, Some(2));
+        // Attachment items are limited, so the size of attachments is not counted
+        mock.assert_call(DataCategory::Attachment, None);
+        mock.assert_call(DataCategory::Session, None);
+    }
+
+    #[test]
+    fn test_enforce_attachment_items_keep_size_quota() {
+        let quota = |id: &str, category, limit| Quota {
+            id: Some(id.to_owned()),
+            categories: smallvec![category],
+            scope: QuotaScope::Organization,
+            scope_id: None,
+            limit: Some(limit),
+            window: Some(60),
+            burst: None,
+            reason_code: None,
+        };
+
+        let quotas = [
+            quota("items", DataCategory::AttachmentItem, 1),
+            quota("size", DataCategory::Attachment, 100),
+        ];
+        let limiter = MemoryRateLimiter::new();
+        let scoping = scoping();
+
+        // Two attachments exceed the item quota and must not count against the size quota.
+        let mut envelope = envelope![Attachment, Attachment];
+        let (_, limits) = EnvelopeLimiter::new(|s, q| limiter.is_rate_limited(&quotas, s, q))
+            .enforce(&mut envelope, &scoping)
+            .unwrap();
+        assert!(limits.is_limited());
+        assert!(envelope.is_empty());
+
+        let usage = limiter.quota_usage(&quotas, &scoping).unwrap();
+        assert_eq!(usage[1].as_ref().map(|usage| usage.consumed), Some(0));
+
+        // A single attachment is within both quotas.
+        let mut envelope = envelope![Attachment];
+        let (_, limits) = EnvelopeLimiter::new(|s, q| limiter.is_rate_limited(&quotas, s, q))
+            .enforce(&mut envelope, &scoping)
+            .unwrap();
+        assert!(!limits.is_limited());
+        assert_eq!(envelope.len(), 1);
+
+        let usage = limiter.quota_usage(&quotas, &scoping).unwrap();
+        assert_eq!(usage[0].as_ref().map(|usage| usage.consumed), Some(1));
+        assert_eq!(usage[1].as_ref().map(|usage| usage.consumed), Some(10));
+    }
+
+    #[test]
+    fn test_enforce_pass_attachment_items() {
+        let mut envelope = envelope![Attachment, Attachment];
+
+        let mut mock = MockLimiter::default();
+        let (_, limits) = EnvelopeLimiter::new(|s, q| mock.check(s, q))
+            .enforce(&mut envelope, &scoping())
+            .unwrap();
+
+        assert!(!limits.is_limited());
+        assert_eq!(envelope.len(), 2);
+        mock.assert_call(DataCategory::AttachmentItem, Some(2));
+        mock.assert_call(DataCategory::Attachment, Some(20));
+    }
+
     #[test]
     fn test_enforce_pass_minidump() {
         let mut envelope = envelope![Attachment::Minidump];
@@ -772,7 +1159,6 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "processing")]
     fn test_enforce_limit_assumed_event() {
         let mut envelope = envelope![];
 
@@ -789,7 +1175,6 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "processing")]
     fn test_enforce_limit_assumed_attachments() {
         let mut envelope = envelope![Attachment, Attachment];
 
@@ -804,4 +1189,48 @@ mod tests {
         mock.assert_call(DataCategory::Attachment, None);
         mock.assert_call(DataCategory::Session, None);
     }
+
+    #[test]
+    fn test_enforce_release_from_trace_context() {
+        let bytes = r#"{"dsn":"https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42","trace":{"trace_id":"89143b0763095bd9c9955e8175d1fb23","public_key":"e12d836b15bb49d7bbf99e64295d995b","release":"1.0.0","environment":"production"}}
+{"type":"session"}
+{}
+"#;
+        let mut envelope = Envelope::parse_bytes(bytes.into()).unwrap();
+
+        let mut scopes = Vec::new();
+        EnvelopeLimiter::new(|s: ItemScoping<'_>, _| {
+            scopes.push((
+                s.release.map(str::to_owned),
+                s.environment.map(str::to_owned),
+            ));
+            Ok::<_, ()>(RateLimits::new())
+        })
+        .enforce(&mut envelope, &scoping())
+        .unwrap();
+
+        assert_eq!(
+            scopes,
+            vec![(Some("1.0.0".to_owned()), Some("production".to_owned()))]
+        );
+    }
+
+    #[test]
+    fn test_enforce_assumed_release() {
+        let mut envelope = envelope![];
+
+        let mut scopes = Vec::new();
+        let mut limiter = EnvelopeLimiter::new(|s: ItemScoping<'_>, _| {
+            scopes.push((
+                s.release.map(str::to_owned),
+                s.environment.map(str::to_owned),
+            ));
+            Ok::<_, ()>(RateLimits::new())
+        });
+        limiter.assume_event(DataCategory::Error);
+        limiter.assume_release(Some("2.0.0".to_owned()));
+        limiter.enforce(&mut envelope, &scoping()).unwrap();
+
+        assert_eq!(scopes, vec![(Some("2.0.0".to_owned()), None)]);
+    }
 }
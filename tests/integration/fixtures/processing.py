@@ -203,6 +203,10 @@ def category_value(category):
         return 4
     if category == "session":
         return 5
+    if category == "metric_bucket":
+        return 6
+    if category == "attachment_item":
+        return 7
     assert False, "invalid category"
 
 
@@ -122,7 +122,7 @@ def test_empty_attachments_with_processing(
     }
 
 
-@pytest.mark.parametrize("rate_limits", [[], ["attachment"]])
+@pytest.mark.parametrize("rate_limits", [[], ["attachment"], ["attachment_item"]])
 def test_attachments_ratelimit(
     mini_sentry, relay_with_processing, outcomes_consumer, rate_limits
 ):
@@ -196,3 +196,40 @@ def test_attachments_quotas(
         relay.send_attachments(42, event_id, attachments)
     assert excinfo.value.response.status_code == 429
     # outcomes_consumer.assert_rate_limited("static_disabled_quota")
+
+
+def test_attachment_items_quotas(
+    mini_sentry, relay_with_processing, attachments_consumer, outcomes_consumer,
+):
+    event_id = "515539018c9b4260a6f999572f1661ee"
+    attachment_body = b"b"
+
+    relay = relay_with_processing()
+    project_id = 42
+    project_config = mini_sentry.add_full_project_config(project_id)
+    project_config["config"]["quotas"] = [
+        {
+            "id": "test_rate_limiting_{}".format(uuid.uuid4().hex),
+            "categories": ["attachment_item"],
+            "window": 3600,
+            "limit": 2,
+            "reasonCode": "attachment_items_exceeded",
+        }
+    ]
+
+    attachments_consumer = attachments_consumer()
+    outcomes_consumer = outcomes_consumer()
+
+    for i in range(2):
+        relay.send_attachments(
+            project_id, event_id, [("att_1", "%s.txt" % i, attachment_body)]
+        )
+        attachment = attachments_consumer.get_individual_attachment()
+        assert attachment["attachment"]["name"] == "%s.txt" % i
+
+    # The third attachment is small, but exceeds the number of attachments
+    attachments = [("att_1", "foo.txt", attachment_body)]
+    relay.send_attachments(project_id, event_id, attachments)
+    outcomes_consumer.assert_rate_limited(
+        "attachment_items_exceeded", categories=["attachment", "attachment_item"]
+    )
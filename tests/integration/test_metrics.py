@@ -1,5 +1,6 @@
 from datetime import datetime, timedelta, timezone
 import json
+import uuid
 
 from .test_envelope import generate_transaction_item
 
@@ -92,6 +93,73 @@ def test_metrics_with_processing(mini_sentry, relay_with_processing, metrics_con
     metrics_consumer.assert_empty()
 
 
+def test_metrics_rate_limited(
+    mini_sentry, relay_with_processing, metrics_consumer, outcomes_consumer
+):
+    relay = relay_with_processing(options=TEST_CONFIG)
+    metrics_consumer = metrics_consumer()
+    outcomes_consumer = outcomes_consumer()
+
+    project_id = 42
+    project_config = mini_sentry.add_full_project_config(project_id)
+    project_config["config"]["quotas"] = [
+        {
+            "id": "drop-metrics",
+            "categories": ["metric_bucket"],
+            "limit": 0,
+            "reasonCode": "metrics_disabled",
+        }
+    ]
+
+    timestamp = int(datetime.now(tz=timezone.utc).timestamp())
+    relay.send_metrics(project_id, f"foo:42|c\nbar:17|c", timestamp)
+
+    # The project state is fetched while the buckets are aggregated, so they are dropped on flush.
+    outcome = outcomes_consumer.get_outcome()
+    assert outcome["outcome"] == 2
+    assert outcome["reason"] == "metrics_disabled"
+    assert outcome["category"] == 6
+    assert outcome["quantity"] == 2
+    metrics_consumer.assert_empty()
+
+
+def test_metrics_quota_consumed_on_flush(
+    mini_sentry, relay_with_processing, metrics_consumer, outcomes_consumer
+):
+    relay = relay_with_processing(options=TEST_CONFIG)
+    metrics_consumer = metrics_consumer()
+    outcomes_consumer = outcomes_consumer()
+
+    project_id = 42
+    project_config = mini_sentry.add_full_project_config(project_id)
+    project_config["config"]["quotas"] = [
+        {
+            "id": "test_metrics_quota_consumed_on_flush_{}".format(uuid.uuid4().hex),
+            "categories": ["metric_bucket"],
+            "limit": 2,
+            "window": 3600,
+            "reasonCode": "metrics_exceeded",
+        }
+    ]
+
+    # Both values of `foo` are merged into the same bucket, so this consumes two buckets.
+    timestamp = int(datetime.now(tz=timezone.utc).timestamp())
+    relay.send_metrics(project_id, f"foo:42|c\nfoo:1|c\nbar:17|c", timestamp)
+
+    metrics = {metrics_consumer.get_metric()["name"] for _ in range(2)}
+    assert metrics == {"foo", "bar"}
+
+    # The quota is exhausted when the next bucket is flushed.
+    relay.send_metrics(project_id, f"baz:1|c", timestamp)
+
+    outcome = outcomes_consumer.get_outcome()
+    assert outcome["outcome"] == 2
+    assert outcome["reason"] == "metrics_exceeded"
+    assert outcome["category"] == 6
+    assert outcome["quantity"] == 1
+    metrics_consumer.assert_empty()
+
+
 def test_metrics_full(mini_sentry, relay, relay_with_processing, metrics_consumer):
     metrics_consumer = metrics_consumer()
 
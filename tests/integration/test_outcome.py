@@ -7,6 +7,7 @@ from queue import Empty
 import requests
 import pytest
 import time
+from sentry_sdk.envelope import Envelope, Item, PayloadRef
 
 HOUR_MILLISEC = 1000 * 3600
 
@@ -478,3 +479,41 @@ def test_outcomes_rate_limit(
         outcomes_consumer.assert_rate_limited(reason_code, categories=[category])
     else:
         outcomes_consumer.assert_empty()
+
+
+def test_client_report_outcomes(mini_sentry, relay_with_processing, outcomes_consumer):
+    project_id = 42
+    mini_sentry.add_full_project_config(project_id)
+    relay = relay_with_processing()
+    outcomes_consumer = outcomes_consumer()
+
+    timestamp = datetime.now(tz=timezone.utc).replace(microsecond=0)
+    report_payload = {
+        "timestamp": timestamp.isoformat(),
+        "discarded_events": [
+            {"reason": "queue_overflow", "category": "error", "quantity": 42},
+            {"reason": "sample_rate", "category": "transaction", "quantity": 23},
+            {"reason": "foo" * 100, "category": "security", "quantity": 1},
+            {"reason": "queue_overflow", "category": "session", "quantity": 10 ** 9},
+        ],
+    }
+
+    envelope = Envelope()
+    envelope.add_item(Item(PayloadRef(json=report_payload), type="client_report"))
+    relay.send_envelope(project_id, envelope)
+
+    outcomes = outcomes_consumer.get_outcomes()
+    outcomes.sort(key=lambda o: o["category"])
+
+    # Unknown reasons are normalized and implausible quantities are dropped.
+    assert [(o["outcome"], o["reason"], o["quantity"]) for o in outcomes] == [
+        (5, "queue_overflow", 42),
+        (5, "sample_rate", 23),
+        (5, "unknown", 1),
+    ]
+
+    for outcome in outcomes:
+        assert outcome["project_id"] == project_id
+        assert outcome["org_id"] == 1
+        assert outcome.get("event_id") is None
+        assert outcome["timestamp"] == timestamp.strftime("%Y-%m-%dT%H:%M:%S.%fZ")
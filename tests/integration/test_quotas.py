@@ -0,0 +1,215 @@
+import time
+import uuid
+from datetime import datetime, timezone
+
+from sentry_sdk.envelope import Envelope
+
+
+def test_quota_usage(mini_sentry, relay):
+    relay = relay(
+        mini_sentry,
+        options={"limits": {"rate_limiter": "memory", "quota_endpoint": True}},
+    )
+
+    project_id = 42
+    project_config = mini_sentry.add_basic_project_config(project_id)
+    quota_id = "test_quota_usage_{}".format(uuid.uuid4().hex)
+    project_config["config"]["quotas"] = [
+        {
+            "id": quota_id,
+            "categories": ["error"],
+            "limit": 5,
+            "window": 3600,
+            "reasonCode": "get_lost",
+        }
+    ]
+
+    for _ in range(2):
+        relay.send_event(project_id)
+        mini_sentry.captured_events.get(timeout=1)
+
+    public_key = mini_sentry.get_dsn_public_key(project_id)
+    response = relay.get(f"/api/relay/quotas/{public_key}/")
+    response.raise_for_status()
+
+    body = response.json()
+    assert body["rateLimits"] == []
+
+    (quota,) = body["quotas"]
+    assert quota["id"] == quota_id
+    assert quota["usage"]["consumed"] == 2
+    assert quota["usage"]["remaining"] == 3
+    assert quota["usage"]["windowEnd"] - quota["usage"]["windowStart"] == 3600
+
+
+def test_quota_usage_unknown_project(mini_sentry, relay):
+    relay = relay(mini_sentry, options={"limits": {"quota_endpoint": True}})
+
+    project_id = 42
+    mini_sentry.add_basic_project_config(project_id)
+
+    # The project exists upstream, but the endpoint must not fetch project states.
+    public_key = mini_sentry.get_dsn_public_key(project_id)
+    response = relay.get(f"/api/relay/quotas/{public_key}/")
+    assert response.status_code == 404
+
+
+def test_quota_usage_disabled(mini_sentry, relay):
+    relay = relay(mini_sentry, options={"limits": {"rate_limiter": "memory"}})
+
+    project_id = 42
+    mini_sentry.add_basic_project_config(project_id)
+
+    relay.send_event(project_id)
+    mini_sentry.captured_events.get(timeout=1)
+
+    public_key = mini_sentry.get_dsn_public_key(project_id)
+    response = relay.get(f"/api/relay/quotas/{public_key}/")
+    assert response.status_code == 404
+
+
+def test_quota_remaining_header(mini_sentry, relay):
+    relay = relay(
+        mini_sentry,
+        options={"limits": {"rate_limiter": "memory", "quota_headers": True}},
+    )
+
+    project_id = 42
+    project_config = mini_sentry.add_basic_project_config(project_id)
+    project_config["config"]["quotas"] = [
+        {
+            "id": "test_quota_remaining_header_{}".format(uuid.uuid4().hex),
+            "categories": ["error"],
+            "limit": 5,
+            "window": 3600,
+            "reasonCode": "get_lost",
+        }
+    ]
+
+    for _ in range(2):
+        relay.send_event(project_id)
+        mini_sentry.captured_events.get(timeout=1)
+
+    def send_session():
+        # Sessions do not count against the error quota.
+        timestamp = datetime.now(tz=timezone.utc).isoformat()
+        envelope = Envelope()
+        envelope.add_session(
+            {
+                "sid": str(uuid.uuid4()),
+                "timestamp": timestamp,
+                "started": timestamp,
+                "attrs": {"release": "sentry-test@1.0.0"},
+            }
+        )
+
+        response = relay.post(
+            "/api/%s/envelope/" % project_id,
+            headers={
+                "Content-Type": "application/x-sentry-envelope",
+                "X-Sentry-Auth": relay.get_auth_header(project_id),
+            },
+            data=envelope.serialize(),
+        )
+        response.raise_for_status()
+        return response.headers.get("X-Sentry-Quota-Remaining")
+
+    # Quota consumption is cached and refreshed in the background at most once per
+    # second, so the header may lag behind until the next refresh.
+    for _ in range(5):
+        time.sleep(1.1)
+        header = send_session()
+        if header == "3:error:organization":
+            break
+
+    assert header == "3:error:organization"
+
+
+def test_quota_remaining_header_disabled(mini_sentry, relay):
+    relay = relay(mini_sentry, options={"limits": {"rate_limiter": "memory"}})
+
+    project_id = 42
+    project_config = mini_sentry.add_basic_project_config(project_id)
+    project_config["config"]["quotas"] = [
+        {
+            "id": "test_quota_remaining_header_{}".format(uuid.uuid4().hex),
+            "categories": ["error"],
+            "limit": 5,
+            "window": 3600,
+            "reasonCode": "get_lost",
+        }
+    ]
+
+    relay.send_event(project_id)
+    mini_sentry.captured_events.get(timeout=1)
+
+    response = relay.post(
+        "/api/%s/store/" % project_id,
+        headers={
+            "Content-Type": "application/octet-stream",
+            "X-Sentry-Auth": relay.get_auth_header(project_id),
+        },
+        json={"message": "Hello, World!"},
+    )
+    response.raise_for_status()
+
+    assert "X-Sentry-Quota-Remaining" not in response.headers
+
+
+def test_release_quota(mini_sentry, relay):
+    relay = relay(mini_sentry, options={"limits": {"rate_limiter": "memory"}})
+
+    project_id = 42
+    project_config = mini_sentry.add_basic_project_config(project_id)
+    project_config["config"]["quotas"] = [
+        {
+            "id": "test_release_quota_{}".format(uuid.uuid4().hex),
+            "categories": ["error"],
+            "scope": "release",
+            "limit": 1,
+            "window": 3600,
+            "reasonCode": "get_lost",
+        }
+    ]
+
+    relay.send_event(project_id, {"message": "Hello", "release": "a@1.0.0"})
+    event = mini_sentry.captured_events.get(timeout=1).get_event()
+    assert event["release"] == "a@1.0.0"
+
+    # The second event of the same release exceeds the quota.
+    relay.send_event(project_id, {"message": "Hello", "release": "a@1.0.0"})
+
+    # Other releases are counted separately.
+    relay.send_event(project_id, {"message": "Hello", "release": "b@1.0.0"})
+    event = mini_sentry.captured_events.get(timeout=1).get_event()
+    assert event["release"] == "b@1.0.0"
+
+    assert mini_sentry.captured_events.empty()
+
+
+def test_spike_protection(mini_sentry, relay):
+    relay = relay(mini_sentry, options={"limits": {"rate_limiter": "memory"}})
+
+    project_id = 42
+    project_config = mini_sentry.add_basic_project_config(project_id)
+    project_config["config"]["spikeProtection"] = {
+        "multiple": 2,
+        "minVolume": 1,
+        "window": 3600,
+    }
+
+    relay.send_event(project_id)
+    mini_sentry.captured_events.get(timeout=1)
+
+    # Without a baseline, only the minimum volume is accepted.
+    relay.send_event(project_id)
+    time.sleep(0.2)
+
+    public_key = mini_sentry.get_dsn_public_key(project_id)
+    response = relay.get(f"/api/relay/quotas/{public_key}/")
+    response.raise_for_status()
+
+    (rate_limit,) = response.json()["rateLimits"]
+    assert rate_limit["reasonCode"] == "spike_protection"
+
+    assert mini_sentry.captured_events.empty()
@@ -135,6 +135,84 @@ def test_security_report(mini_sentry, relay, test_case, json_fixture_provider):
     assert event == expected_evt
 
 
+def test_security_report_batch(mini_sentry, relay):
+    proj_id = 42
+    relay = relay(mini_sentry)
+    mini_sentry.add_full_project_config(proj_id)
+
+    reports = [
+        {
+            "age": 10,
+            "type": "csp-violation",
+            "url": "https://example.com/",
+            "user_agent": "Mozilla/5.0",
+            "body": {
+                "blockedURL": "https://evil.com/script.js",
+                "disposition": "enforce",
+                "documentURL": "https://example.com/",
+                "effectiveDirective": "script-src-elem",
+            },
+        },
+        {
+            "age": 20,
+            "type": "network-error",
+            "url": "https://example.com/about/",
+            "user_agent": "Mozilla/5.0",
+            "body": {
+                "elapsed_time": 823,
+                "method": "GET",
+                "phase": "dns",
+                "protocol": "http/1.1",
+                "sampling_fraction": 1.0,
+                "status_code": 0,
+                "type": "dns.name_not_resolved",
+            },
+        },
+        {
+            "age": 30,
+            "type": "crash",
+            "url": "https://example.com/",
+            "user_agent": "Mozilla/5.0",
+            "body": {"reason": "oom"},
+        },
+    ]
+
+    resp = relay.send_security_report(
+        project_id=proj_id,
+        content_type="application/reports+json",
+        payload=reports,
+        release="01d5c3165d9fbc5c8bdcf9550a1d6793a80fc02b",
+        environment="production",
+    )
+
+    assert resp.status_code == 200
+
+    # Every report in the batch creates a separate event. NEL and crash reports do not have a
+    # security event type in Sentry and are sent as default events.
+    events = []
+    for _ in reports:
+        envelope = mini_sentry.captured_events.get(timeout=1)
+        events.append(get_security_report(envelope) or envelope.get_event())
+    assert mini_sentry.captured_events.empty()
+
+    csp = next(event for event in events if event["type"] == "csp")
+    nel = next(event for event in events if "nel" in event)
+    crash = next(event for event in events if "crash" in event)
+
+    assert csp["type"] == "csp"
+    assert csp["logentry"]["formatted"] == "Blocked 'script' from 'evil.com'"
+    assert csp["release"] == "01d5c3165d9fbc5c8bdcf9550a1d6793a80fc02b"
+
+    assert nel["type"] == "default"
+    assert nel["nel"]["type"] == "dns.name_not_resolved"
+    assert nel["environment"] == "production"
+    assert csp["event_id"] != nel["event_id"]
+
+    assert crash["type"] == "default"
+    assert crash["crash"]["reason"] == "oom"
+    assert crash["logentry"]["formatted"] == "Page crashed on 'example.com' (oom)"
+
+
 def test_security_report_cors(mini_sentry, relay):
     """
     Test that we respond correctly to a CORS preflight request
@@ -0,0 +1,362 @@
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/getsentry/relay/master/artwork/relay-icon.png",
+    html_favicon_url = "https://raw.githubusercontent.com/getsentry/relay/master/artwork/relay-icon.png"
+)]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{format_err, Context, Result};
+use similar::TextDiff;
+use structopt::clap::AppSettings;
+use structopt::StructOpt;
+
+use relay_general::pii::{CompiledPiiConfig, PiiAttachmentsProcessor, PiiConfig, PiiProcessor};
+use relay_general::processor::{process_value, ProcessValue, ProcessingState, Processor};
+use relay_general::protocol::Event;
+use relay_general::types::{Annotated, Meta, ProcessingResult};
+
+/// Expected rule IDs in remarks, keyed by the path of the field.
+type RemarksMap = BTreeMap<String, BTreeSet<String>>;
+
+/// Tests a PII config against fixtures of events and attachments.
+///
+/// The fixtures directory contains inputs and the expected results after scrubbing:
+///
+///     events/NAME.json              An event payload.
+///     events/NAME.expected.json     The expected event payload, including `_meta`.
+///     events/NAME.remarks.json      The expected rule IDs in remarks per path, for example:
+///                                   {"logentry.formatted": ["@ip"]}
+///     attachments/NAME              An attachment.
+///     attachments/NAME.expected     The expected contents of the attachment.
+///
+/// Attachments are scrubbed based on their extension: `.dmp` files as minidumps, `.crash` files as
+/// Apple crash reports, `.json` files as JSON documents and all other files as plain attachments.
+///
+/// Every input requires at least one expectation. Run with `--update` to write the current
+/// results to the expectation files.
+#[derive(Debug, StructOpt)]
+#[structopt(verbatim_doc_comment, setting = AppSettings::ColoredHelp)]
+struct Cli {
+    /// Path to a PII config JSON file.
+    #[structopt(short, long, value_name = "PATH")]
+    config: PathBuf,
+
+    /// Path to the fixtures directory.
+    #[structopt(value_name = "PATH")]
+    fixtures: PathBuf,
+
+    /// Write the current results to the expectation files instead of comparing them.
+    #[structopt(long)]
+    update: bool,
+}
+
+/// Collects the rule IDs of all remarks in a processed value.
+#[derive(Default)]
+struct RemarksCollector {
+    remarks: RemarksMap,
+}
+
+impl Processor for RemarksCollector {
+    fn before_process<T: ProcessValue>(
+        &mut self,
+        _value: Option<&T>,
+        meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        for remark in meta.iter_remarks() {
+            self.remarks
+                .entry(state.path().to_string())
+                .or_default()
+                .insert(remark.rule_id.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the path of a file with the given suffix appended to the file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    path.into()
+}
+
+/// Lists all files in a fixtures subdirectory, skipping files that match `is_expectation`.
+fn list_inputs(dir: &Path, is_expectation: impl Fn(&str) -> bool) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut inputs = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        if path.is_file() && !is_expectation(file_name) {
+            inputs.push(path);
+        }
+    }
+
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// Renders a line diff between the expected and actual text.
+fn diff_text(expected: &str, actual: &str) -> String {
+    TextDiff::from_lines(expected, actual)
+        .unified_diff()
+        .header("expected", "actual")
+        .to_string()
+}
+
+/// Compares pretty-printed JSON and returns a diff if the values differ.
+fn diff_json(expected: &serde_json::Value, actual: &serde_json::Value) -> Result<Option<String>> {
+    if expected == actual {
+        return Ok(None);
+    }
+
+    Ok(Some(diff_text(
+        &serde_json::to_string_pretty(expected)?,
+        &serde_json::to_string_pretty(actual)?,
+    )))
+}
+
+/// Compares the contents of attachments and returns a description of the differences.
+fn diff_bytes(expected: &[u8], actual: &[u8]) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+
+    if let (Ok(expected), Ok(actual)) = (std::str::from_utf8(expected), std::str::from_utf8(actual))
+    {
+        return Some(diff_text(expected, actual));
+    }
+
+    let differing = expected
+        .iter()
+        .zip(actual)
+        .filter(|(expected, actual)| expected != actual)
+        .count();
+    let offset = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+
+    Some(format!(
+        "binary contents differ: {} bytes differ starting at offset {:#x}, expected {} bytes, got {} bytes\n",
+        differing,
+        offset,
+        expected.len(),
+        actual.len()
+    ))
+}
+
+impl Cli {
+    fn load_pii_config(&self) -> Result<PiiConfig> {
+        let json = fs::read_to_string(&self.config).with_context(|| "failed to read PII config")?;
+        let config = PiiConfig::from_json(&json).with_context(|| "failed to parse PII config")?;
+        Ok(config)
+    }
+
+    /// Reads and parses a JSON expectation file, if it exists.
+    fn load_expectation(&self, path: &Path) -> Result<Option<serde_json::Value>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let value = serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        Ok(Some(value))
+    }
+
+    fn write_expectation(&self, path: &Path, data: &[u8]) -> Result<()> {
+        fs::write(path, data).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Scrubs an event and compares it to the expectations.
+    ///
+    /// Returns a list of failures, which is empty if the event matches all expectations.
+    fn test_event(&self, config: &CompiledPiiConfig, path: &Path) -> Result<Vec<String>> {
+        let json = fs::read_to_string(path).with_context(|| "failed to read event")?;
+        let mut event =
+            Annotated::<Event>::from_json(&json).with_context(|| "failed to parse event")?;
+
+        let mut processor = PiiProcessor::new(config);
+        process_value(&mut event, &mut processor, ProcessingState::root())
+            .map_err(|e| format_err!("{}", e))?;
+
+        let mut collector = RemarksCollector::default();
+        process_value(&mut event, &mut collector, ProcessingState::root())
+            .map_err(|e| format_err!("{}", e))?;
+
+        let output: serde_json::Value = serde_json::from_str(&event.to_json()?)?;
+        let remarks = serde_json::to_value(&collector.remarks)?;
+
+        let expectations = [
+            (path.with_extension("expected.json"), output),
+            (path.with_extension("remarks.json"), remarks),
+        ];
+
+        if self.update {
+            for (expected_path, actual) in &expectations {
+                let json = serde_json::to_string_pretty(actual)? + "\n";
+                self.write_expectation(expected_path, json.as_bytes())?;
+            }
+            return Ok(Vec::new());
+        }
+
+        let mut failures = Vec::new();
+        let mut checked = false;
+
+        for (expected_path, actual) in &expectations {
+            if let Some(expected) = self.load_expectation(expected_path)? {
+                checked = true;
+                if let Some(diff) = diff_json(&expected, actual)? {
+                    failures.push(format!("{}:\n{}", expected_path.display(), diff));
+                }
+            }
+        }
+
+        if !checked {
+            failures.push("no expectation found".to_owned());
+        }
+
+        Ok(failures)
+    }
+
+    /// Scrubs an attachment and compares it to the expectation.
+    ///
+    /// Returns a list of failures, which is empty if the attachment matches the expectation.
+    fn test_attachment(&self, config: &CompiledPiiConfig, path: &Path) -> Result<Vec<String>> {
+        let mut data = fs::read(path).with_context(|| "failed to read attachment")?;
+        let filename = path
+            .file_name()
+            .and_then(|os_str| os_str.to_str())
+            .unwrap_or_default();
+
+        let processor = PiiAttachmentsProcessor::new(config);
+        match path.extension().and_then(|os_str| os_str.to_str()) {
+            Some("dmp") => {
+                processor
+                    .scrub_minidump(filename, &mut data)
+                    .map_err(|e| format_err!("{}", e))?; // does not implement std::error::Error
+            }
+            Some("crash") => {
+                processor.scrub_apple_crash_report(filename, &mut data);
+            }
+            Some("json") => {
+                processor
+                    .scrub_json_attachment(filename, &mut data)
+                    .with_context(|| "failed to parse JSON attachment")?;
+            }
+            _ => {
+                processor.scrub_attachment(filename, &mut data);
+            }
+        }
+
+        let expected_path = with_suffix(path, ".expected");
+        if self.update {
+            self.write_expectation(&expected_path, &data)?;
+            return Ok(Vec::new());
+        }
+
+        if !expected_path.exists() {
+            return Ok(vec!["no expectation found".to_owned()]);
+        }
+
+        let expected = fs::read(&expected_path)
+            .with_context(|| format!("failed to read {}", expected_path.display()))?;
+
+        Ok(diff_bytes(&expected, &data)
+            .map(|diff| format!("{}:\n{}", expected_path.display(), diff))
+            .into_iter()
+            .collect())
+    }
+
+    pub fn run(self) -> Result<()> {
+        let config = self.load_pii_config()?;
+        let compiled = config.compiled();
+
+        let events = list_inputs(&self.fixtures.join("events"), |name| {
+            !name.ends_with(".json")
+                || name.ends_with(".expected.json")
+                || name.ends_with(".remarks.json")
+        })?;
+        let attachments = list_inputs(&self.fixtures.join("attachments"), |name| {
+            name.ends_with(".expected")
+        })?;
+
+        if events.is_empty() && attachments.is_empty() {
+            return Err(format_err!(
+                "no fixtures found in {}",
+                self.fixtures.display()
+            ));
+        }
+
+        let mut passed = 0;
+        let mut failed = 0;
+
+        let cases = events
+            .iter()
+            .map(|path| (path, self.test_event(&compiled, path)))
+            .chain(
+                attachments
+                    .iter()
+                    .map(|path| (path, self.test_attachment(&compiled, path))),
+            );
+
+        for (path, result) in cases {
+            let failures = result.unwrap_or_else(|error| vec![format!("error: {:#}", error)]);
+
+            if failures.is_empty() {
+                let status = if self.update { "updated" } else { "ok" };
+                println!("test {} ... {}", path.display(), status);
+                passed += 1;
+            } else {
+                println!("test {} ... FAILED", path.display());
+                for failure in failures {
+                    println!("{}", failure);
+                }
+                failed += 1;
+            }
+        }
+
+        println!();
+        println!("{} passed; {} failed", passed, failed);
+
+        if failed > 0 {
+            return Err(format_err!("{} fixtures failed", failed));
+        }
+
+        Ok(())
+    }
+}
+
+fn print_error(error: &anyhow::Error) {
+    eprintln!("Error: {}", error);
+
+    let mut cause = error.source();
+    while let Some(ref e) = cause {
+        eprintln!("  caused by: {}", e);
+        cause = e.source();
+    }
+}
+
+#[paw::main]
+fn main(cli: Cli) {
+    match cli.run() {
+        Ok(()) => (),
+        Err(error) => {
+            print_error(&error);
+            std::process::exit(1);
+        }
+    }
+}